@@ -1,14 +1,179 @@
 //! An error report with annotations
 //!
 //! The [`AnnotatedError`] type allows to construct error with annotations on it.
+//! Each report has a [`Severity`], which tells whether it is an error or a
+//! warning.
 
-use std::iter;
+use alloc::{
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{convert::Infallible, iter};
 
 use crate::{
     reporter::Annotation as ReportedAnnotation,
     span::{Position, Span},
 };
 
+/// The severity of a report.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum Severity {
+    /// The report describes an error. This is the default severity.
+    #[default]
+    Error,
+    /// The report describes a warning.
+    Warning,
+}
+
+impl Severity {
+    /// Returns the name of the severity, in lowercase.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::error::Severity;
+    ///
+    /// assert_eq!(Severity::Error.name(), "error");
+    /// assert_eq!(Severity::Warning.name(), "warning");
+    /// ```
+    pub const fn name(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+/// Returns the most severe severity among `errs`, or `None` if `errs` is
+/// empty.
+///
+/// Errors are more severe than warnings.
+///
+/// # Example
+///
+/// ```rust
+/// use lisbeth_error::{
+///     error::{worst_severity, AnnotatedError, Severity},
+///     span::SpannedStr,
+/// };
+///
+/// let span = SpannedStr::input_file("foo").span();
+/// let errs = [
+///     AnnotatedError::new(span, "Unused foo").with_severity(Severity::Warning),
+///     AnnotatedError::new(span, "Unknown foo"),
+/// ];
+///
+/// assert_eq!(worst_severity(&errs), Some(Severity::Error));
+/// assert_eq!(worst_severity(&[]), None);
+/// ```
+pub fn worst_severity(errs: &[AnnotatedError]) -> Option<Severity> {
+    if errs.is_empty() {
+        None
+    } else if errs.iter().any(|err| err.severity == Severity::Error) {
+        Some(Severity::Error)
+    } else {
+        Some(Severity::Warning)
+    }
+}
+
+/// Returns the exit code a program should return after reporting `errs`.
+///
+/// The exit code is `1` if `errs` contains at least one error, and `0`
+/// otherwise. Warnings alone do not make a program fail.
+///
+/// # Example
+///
+/// ```rust
+/// use lisbeth_error::{
+///     error::{exit_code, AnnotatedError, Severity},
+///     span::SpannedStr,
+/// };
+///
+/// let span = SpannedStr::input_file("foo").span();
+/// let warning = AnnotatedError::new(span, "Unused foo").with_severity(Severity::Warning);
+///
+/// assert_eq!(exit_code(&[warning]), 0);
+/// ```
+pub fn exit_code(errs: &[AnnotatedError]) -> i32 {
+    match worst_severity(errs) {
+        Some(Severity::Error) => 1,
+        Some(Severity::Warning) | None => 0,
+    }
+}
+
+/// Sets the severity of every report of `errs` matching `pred` to `new_level`.
+///
+/// This allows a post-pass to reclassify reports, for instance by reading a
+/// rule identifier stored in their metadata.
+///
+/// # Example
+///
+/// ```rust
+/// use lisbeth_error::{
+///     error::{downgrade_matching, AnnotatedError, Severity},
+///     span::SpannedStr,
+/// };
+///
+/// let span = SpannedStr::input_file("foo").span();
+/// let mut errs = [
+///     AnnotatedError::new(span, "Unused foo").with_meta("rule", "unused"),
+///     AnnotatedError::new(span, "Unknown foo"),
+/// ];
+///
+/// downgrade_matching(&mut errs, |err| err.meta("rule") == Some("unused"), Severity::Warning);
+///
+/// assert_eq!(errs[0].severity(), Severity::Warning);
+/// assert_eq!(errs[1].severity(), Severity::Error);
+/// ```
+pub fn downgrade_matching<P>(errs: &mut [AnnotatedError], mut pred: P, new_level: Severity)
+where
+    P: FnMut(&AnnotatedError) -> bool,
+{
+    errs.iter_mut()
+        .filter(|err| pred(err))
+        .for_each(|err| err.severity = new_level);
+}
+
+/// Sorts annotations by the position of their span.
+///
+/// Annotations are ordered by the start of their span, then by its end, then
+/// by their text, so that the order does not depend on the initial order of
+/// `anns`. This allows to assemble annotations in any order before adding them
+/// to a report with [`AnnotatedError::with_annotations`].
+///
+/// As spans are compared by position, the consistency note of the [`span`]
+/// module documentation applies.
+///
+/// [`span`]: crate::span
+///
+/// # Example
+///
+/// ```rust
+/// use lisbeth_error::{error::sort_annotations, span::SpannedStr};
+///
+/// let (foo, bar) = SpannedStr::input_file("foobar").split_at(3);
+/// let mut anns = vec![
+///     (bar.span(), "second".to_string()),
+///     (foo.span(), "first".to_string()),
+/// ];
+///
+/// sort_annotations(&mut anns);
+///
+/// assert_eq!(anns[0].1, "first");
+/// ```
+pub fn sort_annotations(anns: &mut [(Span, String)]) {
+    anns.sort_unstable_by(|(left_span, left_text), (right_span, right_text)| {
+        (left_span.start(), left_span.end(), left_text).cmp(&(
+            right_span.start(),
+            right_span.end(),
+            right_text,
+        ))
+    });
+}
+
 /// An error report with annotations.
 ///
 /// This error report is created with the precise span at which the error occurs
@@ -38,7 +203,20 @@ use crate::{
 pub struct AnnotatedError {
     pub(crate) span: Span,
     pub(crate) msg: String,
+    pub(crate) severity: Severity,
     annotations: Vec<Annotation>,
+    pub(crate) related: Vec<AnnotatedError>,
+    // Each suggestion is a report whose message describes an edit of its
+    // span.
+    pub(crate) suggestions: Vec<AnnotatedError>,
+    // Messages displayed after the code snippet. Notes give context, helps
+    // give advice.
+    pub(crate) notes: Vec<String>,
+    pub(crate) helps: Vec<String>,
+    // Annotations located in another input, along with the path of that
+    // input. Each annotation is a report labelled with its message.
+    pub(crate) foreign: Vec<(String, AnnotatedError)>,
+    metadata: BTreeMap<String, String>,
 }
 
 impl AnnotatedError {
@@ -54,11 +232,107 @@ impl AnnotatedError {
         let msg = msg.to_string();
         AnnotatedError {
             annotations: Vec::new(),
+            related: Vec::new(),
+            suggestions: Vec::new(),
+            notes: Vec::new(),
+            helps: Vec::new(),
+            foreign: Vec::new(),
+            metadata: BTreeMap::new(),
+            severity: Severity::default(),
             span,
             msg,
         }
     }
 
+    /// Constructs a new report, building its message with `msg`.
+    ///
+    /// This is equivalent to [`new`], except that the message is produced by
+    /// calling `msg` exactly once. This allows message construction to be
+    /// written next to the span, while keeping it in a closure that can be
+    /// passed around.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, span::SpannedStr};
+    ///
+    /// let file = SpannedStr::input_file("foo");
+    ///
+    /// let report = AnnotatedError::new_with(file.span(), || {
+    ///     format!("Unexpected `{}`", file.content())
+    /// });
+    ///
+    /// assert_eq!(report, AnnotatedError::new(file.span(), "Unexpected `foo`"));
+    /// ```
+    ///
+    /// [`new`]: AnnotatedError::new
+    pub fn new_with<F, Msg>(span: Span, msg: F) -> AnnotatedError
+    where
+        F: FnOnce() -> Msg,
+        Msg: ToString,
+    {
+        AnnotatedError::new(span, msg())
+    }
+
+    /// Constructs a new report with a primary label.
+    ///
+    /// This is equivalent to calling [`new`] with `span` and `msg`, and then
+    /// adding an annotation at `span` with `label`. This allows simple errors
+    /// to be reported with an underline in one call.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, span::SpannedStr};
+    ///
+    /// let file = SpannedStr::input_file("foo bar");
+    /// let bar = file.split_at(4).1;
+    ///
+    /// let report = AnnotatedError::new_with_label(bar.span(), "Unexpected word", "expected `baz`");
+    ///
+    /// assert_eq!(report.annotation_count(), 1);
+    /// ```
+    ///
+    /// [`new`]: AnnotatedError::new
+    pub fn new_with_label<Msg, Label>(span: Span, msg: Msg, label: Label) -> AnnotatedError
+    where
+        Msg: ToString,
+        Label: ToString,
+    {
+        AnnotatedError::new(span, msg).with_annotation(span, label)
+    }
+
+    /// Sets the severity of the report.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{
+    ///     error::{AnnotatedError, Severity},
+    ///     span::SpannedStr,
+    /// };
+    ///
+    /// let file = SpannedStr::input_file("foo");
+    /// let report = AnnotatedError::new(file.span(), "Unused variable")
+    ///     .with_severity(Severity::Warning);
+    ///
+    /// assert_eq!(report.severity(), Severity::Warning);
+    /// ```
+    pub fn with_severity(mut self, severity: Severity) -> AnnotatedError {
+        self.severity = severity;
+        self
+    }
+
+    /// Sets the severity of the report.
+    ///
+    /// This is the same as [`with_severity`], named after the levels lint
+    /// configurations use to reclassify reports.
+    ///
+    /// [`with_severity`]: AnnotatedError::with_severity
+    pub fn with_level(self, level: Severity) -> AnnotatedError {
+        self.with_severity(level)
+    }
+
     /// Adds a new annotation at a given span to the report.
     pub fn with_annotation<Msg>(mut self, span: Span, msg: Msg) -> AnnotatedError
     where
@@ -70,11 +344,526 @@ impl AnnotatedError {
         self
     }
 
+    /// Adds a new annotation at a given span to the report, returning the
+    /// report in a `Result`.
+    ///
+    /// Every span can be annotated, including the ones that cross a line
+    /// boundary, so this method always succeeds. It is equivalent to
+    /// [`with_annotation`], and allows to add annotations in code that
+    /// propagates errors with `?`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, span::SpannedStr};
+    ///
+    /// let file = SpannedStr::input_file("foo\nbar");
+    ///
+    /// let report = AnnotatedError::new(file.span(), "Unexpected words")
+    ///     .try_with_annotation(file.span(), "two lines");
+    ///
+    /// assert!(report.is_ok());
+    /// ```
+    ///
+    /// [`with_annotation`]: AnnotatedError::with_annotation
+    pub fn try_with_annotation<Msg>(
+        self,
+        span: Span,
+        msg: Msg,
+    ) -> Result<AnnotatedError, Infallible>
+    where
+        Msg: ToString,
+    {
+        Ok(self.with_annotation(span, msg))
+    }
+
+    /// Adds multiple annotations to the report.
+    ///
+    /// This is equivalent to calling [`with_annotation`] for each element of
+    /// `anns`, in order.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, span::SpannedStr};
+    ///
+    /// let file = SpannedStr::input_file("foo bar");
+    /// let (foo, bar) = file.split_at(3);
+    ///
+    /// let annotations = vec![
+    ///     (foo.span(), "first word".to_string()),
+    ///     (bar.span(), "second word".to_string()),
+    /// ];
+    ///
+    /// let report = AnnotatedError::new(foo.span(), "Unexpected words")
+    ///     .with_annotations(annotations);
+    ///
+    /// assert_eq!(report.annotation_count(), 2);
+    /// ```
+    ///
+    /// [`with_annotation`]: AnnotatedError::with_annotation
+    pub fn with_annotations<I, Msg>(self, anns: I) -> AnnotatedError
+    where
+        I: IntoIterator<Item = (Span, Msg)>,
+        Msg: ToString,
+    {
+        anns.into_iter()
+            .fold(self, |err, (span, msg)| err.with_annotation(span, msg))
+    }
+
+    /// Adds a related error to the report.
+    ///
+    /// Related errors describe other failures that help to understand the
+    /// report, such as an earlier definition that conflicts with the current
+    /// one. They are rendered after the report, with their own header and code
+    /// snippet.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, span::SpannedStr};
+    ///
+    /// let file = SpannedStr::input_file("let a; let a;");
+    /// let first = file.find("a").unwrap();
+    /// let second = file.split_at(7).1.find("a").unwrap();
+    ///
+    /// let report = AnnotatedError::new(second.span(), "`a` is defined twice")
+    ///     .with_related(AnnotatedError::new(first.span(), "`a` is first defined here"));
+    /// ```
+    pub fn with_related(mut self, other: AnnotatedError) -> AnnotatedError {
+        self.related.push(other);
+        self
+    }
+
+    /// Adds a note to the report.
+    ///
+    /// Notes give factual context about the error. They are not attached to
+    /// a span, and are displayed after the code snippet as `note:` lines.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, reporter::ErrorReporter};
+    ///
+    /// let reporter = ErrorReporter::non_file_input("let a = b;".to_string());
+    /// let b = reporter.spanned_str().find("b").unwrap();
+    ///
+    /// let report = AnnotatedError::new(b.span(), "Unknown variable `b`")
+    ///     .with_note("variables must be defined before being used");
+    ///
+    /// let formatted = reporter.format_error(&report).to_string();
+    /// assert!(formatted.ends_with("= note: variables must be defined before being used\n"));
+    /// ```
+    pub fn with_note<Msg>(mut self, msg: Msg) -> AnnotatedError
+    where
+        Msg: ToString,
+    {
+        self.notes.push(msg.to_string());
+        self
+    }
+
+    /// Adds a help message to the report.
+    ///
+    /// Help messages give actionable advice to fix the error. They are not
+    /// attached to a span, and are displayed as `help:` lines after the
+    /// notes added with [`with_note`].
+    ///
+    /// [`with_note`]: AnnotatedError::with_note
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, reporter::ErrorReporter};
+    ///
+    /// let reporter = ErrorReporter::non_file_input("let a = b;".to_string());
+    /// let b = reporter.spanned_str().find("b").unwrap();
+    ///
+    /// let report = AnnotatedError::new(b.span(), "Unknown variable `b`")
+    ///     .with_help("define `b` before this statement");
+    ///
+    /// let formatted = reporter.format_error(&report).to_string();
+    /// assert!(formatted.ends_with("= help: define `b` before this statement\n"));
+    /// ```
+    pub fn with_help<Msg>(mut self, msg: Msg) -> AnnotatedError
+    where
+        Msg: ToString,
+    {
+        self.helps.push(msg.to_string());
+        self
+    }
+
+    /// Adds an annotation located in another input.
+    ///
+    /// `source` is the path of the input that contains `span`. The annotation
+    /// is displayed in a snippet of its own input when the report is formatted
+    /// with [`ErrorReporter::format_error_with_sources`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, span::SpannedStr};
+    ///
+    /// let main = SpannedStr::input_file("let x: u32 = f();");
+    /// let lib = SpannedStr::input_file("fn f() -> String");
+    ///
+    /// let report = AnnotatedError::new(main.find("f()").unwrap().span(), "Mismatched types")
+    ///     .with_annotation_in("lib.rs", lib.find("String").unwrap().span(), "expected because of this");
+    /// ```
+    ///
+    /// [`ErrorReporter::format_error_with_sources`]: crate::reporter::ErrorReporter::format_error_with_sources
+    pub fn with_annotation_in<Src, Msg>(
+        mut self,
+        source: Src,
+        span: Span,
+        msg: Msg,
+    ) -> AnnotatedError
+    where
+        Src: ToString,
+        Msg: ToString,
+    {
+        self.foreign
+            .push((source.to_string(), AnnotatedError::new(span, msg)));
+        self
+    }
+
+    /// Attaches a metadata entry to the report.
+    ///
+    /// Metadata is not rendered. It allows downstream tools to filter or
+    /// categorize reports, for instance by rule identifier. If `key` is
+    /// already set, its value is replaced.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, span::SpannedStr};
+    ///
+    /// let file = SpannedStr::input_file("foo");
+    /// let report = AnnotatedError::new(file.span(), "Unused variable")
+    ///     .with_meta("rule", "unused-variable");
+    ///
+    /// assert_eq!(report.meta("rule"), Some("unused-variable"));
+    /// ```
+    pub fn with_meta<Key, Value>(mut self, key: Key, value: Value) -> AnnotatedError
+    where
+        Key: ToString,
+        Value: ToString,
+    {
+        self.metadata.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Adds a suggestion to remove the text covered by `span`.
+    ///
+    /// The suggestion is displayed after the error, as a `help: remove this`
+    /// message above the code snippet, in which `span` is underlined with
+    /// dashes.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, span::SpannedStr};
+    ///
+    /// let file = SpannedStr::input_file("let a = 42;;");
+    /// let semi = file.split_at(11).1;
+    ///
+    /// let report = AnnotatedError::new(semi.span(), "Unnecessary semicolon")
+    ///     .with_removal_suggestion(semi.span());
+    /// ```
+    pub fn with_removal_suggestion(self, span: Span) -> AnnotatedError {
+        self.with_suggestion(span, "remove this")
+    }
+
+    /// Adds a suggestion described by `msg` at a given span.
+    ///
+    /// The suggestion is displayed after the error, as a `help: <msg>`
+    /// message above the code snippet, in which `span` is underlined with
+    /// dashes. An empty span is marked with a caret.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, span::SpannedStr};
+    ///
+    /// let file = SpannedStr::input_file("let a = fo;");
+    /// let fo = file.find("fo").unwrap();
+    ///
+    /// let report = AnnotatedError::new(fo.span(), "Unknown variable `fo`")
+    ///     .with_suggestion(fo.span(), "replace with `foo`");
+    /// ```
+    pub fn with_suggestion<Msg>(mut self, span: Span, msg: Msg) -> AnnotatedError
+    where
+        Msg: ToString,
+    {
+        self.suggestions.push(AnnotatedError::new(span, msg));
+        self
+    }
+
+    /// Adds a suggestion to insert `text` at `pos`.
+    ///
+    /// The suggestion is displayed after the error, as a ``help: insert `text`
+    /// here`` message above the code snippet, in which a caret marks the
+    /// insertion point.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, span::SpannedStr};
+    ///
+    /// let file = SpannedStr::input_file("let a = 42");
+    /// let end = file.span().end();
+    ///
+    /// let report = AnnotatedError::new(file.span(), "Missing semicolon")
+    ///     .with_insertion(end, ";");
+    /// ```
+    pub fn with_insertion<Text>(self, pos: Position, text: Text) -> AnnotatedError
+    where
+        Text: ToString,
+    {
+        let msg = format!("insert `{}` here", text.to_string());
+        self.with_suggestion(Span::new(pos, pos), msg)
+    }
+
+    /// Merges two reports together.
+    ///
+    /// The annotations, the related errors, the suggestions, the notes, the
+    /// help messages and the annotations located in other inputs of `other`
+    /// are appended to the ones of `self`. The span and the general message of
+    /// `self` are kept, while the span and the general message of `other` are
+    /// discarded. As such, `other` should describe the same failure as `self`.
+    ///
+    /// The metadata of `other` is added to the one of `self`. When a key is
+    /// set in both reports, the value of `self` is kept.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, span::SpannedStr};
+    ///
+    /// let file = SpannedStr::input_file("foo bar");
+    /// let (foo, bar) = file.split_at(3);
+    ///
+    /// let left = AnnotatedError::new(foo.span(), "Expected number")
+    ///     .with_annotation(foo.span(), "Expected number here");
+    /// let right = AnnotatedError::new(foo.span(), "Expected string")
+    ///     .with_annotation(bar.span(), "Expected string here");
+    ///
+    /// let report = left.merge(right);
+    ///
+    /// assert_eq!(report.annotation_count(), 2);
+    /// ```
+    pub fn merge(mut self, other: AnnotatedError) -> AnnotatedError {
+        self.annotations.extend(other.annotations);
+        self.related.extend(other.related);
+        self.suggestions.extend(other.suggestions);
+        self.notes.extend(other.notes);
+        self.helps.extend(other.helps);
+        self.foreign.extend(other.foreign);
+
+        for (key, value) in other.metadata {
+            self.metadata.entry(key).or_insert(value);
+        }
+
+        self
+    }
+
+    /// Applies `f` to the span of the report, to the span of each of its
+    /// annotations, and to the spans of its related errors.
+    ///
+    /// This allows to relocate an error that has been reported on a sub-slice
+    /// of the input, such as an included file, into its parent input. The
+    /// annotations located in other inputs are left untouched.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, span::{Span, SpannedStr}};
+    ///
+    /// let parent = SpannedStr::input_file("let a = foo;");
+    /// let child = SpannedStr::input_file("foo");
+    ///
+    /// // Maps a span of child to the corresponding span of parent.
+    /// let relocate = |span: Span| {
+    ///     let (start, end) = (span.start().offset() as usize, span.end().offset() as usize);
+    ///     parent.split_at(start + 8).1.split_at(end - start).0.span()
+    /// };
+    ///
+    /// let report = AnnotatedError::new(child.span(), "Unknown identifier").map_spans(relocate);
+    ///
+    /// assert_eq!(report.span(), parent.find("foo").unwrap().span());
+    /// ```
+    pub fn map_spans<F>(self, f: F) -> AnnotatedError
+    where
+        F: Fn(Span) -> Span,
+    {
+        self.map_spans_dyn(&f)
+    }
+
+    // Related errors are mapped recursively, which can not be done with a
+    // generic function.
+    fn map_spans_dyn(mut self, f: &dyn Fn(Span) -> Span) -> AnnotatedError {
+        self.span = f(self.span);
+        self.annotations
+            .iter_mut()
+            .for_each(|ann| ann.span = f(ann.span));
+        self.related = self
+            .related
+            .into_iter()
+            .map(|related| related.map_spans_dyn(f))
+            .collect();
+        self.suggestions = self
+            .suggestions
+            .into_iter()
+            .map(|suggestion| suggestion.map_spans_dyn(f))
+            .collect();
+
+        self
+    }
+
+    /// Merges the annotations that have the same text and whose spans touch
+    /// or overlap on a single line.
+    ///
+    /// The merged annotation covers the union of the spans, so that it is
+    /// rendered with a single underline. It takes the place of the first
+    /// annotation it is made of. Annotations that are not contiguous, or that
+    /// span more than one line, are left untouched.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, span::SpannedStr};
+    ///
+    /// let file = SpannedStr::input_file("a + b");
+    /// let (a, tail) = file.split_at(1);
+    /// let (plus, b) = tail.split_at(3);
+    ///
+    /// let report = AnnotatedError::new(file.span(), "Unsupported operation")
+    ///     .with_annotation(a.span(), "here")
+    ///     .with_annotation(plus.span(), "here")
+    ///     .with_annotation(b.span(), "here")
+    ///     .merge_identical_annotations();
+    ///
+    /// assert_eq!(report.annotation_count(), 1);
+    /// assert_eq!(report.annotations().next(), Some((file.span(), "here")));
+    /// ```
+    pub fn merge_identical_annotations(mut self) -> AnnotatedError {
+        let mut merged = Vec::<Annotation>::with_capacity(self.annotations.len());
+
+        for mut ann in self.annotations {
+            let mut idx = merged.len();
+
+            // Merging two annotations may make the result contiguous with
+            // another one, so the lookup is repeated until nothing matches.
+            while let Some(other_idx) = merged.iter().position(|other| other.is_contiguous(&ann)) {
+                let other = merged.remove(other_idx);
+                ann.span = other.span.merge(ann.span);
+                idx = usize::min(idx, other_idx);
+            }
+
+            merged.insert(usize::min(idx, merged.len()), ann);
+        }
+
+        self.annotations = merged;
+        self
+    }
+
+    /// Returns the severity of the report.
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    /// Returns the general message of the report.
+    pub fn message(&self) -> &str {
+        &self.msg
+    }
+
+    /// Returns the metadata value associated to `key`, if any.
+    pub fn meta(&self, key: &str) -> Option<&str> {
+        self.metadata.get(key).map(String::as_str)
+    }
+
     /// Returns the span at which the error is encountered.
     pub fn span(&self) -> Span {
         self.span
     }
 
+    /// Returns the position at which the error starts.
+    ///
+    /// This is the start of the span passed when the report was created.
+    pub fn primary_position(&self) -> Position {
+        self.span.start()
+    }
+
+    /// Returns the zero-indexed line and column at which the error starts.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, span::SpannedStr};
+    ///
+    /// let input = SpannedStr::input_file("let a = 42;\nlet b = a +;");
+    /// let plus = input.find("+").unwrap();
+    /// let report = AnnotatedError::new(plus.span(), "Expected expression");
+    ///
+    /// assert_eq!(report.primary_line_col(), (1, 10));
+    /// ```
+    pub fn primary_line_col(&self) -> (u32, u32) {
+        let start = self.primary_position();
+        (start.line(), start.col())
+    }
+
+    /// Returns an iterator over the annotations of the report, in the order
+    /// in which they were added.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, span::SpannedStr};
+    ///
+    /// let file = SpannedStr::input_file("foo bar");
+    /// let (foo, bar) = file.split_at(3);
+    ///
+    /// let report = AnnotatedError::new(foo.span(), "Unexpected words")
+    ///     .with_annotation(foo.span(), "first word")
+    ///     .with_annotation(bar.span(), "second word");
+    ///
+    /// let mut annotations = report.annotations();
+    /// assert_eq!(annotations.next(), Some((foo.span(), "first word")));
+    /// assert_eq!(annotations.next(), Some((bar.span(), "second word")));
+    /// assert_eq!(annotations.next(), None);
+    /// ```
+    pub fn annotations(&self) -> impl Iterator<Item = (Span, &str)> + '_ {
+        self.annotations
+            .iter()
+            .map(|a| (a.span, a.content.as_str()))
+    }
+
+    /// Returns the number of annotations attached to the report.
+    pub fn annotation_count(&self) -> usize {
+        self.annotations.len()
+    }
+
+    /// Returns whether the report has no annotation.
+    ///
+    /// Such reports are rendered with an underline below the span at which
+    /// the error is encountered.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, span::SpannedStr};
+    ///
+    /// let file = SpannedStr::input_file("foo");
+    ///
+    /// let report = AnnotatedError::new(file.span(), "Unexpected foo");
+    /// assert!(report.is_empty());
+    ///
+    /// let report = report.with_annotation(file.span(), "here");
+    /// assert!(!report.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.annotations.is_empty()
+    }
+
     fn all_spans(&self) -> impl Iterator<Item = Span> + '_ {
         self.annotations
             .iter()
@@ -91,7 +880,18 @@ impl AnnotatedError {
         (min, max)
     }
 
-    pub(crate) fn error_matrix(&self) -> Vec<Vec<ReportedAnnotation>> {
+    // Returns the annotations of each line covered by the report, sorted by
+    // column. An annotation that spans multiple lines is split into one
+    // annotation per line: its text is attached to the last one, and the
+    // other ones are only underlined. line_length returns the number of
+    // characters of a given line, without its line return.
+    //
+    // When the report has no annotation, the primary span is annotated with
+    // the general message.
+    pub(crate) fn error_matrix<F>(&self, line_length: F) -> Vec<Vec<ReportedAnnotation<'_>>>
+    where
+        F: Fn(u32) -> usize,
+    {
         let (start_pos, end_pos) = self.bounds();
 
         let (first_line_number, last_line_number) =
@@ -102,25 +902,40 @@ impl AnnotatedError {
             .map(|_| Vec::new())
             .collect::<Vec<_>>();
 
-        for annotation in self.annotations.iter() {
-            let line_idx = annotation.span.start().line() as usize - first_line_number;
+        let annotations = if self.annotations.is_empty() {
+            vec![(self.span, self.msg.as_str())]
+        } else {
+            self.annotations
+                .iter()
+                .map(|a| (a.span, a.content.as_str()))
+                .collect()
+        };
 
-            assert_eq!(
-                annotation.span.start().line(),
-                annotation.span.end().line(),
-                "Multiline spans are not supported",
-            );
+        for (span, text) in annotations {
+            let (start, end) = (span.start(), span.end());
 
-            let col_number = annotation.span.start().col() as usize;
-            let length = annotation.span.end().col() as usize - col_number;
-            let text = annotation.content.as_str();
+            for line in start.line()..=end.line() {
+                let line_idx = line as usize - first_line_number;
 
-            let ann = ReportedAnnotation {
-                col_number,
-                length,
-                text,
-            };
-            matrix[line_idx].push(ann);
+                let col_number = if line == start.line() {
+                    start.col() as usize
+                } else {
+                    0
+                };
+
+                let (end_col, text) = if line == end.line() {
+                    (end.col() as usize, text)
+                } else {
+                    (line_length(line), "")
+                };
+
+                let ann = ReportedAnnotation {
+                    col_number,
+                    length: end_col.saturating_sub(col_number),
+                    text,
+                };
+                matrix[line_idx].push(ann);
+            }
         }
 
         matrix
@@ -131,21 +946,396 @@ impl AnnotatedError {
     }
 }
 
+/// A set of reports grouped under a common message.
+///
+/// A group is useful to summarize several errors that share a common cause, for
+/// instance all the errors encountered in a given function. Unlike
+/// [`AnnotatedError::with_related`], the child reports are independent from
+/// each other.
+///
+/// # Example
+///
+/// ```rust
+/// use lisbeth_error::{
+///     error::{AnnotatedError, DiagnosticGroup},
+///     span::SpannedStr,
+/// };
+///
+/// let file = SpannedStr::input_file("foo bar");
+///
+/// let group = DiagnosticGroup::new("2 errors in this file")
+///     .with_error(AnnotatedError::new(file.find("foo").unwrap().span(), "Unknown foo"))
+///     .with_error(AnnotatedError::new(file.find("bar").unwrap().span(), "Unknown bar"));
+///
+/// assert_eq!(group.errors().len(), 2);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiagnosticGroup {
+    pub(crate) msg: String,
+    pub(crate) severity: Severity,
+    errors: Vec<AnnotatedError>,
+}
+
+impl DiagnosticGroup {
+    /// Constructs a new, empty group.
+    ///
+    /// `msg` is the message displayed once, above every child report. It can
+    /// be either a static string slice or a `String`.
+    pub fn new<Msg>(msg: Msg) -> DiagnosticGroup
+    where
+        Msg: ToString,
+    {
+        DiagnosticGroup {
+            msg: msg.to_string(),
+            severity: Severity::default(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Sets the severity of the group message.
+    ///
+    /// The default severity is [`Severity::Error`]. The severity of the child
+    /// reports is left untouched.
+    pub fn with_severity(mut self, severity: Severity) -> DiagnosticGroup {
+        self.severity = severity;
+        self
+    }
+
+    /// Adds a child report to the group.
+    pub fn with_error(mut self, err: AnnotatedError) -> DiagnosticGroup {
+        self.errors.push(err);
+        self
+    }
+
+    /// Returns the child reports of the group, in the order in which they were
+    /// added.
+    pub fn errors(&self) -> &[AnnotatedError] {
+        &self.errors
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 struct Annotation {
     span: Span,
     content: String,
 }
 
+impl Annotation {
+    // Returns whether both annotations have the same text and touch or
+    // overlap on a single line.
+    fn is_contiguous(&self, other: &Annotation) -> bool {
+        let line = self.span.start().line();
+        let single_line = [self.span, other.span]
+            .iter()
+            .all(|span| span.start().line() == line && span.end().line() == line);
+        let touch =
+            self.span.is_adjacent_to(other.span) || self.span.intersect(other.span).is_some();
+
+        self.content == other.content && single_line && touch
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    mod annotated_error {
+    use alloc::format;
+
+    mod severity {
         use super::*;
 
         use crate::span::SpannedStr;
 
+        fn report(severity: Severity) -> AnnotatedError {
+            let span = SpannedStr::input_file("foo").span();
+            AnnotatedError::new(span, "Some message").with_severity(severity)
+        }
+
+        #[test]
+        fn worst_severity_empty() {
+            assert_eq!(worst_severity(&[]), None);
+        }
+
+        #[test]
+        fn worst_severity_all_warnings() {
+            let errs = [report(Severity::Warning), report(Severity::Warning)];
+            assert_eq!(worst_severity(&errs), Some(Severity::Warning));
+        }
+
+        #[test]
+        fn worst_severity_mixed() {
+            let errs = [report(Severity::Warning), report(Severity::Error)];
+            assert_eq!(worst_severity(&errs), Some(Severity::Error));
+        }
+
+        #[test]
+        fn exit_code_all_warnings() {
+            let errs = [report(Severity::Warning), report(Severity::Warning)];
+            assert_eq!(exit_code(&errs), 0);
+        }
+
+        #[test]
+        fn exit_code_mixed() {
+            let errs = [report(Severity::Warning), report(Severity::Error)];
+            assert_eq!(exit_code(&errs), 1);
+        }
+
+        #[test]
+        fn exit_code_empty() {
+            assert_eq!(exit_code(&[]), 0);
+        }
+
+        #[test]
+        fn with_level_sets_severity() {
+            let err = report(Severity::Error).with_level(Severity::Warning);
+            assert_eq!(err.severity(), Severity::Warning);
+        }
+
+        #[test]
+        fn downgrade_matching_metadata() {
+            let mut errs = [
+                report(Severity::Error).with_meta("rule", "unused"),
+                report(Severity::Error),
+                report(Severity::Error).with_meta("rule", "unused"),
+            ];
+
+            downgrade_matching(
+                &mut errs,
+                |err| err.meta("rule").is_some(),
+                Severity::Warning,
+            );
+
+            let left = errs
+                .iter()
+                .map(AnnotatedError::severity)
+                .collect::<Vec<_>>();
+            let right = [Severity::Warning, Severity::Error, Severity::Warning];
+
+            assert_eq!(left, right);
+            assert_eq!(exit_code(&errs), 1);
+        }
+    }
+
+    mod sort_annotations {
+        use super::*;
+
+        use crate::span::SpannedStr;
+
+        #[test]
+        fn out_of_order() {
+            let input = SpannedStr::input_file("foo bar\nbaz");
+            let foo = input.find("foo").unwrap().span();
+            let fo = input.find("fo").unwrap().span();
+            let bar = input.find("bar").unwrap().span();
+            let baz = input.find("baz").unwrap().span();
+
+            let mut anns = vec![
+                (baz, "baz".to_string()),
+                (foo, "foo, again".to_string()),
+                (bar, "bar".to_string()),
+                (foo, "foo".to_string()),
+                (fo, "fo".to_string()),
+            ];
+
+            sort_annotations(&mut anns);
+
+            let left = anns
+                .iter()
+                .map(|(_, text)| text.as_str())
+                .collect::<Vec<_>>();
+            assert_eq!(left, ["fo", "foo", "foo, again", "bar", "baz"]);
+        }
+
+        #[test]
+        fn empty() {
+            let mut anns = Vec::new();
+            sort_annotations(&mut anns);
+
+            assert!(anns.is_empty());
+        }
+    }
+
+    mod annotated_error {
+        use super::*;
+
+        use crate::{reporter::ErrorReporter, span::SpannedStr};
+
+        #[test]
+        fn try_with_annotation_multiline() {
+            let input = SpannedStr::input_file("foo\nbar");
+            let multiline = input.find("o\nb").unwrap().span();
+
+            let report = AnnotatedError::new(multiline, "Unexpected line return");
+
+            let left = report.clone().try_with_annotation(multiline, "here");
+            let right = report.with_annotation(multiline, "here");
+
+            assert_eq!(left, Ok(right));
+        }
+
+        #[test]
+        fn merge_identical_annotations_adjacent() {
+            let input_file = ErrorReporter::non_file_input("foo bar".to_string());
+            let input = input_file.spanned_str();
+            let (foo, bar) = input.split_at(3);
+
+            let report = AnnotatedError::new(input.span(), "Unknown words")
+                .with_annotation(foo.span(), "unknown")
+                .with_annotation(bar.span(), "unknown")
+                .merge_identical_annotations();
+
+            let left = report.annotations().collect::<Vec<_>>();
+            assert_eq!(left, vec![(input.span(), "unknown")]);
+
+            let formatted = input_file.format_error(&report).to_string();
+            assert!(formatted.contains(" ^^^^^^^\n"));
+            assert_eq!(formatted.matches('^').count(), 7);
+            assert_eq!(formatted.matches("unknown").count(), 1);
+        }
+
+        #[test]
+        fn merge_identical_annotations_chain() {
+            let input = SpannedStr::input_file("abc");
+            let (a, bc) = input.split_at(1);
+            let (b, c) = bc.split_at(1);
+
+            let report = AnnotatedError::new(input.span(), "Unknown letters")
+                .with_annotation(a.span(), "letter")
+                .with_annotation(c.span(), "letter")
+                .with_annotation(b.span(), "letter")
+                .merge_identical_annotations();
+
+            let left = report.annotations().collect::<Vec<_>>();
+            assert_eq!(left, vec![(input.span(), "letter")]);
+        }
+
+        #[test]
+        fn merge_identical_annotations_keeps_separate() {
+            let source = "foo bar\nbaz";
+            let input = SpannedStr::input_file(source);
+            let foo = input.find("foo").unwrap().span();
+            let bar = input.find("bar").unwrap().span();
+            let ar = input.find("ar").unwrap().span();
+            let baz = input.find("baz").unwrap().span();
+            let line_return = input.find("r\nb").unwrap().span();
+
+            let report = AnnotatedError::new(input.span(), "Unknown words")
+                .with_annotation(foo, "unknown")
+                .with_annotation(bar, "unknown")
+                .with_annotation(ar, "suffix")
+                .with_annotation(line_return, "unknown")
+                .with_annotation(baz, "unknown");
+
+            let left = report.clone().merge_identical_annotations();
+
+            assert_eq!(left, report);
+        }
+
+        #[test]
+        fn metadata_set_and_read() {
+            let input = SpannedStr::input_file("foo");
+            let report = AnnotatedError::new(input.span(), "Unused variable")
+                .with_meta("rule", "unused-variable")
+                .with_meta("category", "style")
+                .with_meta("category", "lint");
+
+            assert_eq!(report.meta("rule"), Some("unused-variable"));
+            assert_eq!(report.meta("category"), Some("lint"));
+            assert_eq!(report.meta("id"), None);
+        }
+
+        #[test]
+        fn merge_keeps_own_metadata() {
+            let input = SpannedStr::input_file("foo");
+            let left = AnnotatedError::new(input.span(), "Left").with_meta("rule", "left");
+            let right = AnnotatedError::new(input.span(), "Right")
+                .with_meta("rule", "right")
+                .with_meta("category", "style");
+
+            let merged = left.merge(right);
+
+            assert_eq!(merged.meta("rule"), Some("left"));
+            assert_eq!(merged.meta("category"), Some("style"));
+        }
+
+        #[test]
+        fn new_with_calls_closure_once() {
+            let input = SpannedStr::input_file("foo");
+            let mut calls = 0;
+
+            let report = AnnotatedError::new_with(input.span(), || {
+                calls += 1;
+                format!("Unknown word: `{}`", input.content())
+            });
+
+            assert_eq!(calls, 1);
+            assert_eq!(report.msg, "Unknown word: `foo`");
+            assert_eq!(report.span(), input.span());
+        }
+
+        #[test]
+        fn removal_suggestion_is_kept_by_merge() {
+            let input = SpannedStr::input_file("foo;;");
+            let semi = input.split_at(4).1;
+
+            let left = AnnotatedError::new(semi.span(), "Unnecessary semicolon");
+            let right = AnnotatedError::new(semi.span(), "Unnecessary semicolon")
+                .with_removal_suggestion(semi.span());
+
+            let merged = left.merge(right);
+
+            assert_eq!(
+                merged.suggestions,
+                [AnnotatedError::new(semi.span(), "remove this")]
+            );
+        }
+
+        #[test]
+        fn primary_position() {
+            let input = SpannedStr::input_file("foo\nbar baz");
+            let baz = input.find("baz").unwrap();
+
+            let report = AnnotatedError::new(baz.span(), "Unexpected baz")
+                .with_annotation(input.split_at(2).0.span(), "unrelated");
+
+            assert_eq!(report.primary_position(), baz.span().start());
+            assert_eq!(report.primary_position().offset(), 8);
+            assert_eq!(report.primary_line_col(), (1, 4));
+        }
+
+        #[test]
+        fn map_spans_shifts_bounds() {
+            let parent = SpannedStr::input_file("    abcd");
+            let child = SpannedStr::input_file("abcd");
+            let delta = 4;
+
+            let shift = |span: Span| {
+                let (start, end) = (span.start().offset() as usize, span.end().offset() as usize);
+                parent
+                    .split_at(start + delta)
+                    .1
+                    .split_at(end - start)
+                    .0
+                    .span()
+            };
+
+            let (a, tail) = child.split_at(1);
+            let d = tail.split_at(2).1;
+
+            let report = AnnotatedError::new(a.span(), "Some generic message")
+                .with_annotation(a.span(), "ann1")
+                .with_annotation(d.span(), "ann2");
+
+            let (start, end) = report.bounds();
+            let (shifted_start, shifted_end) = report.map_spans(shift).bounds();
+
+            assert_eq!(shifted_start.offset(), start.offset() + delta as u32);
+            assert_eq!(shifted_end.offset(), end.offset() + delta as u32);
+
+            assert_eq!(shifted_start.col(), start.col() + delta as u32);
+            assert_eq!(shifted_end.col(), end.col() + delta as u32);
+        }
+
         #[test]
         fn bounds_double() {
             let input = SpannedStr::input_file("ab");
@@ -185,6 +1375,68 @@ mod tests {
             assert_eq!(end.line(), 0);
         }
 
+        #[test]
+        fn annotations() {
+            let input = SpannedStr::input_file("ab");
+            let (a, b) = input.split_at(1);
+
+            let report = AnnotatedError::new(a.span(), "Some generic message")
+                .with_annotation(a.span(), "ann1")
+                .with_annotation(b.span(), "ann2");
+
+            let annotations = report.annotations().collect::<Vec<_>>();
+
+            assert_eq!(report.annotation_count(), 2);
+            assert_eq!(annotations, [(a.span(), "ann1"), (b.span(), "ann2")]);
+        }
+
+        #[test]
+        fn with_annotations() {
+            let input = SpannedStr::input_file("abc");
+            let (a, bc) = input.split_at(1);
+            let (b, c) = bc.split_at(1);
+
+            let anns = vec![
+                (a.span(), "ann1".to_string()),
+                (b.span(), "ann2".to_string()),
+                (c.span(), "ann3".to_string()),
+            ];
+
+            let report = AnnotatedError::new(a.span(), "Some generic message")
+                .with_annotation(a.span(), "ann0")
+                .with_annotations(anns);
+
+            assert_eq!(report.annotation_count(), 4);
+
+            let texts = report.annotations().map(|(_, t)| t).collect::<Vec<_>>();
+            assert_eq!(texts, ["ann0", "ann1", "ann2", "ann3"]);
+        }
+
+        #[test]
+        fn merge() {
+            let input = SpannedStr::input_file("foo\nbar");
+            let (foo, bar) = input.split_at(3);
+
+            let left = AnnotatedError::new(foo.span(), "First message")
+                .with_annotation(foo.span(), "ann1");
+            let right = AnnotatedError::new(bar.span(), "Second message")
+                .with_annotation(bar.span(), "ann2");
+
+            let report = left.merge(right);
+
+            assert_eq!(report.annotation_count(), 2);
+            assert_eq!(report.span(), foo.span());
+            assert_eq!(report.msg, "First message");
+
+            let (start, end) = report.bounds();
+
+            assert_eq!(start.offset(), 0);
+            assert_eq!(end.offset(), 7);
+
+            assert_eq!(start.line(), 0);
+            assert_eq!(end.line(), 1);
+        }
+
         #[test]
         fn error_matrix_for() {
             // In this text, there is a line that gets ignored because it has
@@ -205,7 +1457,7 @@ mod tests {
                 .with_annotation(l2.span(), "second line")
                 .with_annotation(num.span(), "second line, but better");
 
-            let matrix = report.error_matrix();
+            let matrix = report.error_matrix(|_| 6);
 
             assert_eq!(matrix.len(), 2);
             assert_eq!(matrix[0].len(), 1);
@@ -213,5 +1465,25 @@ mod tests {
 
             assert!(matrix[1][0].col_number < matrix[1][1].col_number);
         }
+
+        #[test]
+        fn error_matrix_multiline() {
+            let input_file = SpannedStr::input_file("abc\ndefgh\nij");
+            let span = input_file
+                .split_at(1)
+                .0
+                .span()
+                .merge(input_file.find("i").unwrap().span());
+
+            let report = AnnotatedError::new(span, "Unterminated").with_annotation(span, "here");
+            let matrix = report.error_matrix(|line| [3, 5, 2][line as usize]);
+
+            let pieces = matrix
+                .iter()
+                .map(|anns| (anns[0].col_number, anns[0].length, anns[0].text))
+                .collect::<Vec<_>>();
+
+            assert_eq!(pieces, [(0, 3, ""), (0, 5, ""), (0, 1, "here")]);
+        }
     }
 }
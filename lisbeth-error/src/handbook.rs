@@ -58,27 +58,17 @@
 //! # };
 //! #
 //! fn space<'a>(input: SpannedStr<'a>) -> Result<SpannedStr<'a>, AnnotatedError> {
-//!     let first_char = input.content().chars().next();
-//!
-//!     let first_char = match first_char {
-//!         Some(chr) => chr,
+//!     let (first_char, span) = match input.first_char() {
+//!         Some(first) => first,
 //!         None => {
-//!             let report = AnnotatedError::new(
-//!                 input.span(),
-//!                 "Expected ` `, found EOF"
-//!             );
+//!             let report = AnnotatedError::new(input.span(), "Expected ` `, found EOF");
 //!             return Err(report);
 //!         },
 //!     };
 //!
 //!     if first_char != ' ' {
-//!         let err_span = input.split_at(first_char.len_utf8()).0;
-//!
-//!         let report = AnnotatedError::new(err_span.span(), "Expected ` `.")
-//!             .with_annotation(
-//!                 err_span.span(),
-//!                 format!("Expected ` `, found `{}`.", err_span.content()),
-//!             );
+//!         let report = AnnotatedError::new(span, "Expected ` `.")
+//!             .with_annotation(span, format!("Expected ` `, found `{}`.", first_char));
 //!
 //!         return Err(report);
 //!     }
@@ -118,7 +108,7 @@
 //!             break;
 //!         }
 //!
-//!         let tail = space(input)?;
+//!         let tail = space(tail)?;
 //!
 //!         input = tail;
 //!     }
@@ -176,10 +166,8 @@
 //! # }
 //! #
 //! # fn space<'a>(input: SpannedStr<'a>) -> Result<SpannedStr<'a>, AnnotatedError> {
-//! #     let first_char = input.content().chars().next();
-//! #
-//! #     let first_char = match first_char {
-//! #         Some(chr) => chr,
+//! #     let (first_char, span) = match input.first_char() {
+//! #         Some(first) => first,
 //! #         None => {
 //! #             let report = AnnotatedError::new(input.span(), "Expected ` `, found EOF.");
 //! #             return Err(report);
@@ -187,13 +175,8 @@
 //! #     };
 //! #
 //! #     if first_char != ' ' {
-//! #         let err_span = input.split_at(first_char.len_utf8()).0;
-//! #
-//! #         let report = AnnotatedError::new(err_span.span(), "Expected ` `")
-//! #             .with_annotation(
-//! #                 err_span.span(),
-//! #                 format!("Expected ` `, found `{}`.", err_span.content()),
-//! #             );
+//! #         let report = AnnotatedError::new(span, "Expected ` `")
+//! #             .with_annotation(span, format!("Expected ` `, found `{}`.", first_char));
 //! #
 //! #         return Err(report);
 //! #     }
@@ -273,10 +256,8 @@
 //! # }
 //! #
 //! # fn space<'a>(input: SpannedStr<'a>) -> Result<SpannedStr<'a>, AnnotatedError> {
-//! #     let first_char = input.content().chars().next();
-//! #
-//! #     let first_char = match first_char {
-//! #         Some(chr) => chr,
+//! #     let (first_char, span) = match input.first_char() {
+//! #         Some(first) => first,
 //! #         None => {
 //! #             let report = AnnotatedError::new(input.span(), "Expected ` `, found EOF.");
 //! #             return Err(report);
@@ -284,13 +265,8 @@
 //! #     };
 //! #
 //! #     if first_char != ' ' {
-//! #         let err_span = input.split_at(first_char.len_utf8()).0;
-//! #
-//! #         let report = AnnotatedError::new(err_span.span(), "Expected ` `")
-//! #             .with_annotation(
-//! #                 err_span.span(),
-//! #                 format!("Expected ` `, found `{}`.", err_span.content()),
-//! #             );
+//! #         let report = AnnotatedError::new(span, "Expected ` `")
+//! #             .with_annotation(span, format!("Expected ` `, found `{}`.", first_char));
 //! #
 //! #         return Err(report);
 //! #     }
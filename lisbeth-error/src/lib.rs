@@ -9,11 +9,20 @@
 //!   - tokens are produced, they store their position with a [`Span`],
 //!   - when an error occurs, an error is reported with an [`AnnotatedError`],
 //!   - this error is formatted by the [`ErrorReporter`] declared previously,
-//!   which returns a [`FormattedError`],
+//!     which returns a [`FormattedError`],
 //!   - the [`FormattedError`] is printed on the console.
 //!
 //! An example of usage can be found in the [handbook] module.
 //!
+//! # `no_std` support
+//!
+//! This crate depends only on `alloc` when the default `std` feature is
+//! disabled. The constructors of [`ErrorReporter`] that read files or
+//! readers, and the methods that write to an [`io::Write`] target, are
+//! available only with the `std` feature.
+//!
+//! [`io::Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
+//!
 //! [`ErrorReporter`]: reporter::ErrorReporter
 //! [`SpannedStr`]: span::SpannedStr
 //! [`Span`]: span::Span
@@ -21,8 +30,42 @@
 //! [`FormattedError`]: reporter::FormattedError
 
 #![deny(missing_docs, warnings)]
+#![no_std]
+
+extern crate alloc;
+#[cfg(any(feature = "std", test))]
+extern crate std;
 
 pub mod error;
 pub mod handbook;
+#[cfg(feature = "serde")]
+pub mod lsp;
 pub mod reporter;
 pub mod span;
+
+#[cfg(test)]
+mod tests {
+    // This test only relies on `core` and `alloc`, so that it exercises the
+    // rendering available when the `std` feature is disabled. The test
+    // harness still links the standard library: building the crate without
+    // it is checked by CI, for a target that has no standard library.
+    use alloc::string::{String, ToString};
+
+    use crate::{error::AnnotatedError, reporter::ErrorReporter, span::SpannedStr};
+
+    #[test]
+    fn render_without_std() {
+        let input = SpannedStr::input_file("foo bar");
+        let bar = input.find("bar").unwrap();
+        let report =
+            AnnotatedError::new(bar.span(), "Unexpected bar").with_annotation(bar.span(), "here");
+
+        let reporter = ErrorReporter::non_file_input("foo bar".to_string());
+
+        let mut left = String::new();
+        reporter.format_error(&report).render_into(&mut left);
+
+        assert!(left.starts_with("Error: Unexpected bar\n --> 1:5\n"));
+        assert!(left.contains("here"));
+    }
+}
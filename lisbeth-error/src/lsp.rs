@@ -0,0 +1,93 @@
+//! Diagnostics in the shape of the Language Server Protocol.
+//!
+//! The types of this module mirror the `Diagnostic` structure defined by the
+//! [Language Server Protocol specification][lsp-spec]. They are created with
+//! [`FormattedError::to_lsp_diagnostic`], and serialize to the JSON expected by
+//! editors.
+//!
+//! This module is available only when the `serde` feature is enabled.
+//!
+//! [lsp-spec]: https://microsoft.github.io/language-server-protocol/specification
+//! [`FormattedError::to_lsp_diagnostic`]: crate::reporter::FormattedError::to_lsp_diagnostic
+
+use alloc::{string::String, vec::Vec};
+
+use serde::Serialize;
+
+use crate::error::Severity;
+
+/// A diagnostic, such as an error or a warning.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostic {
+    /// The range at which the diagnostic applies.
+    pub range: Range,
+    /// The severity of the diagnostic, as defined by [`severity_code`].
+    pub severity: u8,
+    /// The message of the diagnostic.
+    pub message: String,
+    /// Locations related to the diagnostic, such as the annotations and the
+    /// related errors.
+    pub related_information: Vec<DiagnosticRelatedInformation>,
+}
+
+/// A location related to a diagnostic, with a message.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DiagnosticRelatedInformation {
+    /// The location of the information.
+    pub location: Location,
+    /// The message of the information.
+    pub message: String,
+}
+
+/// A range in a given document.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Location {
+    /// The document, which is the path of the input, or an empty string for
+    /// inputs that are not files.
+    pub uri: String,
+    /// The range in the document.
+    pub range: Range,
+}
+
+/// A range in a document, expressed with a start and an end position.
+///
+/// The start is included, the end is excluded.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Range {
+    /// The position at which the range starts.
+    pub start: Position,
+    /// The position at which the range ends.
+    pub end: Position,
+}
+
+/// A position in a document.
+///
+/// Both the line and the character are zero-indexed.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Position {
+    /// The line of the position.
+    pub line: u32,
+    /// The character offset of the position on its line.
+    pub character: u32,
+}
+
+/// Returns the code used by the Language Server Protocol to represent
+/// `severity`.
+///
+/// Errors are represented with `1` and warnings with `2`.
+///
+/// # Example
+///
+/// ```rust
+/// use lisbeth_error::{error::Severity, lsp::severity_code};
+///
+/// assert_eq!(severity_code(Severity::Error), 1);
+/// assert_eq!(severity_code(Severity::Warning), 2);
+/// ```
+pub const fn severity_code(severity: Severity) -> u8 {
+    match severity {
+        Severity::Error => 1,
+        Severity::Warning => 2,
+    }
+}
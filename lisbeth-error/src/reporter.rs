@@ -45,17 +45,33 @@
 //!     |
 //! ```
 
+use alloc::{
+    borrow::Cow,
+    boxed::Box,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{
+    convert::{Infallible, TryFrom},
+    fmt::{self, Display, Write},
+    iter,
+    str::FromStr,
+};
+#[cfg(feature = "std")]
 use std::{
-    fmt::{self, Display},
     fs,
-    io::Error as IOError,
+    io::{self, Error as IOError, Read},
 };
 
 use crate::{
-    error::AnnotatedError,
+    error::{AnnotatedError, DiagnosticGroup, Severity},
     span::{Position, Span, SpannedStr},
 };
 
+#[cfg(feature = "serde")]
+use crate::lsp;
+
 /// Holds metadata about the input, allows to report errors to the user.
 ///
 /// This structure should be created before the parsing process and will provide
@@ -65,50 +81,205 @@ use crate::{
 /// This is needed because the file path is printed on the console when an error
 /// is reported.
 ///
+/// The input can either be owned by the reporter or borrowed from somewhere
+/// else. The `'a` lifetime represents the lifetime of the borrowed data. It is
+/// `'static` when the reporter owns its input.
+///
 /// [`AnnotatedError`]: [super::error::AnnotatedError]
-pub struct ErrorReporter {
-    path: Option<String>,
-    content: String,
+pub struct ErrorReporter<'a> {
+    path: Option<Cow<'a, str>>,
+    content: Cow<'a, str>,
     span: Span,
+    // Byte offset of the beginning of each line of content.
+    line_starts: Vec<usize>,
+    #[cfg(feature = "unicode")]
+    grapheme_columns: bool,
 }
 
-impl ErrorReporter {
+impl ErrorReporter<'static> {
     /// Given a file path and its content, creates a new [`ErrorReporter`].
     ///
     /// `path` is not checked to be a valid path.
-    pub fn input_file(path: String, content: String) -> ErrorReporter {
-        let path = Some(path);
-        let span = Span::of_file(content.as_str());
-        ErrorReporter {
-            content,
-            path,
-            span,
-        }
+    pub fn input_file(path: String, content: String) -> ErrorReporter<'static> {
+        ErrorReporter::new(Some(Cow::Owned(path)), Cow::Owned(content))
     }
 
     /// Creates an [`ErrorReporter`] with no file path, just its content.
     ///
     /// This can be usefull in situations in which non-file inputs such as STDIN
     /// are processed.
-    pub fn non_file_input(content: String) -> ErrorReporter {
-        let path = None;
-        let span = Span::of_file(content.as_str());
+    pub fn non_file_input(content: String) -> ErrorReporter<'static> {
+        ErrorReporter::new(None, Cow::Owned(content))
+    }
+
+    /// Reads the content of `path`, and creates an [`ErrorReporter`] with it.
+    ///
+    /// An error of kind [`InvalidData`] is returned if the content is not
+    /// valid UTF-8 or if it is larger than `u32::MAX` bytes.
+    ///
+    /// This method is available only when the `std` feature is enabled.
+    ///
+    /// [`InvalidData`]: std::io::ErrorKind::InvalidData
+    #[cfg(feature = "std")]
+    pub fn from_path(path: String) -> Result<ErrorReporter<'static>, IOError> {
+        let content = fs::read_to_string(path.as_str())?;
+        ErrorReporter::try_new(Some(Cow::Owned(path)), Cow::Owned(content))
+    }
+
+    /// Reads the whole content of `reader`, and creates an [`ErrorReporter`]
+    /// named `path` with it.
+    ///
+    /// An error of kind [`InvalidData`] is returned if the content is not
+    /// valid UTF-8 or if it is larger than `u32::MAX` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::reporter::ErrorReporter;
+    ///
+    /// let input: &[u8] = b"Hello, world";
+    /// let file = ErrorReporter::from_reader("hello.txt".to_string(), input).unwrap();
+    ///
+    /// assert_eq!(file.spanned_str().content(), "Hello, world");
+    /// ```
+    ///
+    /// This method is available only when the `std` feature is enabled.
+    ///
+    /// [`InvalidData`]: std::io::ErrorKind::InvalidData
+    #[cfg(feature = "std")]
+    pub fn from_reader<R>(path: String, mut reader: R) -> Result<ErrorReporter<'static>, IOError>
+    where
+        R: Read,
+    {
+        let mut content = String::new();
+        reader.read_to_string(&mut content)?;
+
+        ErrorReporter::try_new(Some(Cow::Owned(path)), Cow::Owned(content))
+    }
+
+    /// Reads the whole content of STDIN, and creates an [`ErrorReporter`]
+    /// named `<stdin>` with it.
+    ///
+    /// An error of kind [`InvalidData`] is returned if the content is not
+    /// valid UTF-8 or if it is larger than `u32::MAX` bytes.
+    ///
+    /// This method is available only when the `std` feature is enabled.
+    ///
+    /// [`InvalidData`]: std::io::ErrorKind::InvalidData
+    #[cfg(feature = "std")]
+    pub fn input_stdin() -> Result<ErrorReporter<'static>, IOError> {
+        ErrorReporter::from_reader("<stdin>".to_string(), io::stdin().lock())
+    }
+}
+
+impl FromStr for ErrorReporter<'static> {
+    type Err = Infallible;
+
+    /// Creates an [`ErrorReporter`] with no file path, just its content.
+    ///
+    /// This is equivalent to [`ErrorReporter::non_file_input`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::reporter::ErrorReporter;
+    ///
+    /// let file: ErrorReporter = "Hello, world".parse().unwrap();
+    /// assert_eq!(file.spanned_str().content(), "Hello, world");
+    /// ```
+    fn from_str(content: &str) -> Result<ErrorReporter<'static>, Infallible> {
+        Ok(ErrorReporter::non_file_input(content.to_string()))
+    }
+}
+
+impl<'a> ErrorReporter<'a> {
+    fn new(path: Option<Cow<'a, str>>, content: Cow<'a, str>) -> ErrorReporter<'a> {
+        let span = Span::of_file(content.as_ref());
+        let line_starts = line_starts(content.as_ref());
+
         ErrorReporter {
             content,
             path,
             span,
+            line_starts,
+            #[cfg(feature = "unicode")]
+            grapheme_columns: false,
         }
     }
 
-    /// Reads the content of `path`, and creates an [`ErrorReporter`] with it.
-    pub fn from_path(path: String) -> Result<ErrorReporter, IOError> {
-        fs::read_to_string(path.as_str())
-            .map(|content| (Span::of_file(content.as_str()), content, Some(path)))
-            .map(|(span, content, path)| ErrorReporter {
-                content,
-                path,
-                span,
-            })
+    #[cfg(feature = "std")]
+    fn try_new(
+        path: Option<Cow<'a, str>>,
+        content: Cow<'a, str>,
+    ) -> Result<ErrorReporter<'a>, IOError> {
+        let span = Span::checked_of_file(content.as_ref()).ok_or_else(|| {
+            IOError::new(
+                io::ErrorKind::InvalidData,
+                "input is too large to be spanned",
+            )
+        })?;
+        let line_starts = line_starts(content.as_ref());
+
+        Ok(ErrorReporter {
+            content,
+            path,
+            span,
+            line_starts,
+            #[cfg(feature = "unicode")]
+            grapheme_columns: false,
+        })
+    }
+
+    /// Creates an [`ErrorReporter`] that borrows its input instead of owning
+    /// it.
+    ///
+    /// This avoids copying the input when it already lives long enough, for
+    /// instance when it comes from a memory-mapped file. `path` is not checked
+    /// to be a valid path.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::reporter::ErrorReporter;
+    ///
+    /// let content = "Hello, world";
+    /// let file = ErrorReporter::from_borrowed(Some("hello.txt"), content);
+    ///
+    /// assert_eq!(file.path(), Some("hello.txt"));
+    /// assert_eq!(file.spanned_str().content(), "Hello, world");
+    /// ```
+    pub fn from_borrowed(path: Option<&'a str>, content: &'a str) -> ErrorReporter<'a> {
+        ErrorReporter::new(path.map(Cow::Borrowed), Cow::Borrowed(content))
+    }
+
+    /// Sets whether columns should be counted in grapheme clusters instead of
+    /// `char`s when errors are formatted.
+    ///
+    /// Some visible characters are made of more than one `char`, for instance
+    /// when a letter is followed by a combining accent. Counting columns in
+    /// grapheme clusters allows the rendered column numbers and the carets to
+    /// match what the user sees. This is disabled by default.
+    ///
+    /// This method is available only when the `unicode` feature is enabled.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, reporter::ErrorReporter};
+    ///
+    /// let reporter = ErrorReporter::non_file_input("cafe\u{301} bar".to_string())
+    ///     .with_grapheme_columns(true);
+    /// let bar = reporter.spanned_str().split_at(7).1;
+    ///
+    /// let report = AnnotatedError::new(bar.span(), "Unexpected word");
+    /// let formatted = reporter.format_error(&report).to_string();
+    ///
+    /// assert!(formatted.contains(" --> 1:6\n"));
+    /// ```
+    #[cfg(feature = "unicode")]
+    pub fn with_grapheme_columns(mut self, enabled: bool) -> ErrorReporter<'a> {
+        self.grapheme_columns = enabled;
+        self
     }
 
     /// Returns the file path, if it exists.
@@ -116,6 +287,26 @@ impl ErrorReporter {
         self.path.as_deref()
     }
 
+    /// Sets the file path, or removes it if `path` is `None`.
+    ///
+    /// This allows to name the input once its logical name is known, for
+    /// instance when it is read from STDIN. `path` is not checked to be a
+    /// valid path.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::reporter::ErrorReporter;
+    ///
+    /// let mut file = ErrorReporter::non_file_input("Hello, world".to_string());
+    /// file.set_path(Some("hello.txt".to_string()));
+    ///
+    /// assert_eq!(file.path(), Some("hello.txt"));
+    /// ```
+    pub fn set_path(&mut self, path: Option<String>) {
+        self.path = path.map(Cow::Owned);
+    }
+
     /// Returns the [`SpannedStr`] associated to the whole input.
     ///
     /// # Example
@@ -126,239 +317,3790 @@ impl ErrorReporter {
     /// let file = ErrorReporter::non_file_input("Hello, world".to_string());
     /// assert_eq!(file.spanned_str().content(), "Hello, world");
     /// ```
-    pub fn spanned_str(&self) -> SpannedStr {
+    pub fn spanned_str(&self) -> SpannedStr<'_> {
         // self.span has been built from self.content, so this call is fine.
-        SpannedStr::assemble(self.content.as_str(), self.span)
+        SpannedStr::assemble(self.content.as_ref(), self.span)
     }
 
-    fn code_snippet_for(&self, start_pos: Position, end_pos: Position) -> &str {
-        let (start_offset, end_offset) = (start_pos.offset() as usize, end_pos.offset() as usize);
+    /// Returns the text covered by `span`.
+    ///
+    /// Unlike the code snippet displayed when an error is formatted, the text
+    /// is not expanded to full lines. `None` is returned if the span goes past
+    /// the end of the input or if it does not start and end on character
+    /// boundaries.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::reporter::ErrorReporter;
+    ///
+    /// let file = ErrorReporter::non_file_input("Hello, world".to_string());
+    /// let world = file.spanned_str().split_at(7).1;
+    ///
+    /// assert_eq!(file.text_of(world.span()), Some("world"));
+    /// ```
+    pub fn text_of(&self, span: Span) -> Option<&str> {
+        let start = span.start().offset() as usize;
+        let end = span.end().offset() as usize;
 
-        let before_start = self.content.split_at(start_offset).0;
-        let after_end = self.content.split_at(end_offset).1;
+        self.content.get(start..end)
+    }
 
-        let end_idx = end_offset as usize
-            + after_end
-                .char_indices()
-                .find(|(_, c)| *c == '\n')
-                .map(|(idx, _)| idx)
-                .unwrap_or_else(|| after_end.len());
+    /// Returns the span covering the zero-indexed line `line`.
+    ///
+    /// The span does not include the line return that ends the line. `None`
+    /// is returned if the input does not have that many lines. This allows to
+    /// annotate a whole line without building a [`Span`] from its text.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, reporter::ErrorReporter};
+    ///
+    /// let file = ErrorReporter::non_file_input("let a = 42;\nlet b = a;".to_string());
+    /// let second = file.line_span(1).unwrap();
+    ///
+    /// assert_eq!(file.text_of(second), Some("let b = a;"));
+    ///
+    /// let report = AnnotatedError::new(second, "Line is too long")
+    ///     .with_annotation(second, "This line");
+    /// ```
+    pub fn line_span(&self, line: u32) -> Option<Span> {
+        let line_idx = usize::try_from(line).ok()?;
+        let start_idx = *self.line_starts.get(line_idx)?;
+        let text = self.lines_between(line_idx, line_idx);
+        let text = text.strip_suffix('\r').unwrap_or(text);
 
-        let start_idx = before_start
-            .char_indices()
-            .rev()
-            .take_while(|(_, c)| *c != '\n')
-            .last()
-            .map(|(idx, _)| idx)
-            .unwrap_or_else(|| before_start.len());
+        let start = Position::new(line, 0, start_idx as u32);
+        let end = Position::new(
+            line,
+            text.chars().count() as u32,
+            (start_idx + text.len()) as u32,
+        );
 
-        self.content.split_at(end_idx).0.split_at(start_idx).1
+        Some(Span::new(start, end))
     }
 
-    /// Constructs a [`FormattedError`] from an [`AnnotatedError`].
+    /// Returns a copy of the input in which every `\r\n` line ending is
+    /// replaced by `\n`.
     ///
-    /// The returned value can finally be printed to the user.
-    pub fn format_error<'a>(&'a self, err: &'a AnnotatedError) -> FormattedError<'a> {
-        let (start_pos, end_pos) = err.bounds();
-        let stream_name = self.path();
-        let text = self.code_snippet_for(start_pos, end_pos);
-
-        let pos = err.span.start();
-        let general_msg = err.msg.as_str();
+    /// Lexing the returned [`NormalizedInput`] does not need to care about
+    /// `\r`. The spans it produces are translated back to the original input
+    /// with [`NormalizedInput::original_span`] or
+    /// [`NormalizedInput::original_error`], so that errors point at the
+    /// original file when they are formatted by this reporter.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, reporter::ErrorReporter};
+    ///
+    /// let reporter = ErrorReporter::non_file_input("foo\r\nbar".to_string());
+    /// let normalized = reporter.normalized_line_endings();
+    ///
+    /// let bar = normalized.spanned_str().find("bar").unwrap();
+    /// assert_eq!(bar.span().start().offset(), 4);
+    ///
+    /// let report = normalized.original_error(AnnotatedError::new(bar.span(), "Unexpected bar"));
+    /// assert_eq!(report.span().start().offset(), 5);
+    /// assert_eq!(reporter.text_of(report.span()), Some("bar"));
+    /// ```
+    pub fn normalized_line_endings(&self) -> NormalizedInput {
+        let mut content = String::with_capacity(self.content.len());
+        let mut removed = Vec::new();
 
-        let errors = err.error_matrix();
+        let mut lines = self.content.split('\n').peekable();
+        while let Some(line) = lines.next() {
+            let is_last = lines.peek().is_none();
 
-        let first_line_number = start_pos.line() as usize;
+            match line.strip_suffix('\r') {
+                Some(line) if !is_last => {
+                    content.push_str(line);
+                    removed.push(content.len());
+                }
+                _ => content.push_str(line),
+            }
 
-        FormattedError {
-            pos,
-            first_line_number,
-            general_msg,
-            stream_name,
-            text,
-            errors,
+            if !is_last {
+                content.push('\n');
+            }
         }
-    }
-}
-
-/// An error object that can finally be displayed.
-///
-/// This structure is created by [`ErrorReporter::format_error`], and
-/// implements the [`Display`] trait.
-#[derive(Clone, Debug, PartialEq)]
-pub struct FormattedError<'a> {
-    pos: Position,
-    general_msg: &'a str,
-    stream_name: Option<&'a str>,
-    first_line_number: usize,
-    // Invariant: text.lines().count() == errors.len()
-    text: &'a str,
-    errors: Vec<Vec<Annotation<'a>>>,
-}
 
-impl<'a> FormattedError<'a> {
-    fn write_general_message(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        writeln!(f, "Error: {}", self.general_msg)
+        NormalizedInput { content, removed }
     }
 
-    fn write_position(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let (line, col) = (self.pos.line() + 1, self.pos.col() + 1);
-        match self.stream_name {
-            Some(name) => writeln!(f, " --> {}:{}:{}", name, line, col),
-            None => writeln!(f, " --> {}:{}", line, col),
-        }
+    // Returns the index of the line containing the byte at `offset`.
+    fn line_index(&self, offset: usize) -> usize {
+        // The first line always starts at offset 0, so the partition point is
+        // never 0.
+        self.line_starts.partition_point(|start| *start <= offset) - 1
     }
 
-    fn write_header(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        self.write_general_message(f)?;
-        self.write_position(f)
-    }
+    // Returns the content of the lines from first_line to last_line, both
+    // included, without the line return that follows the last line. As such,
+    // it contains exactly one line return per line crossed.
+    fn lines_between(&self, first_line: usize, last_line: usize) -> &str {
+        let start_idx = self.line_starts[first_line];
+        let end_idx = self
+            .line_starts
+            .get(last_line + 1)
+            .map_or(self.content.len(), |next_start| next_start - 1);
 
-    fn spacing(&self) -> usize {
-        self.errors
-            .iter()
-            .flatten()
-            .map(|ann| ann.text.len())
-            .max()
-            .unwrap_or(0)
+        &self.content[start_idx..end_idx]
     }
 
-    fn write_line(
-        content: &str,
-        spacing: usize,
-        number: usize,
-        f: &mut fmt::Formatter,
-    ) -> fmt::Result {
-        writeln!(f, " {:>3} | {} {}", number, " ".repeat(spacing), content)
+    // Returns the number of characters of a line, without its line return.
+    fn line_length(&self, line: usize) -> usize {
+        if line >= self.line_starts.len() {
+            return 0;
+        }
+
+        let text = self.lines_between(line, line);
+        text.strip_suffix('\r').unwrap_or(text).chars().count()
     }
 
-    fn write_underlines(
-        errs: &[Annotation<'_>],
-        spacing: usize,
-        f: &mut fmt::Formatter,
-    ) -> fmt::Result {
-        write!(f, "     | {} ", " ".repeat(spacing))?;
+    #[cfg(test)]
+    fn code_snippet_for(
+        &self,
+        start_pos: crate::span::Position,
+        end_pos: crate::span::Position,
+    ) -> &str {
+        let (start_offset, end_offset) = (start_pos.offset() as usize, end_pos.offset() as usize);
+        self.lines_between(self.line_index(start_offset), self.line_index(end_offset))
+    }
 
-        let mut current_col_number = 0;
-        for annotation in errs {
-            let delta = annotation.col_number - current_col_number;
-            let length = usize::max(1, annotation.length);
-            let chr = if length == 1 { "|" } else { "^" };
+    // Splits s in the units that are counted as a column.
+    fn column_units<'s>(&self, s: &'s str) -> Box<dyn Iterator<Item = &'s str> + 's> {
+        #[cfg(feature = "unicode")]
+        {
+            if self.grapheme_columns {
+                return Box::new(unicode_segmentation::UnicodeSegmentation::graphemes(
+                    s, true,
+                ));
+            }
+        }
 
-            write!(f, "{}{}", " ".repeat(delta), chr.repeat(length))?;
+        Box::new(
+            s.char_indices()
+                .map(move |(idx, chr)| &s[idx..idx + chr.len_utf8()]),
+        )
+    }
 
-            current_col_number += delta + length;
-        }
+    // Returns the column at which the character at char_col is displayed.
+    fn display_col(&self, line: &str, char_col: usize, tab_width: usize) -> usize {
+        let byte_idx = line
+            .char_indices()
+            .nth(char_col)
+            .map_or(line.len(), |(idx, _)| idx);
 
-        writeln!(f)
+        self.column_units(&line[..byte_idx])
+            .fold(0, |col, unit| next_display_col(col, unit, tab_width))
     }
 
-    fn write_error_line(
-        annotation: &Annotation,
-        spacing: usize,
-        other_annotations: &[Annotation],
-        f: &mut fmt::Formatter,
-    ) -> fmt::Result {
-        let pipe_len = spacing - annotation.text.len() + annotation.col_number + 1;
+    fn expand_tabs(&self, text: &str, tab_width: usize) -> String {
+        let mut expanded = String::with_capacity(text.len());
 
-        write!(f, "     | {}{}'", annotation.text, "-".repeat(pipe_len))?;
+        for (idx, line) in text.split('\n').enumerate() {
+            if idx != 0 {
+                expanded.push('\n');
+            }
 
-        let mut current_col_number = annotation.col_number;
+            let mut col = 0;
+            for unit in self.column_units(line) {
+                let next_col = next_display_col(col, unit, tab_width);
 
-        for annotation in other_annotations {
-            let delta = annotation.col_number - current_col_number - 1;
-            write!(f, "{}|", " ".repeat(delta))?;
+                if unit == "\t" {
+                    expanded.push_str(&" ".repeat(next_col - col));
+                } else {
+                    expanded.push_str(unit);
+                }
 
-            current_col_number = annotation.col_number;
+                col = next_col;
+            }
         }
 
-        writeln!(f)
+        expanded
     }
 
-    fn write_errors(
-        annotations: &[Annotation<'_>],
-        spacing: usize,
-        f: &mut fmt::Formatter,
-    ) -> fmt::Result {
-        Self::write_underlines(annotations, spacing, f)?;
+    /// Constructs a [`FormattedError`] from an [`AnnotatedError`].
+    ///
+    /// The returned value can finally be printed to the user. This is
+    /// equivalent to calling [`format_error_with`] with the default
+    /// [`FormatOptions`].
+    ///
+    /// [`format_error_with`]: ErrorReporter::format_error_with
+    pub fn format_error<'b>(&'b self, err: &'b AnnotatedError) -> FormattedError<'b> {
+        self.format_error_with(err, &FormatOptions::default())
+    }
 
-        for idx in 0..annotations.len() {
-            let annotation = &annotations[idx];
-            let annotations = &annotations[idx + 1..];
+    /// Constructs a [`FormattedError`] from an [`AnnotatedError`] whose
+    /// annotations may be located in other inputs.
+    ///
+    /// Each annotation added with [`AnnotatedError::with_annotation_in`] is
+    /// displayed after the error, in a snippet of the reporter of `sources`
+    /// whose path matches the annotation source. The annotations whose source
+    /// is not found are not displayed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, reporter::ErrorReporter};
+    ///
+    /// let main = ErrorReporter::input_file("main.rs".to_string(), "let x: u32 = f();".to_string());
+    /// let lib = ErrorReporter::input_file("lib.rs".to_string(), "fn f() -> String".to_string());
+    ///
+    /// let call = main.spanned_str().find("f()").unwrap();
+    /// let ret = lib.spanned_str().find("String").unwrap();
+    ///
+    /// let report = AnnotatedError::new(call.span(), "Mismatched types")
+    ///     .with_annotation_in("lib.rs", ret.span(), "expected because of this");
+    ///
+    /// let formatted = main.format_error_with_sources(&report, &[&lib]).to_string();
+    ///
+    /// assert!(formatted.contains(" --> lib.rs:1:11\n"));
+    /// ```
+    pub fn format_error_with_sources<'b>(
+        &'b self,
+        err: &'b AnnotatedError,
+        sources: &[&'b ErrorReporter<'b>],
+    ) -> FormattedError<'b> {
+        let mut formatted = self.format_error(err);
 
-            Self::write_error_line(annotation, spacing, annotations, f)?;
+        for (source, annotation) in err.foreign.iter() {
+            let annotation = if self.path() == Some(source.as_str()) {
+                Some(self.format_error(annotation))
+            } else {
+                sources
+                    .iter()
+                    .find(|reporter| reporter.path() == Some(source.as_str()))
+                    .map(|reporter| reporter.format_error(annotation))
+            };
+
+            if let Some(mut annotation) = annotation {
+                annotation.foreign = true;
+                formatted.related.push(annotation);
+            }
         }
 
-        Ok(())
+        formatted
     }
-}
-
-impl<'a> Display for FormattedError<'a> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        self.write_header(f)?;
 
-        let spacing = self.spacing();
+    /// Constructs a [`FormattedErrors`] from a sequence of [`AnnotatedError`].
+    ///
+    /// Each error is formatted with the default [`FormatOptions`]. The
+    /// returned value displays every error, followed by a summary line that
+    /// counts the errors and the warnings.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{
+    ///     error::{AnnotatedError, Severity},
+    ///     reporter::ErrorReporter,
+    /// };
+    ///
+    /// let reporter = ErrorReporter::non_file_input("foo bar".to_string());
+    /// let file = reporter.spanned_str();
+    ///
+    /// let errors = vec![
+    ///     AnnotatedError::new(file.find("foo").unwrap().span(), "Unknown foo"),
+    ///     AnnotatedError::new(file.find("bar").unwrap().span(), "Unused bar")
+    ///         .with_severity(Severity::Warning),
+    /// ];
+    ///
+    /// let formatted = reporter.format_errors(&errors);
+    ///
+    /// assert_eq!(formatted.summary(), "1 error, 1 warning emitted");
+    /// ```
+    pub fn format_errors<'b, I>(&'b self, errs: I) -> FormattedErrors<'b>
+    where
+        I: IntoIterator<Item = &'b AnnotatedError>,
+    {
+        let errors = errs.into_iter().map(|err| self.format_error(err)).collect();
 
-        writeln!(f, "     |")?;
+        FormattedErrors { errors }
+    }
 
-        for (idx, (line, errs)) in self.text.lines().zip(self.errors.iter()).enumerate() {
-            Self::write_line(line, spacing, idx + self.first_line_number + 1, f)?;
-            Self::write_errors(errs, spacing, f)?;
+    /// Constructs a [`FormattedGroup`] from a [`DiagnosticGroup`].
+    ///
+    /// Each child report is formatted with the default [`FormatOptions`]. The
+    /// returned value displays the group message once, followed by every
+    /// child report.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{
+    ///     error::{AnnotatedError, DiagnosticGroup},
+    ///     reporter::ErrorReporter,
+    /// };
+    ///
+    /// let reporter = ErrorReporter::non_file_input("foo bar".to_string());
+    /// let file = reporter.spanned_str();
+    ///
+    /// let group = DiagnosticGroup::new("2 errors in this file")
+    ///     .with_error(AnnotatedError::new(file.find("foo").unwrap().span(), "Unknown foo"))
+    ///     .with_error(AnnotatedError::new(file.find("bar").unwrap().span(), "Unknown bar"));
+    ///
+    /// let formatted = reporter.format_group(&group).to_string();
+    /// assert!(formatted.starts_with("Error: 2 errors in this file\n"));
+    /// ```
+    pub fn format_group<'b>(&'b self, group: &'b DiagnosticGroup) -> FormattedGroup<'b> {
+        let errors = group
+            .errors()
+            .iter()
+            .map(|err| self.format_error(err))
+            .collect();
 
-            writeln!(f, "     |")?;
+        FormattedGroup {
+            msg: group.msg.as_str(),
+            severity: group.severity,
+            errors,
         }
+    }
 
-        Ok(())
+    /// Bundles `err` with the reporter, so that it can be displayed later.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, reporter::ErrorReporter};
+    ///
+    /// let reporter = ErrorReporter::non_file_input("foo".to_string());
+    /// let report = AnnotatedError::new(reporter.spanned_str().span(), "Unknown foo");
+    ///
+    /// let diagnostic = reporter.diagnostic(report.clone());
+    ///
+    /// assert_eq!(
+    ///     diagnostic.to_string(),
+    ///     reporter.format_error(&report).to_string(),
+    /// );
+    /// ```
+    pub fn diagnostic(&self, err: AnnotatedError) -> Diagnostic<'_> {
+        Diagnostic {
+            reporter: self,
+            err,
+        }
     }
-}
 
-#[derive(Clone, Debug, PartialEq)]
-pub(crate) struct Annotation<'a> {
-    pub(crate) col_number: usize,
-    pub(crate) length: usize,
-    pub(crate) text: &'a str,
+    /// Constructs a [`FormattedError`] from an [`AnnotatedError`], using
+    /// `options` to tweak the rendering.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{
+    ///     error::AnnotatedError,
+    ///     reporter::{ErrorReporter, FormatOptions},
+    /// };
+    ///
+    /// let reporter = ErrorReporter::non_file_input("foo\nbar\nbaz".to_string());
+    /// let bar = reporter.spanned_str().find("bar").unwrap();
+    /// let report = AnnotatedError::new(bar.span(), "Unexpected bar");
+    ///
+    /// let mut options = FormatOptions::default();
+    /// options.context = 1;
+    /// options.header = false;
+    ///
+    /// let formatted = reporter.format_error_with(&report, &options).to_string();
+    ///
+    /// assert!(formatted.contains("   1 |                foo\n"));
+    /// assert!(formatted.contains("   3 |                baz\n"));
+    /// ```
+    pub fn format_error_with<'b>(
+        &'b self,
+        err: &'b AnnotatedError,
+        options: &FormatOptions,
+    ) -> FormattedError<'b> {
+        let (start_pos, end_pos) = err.bounds();
+        let stream_name = self.path();
+
+        let (start_line, end_line) = (
+            self.line_index(start_pos.offset() as usize),
+            self.line_index(end_pos.offset() as usize),
+        );
+        let first_line_number = start_line.saturating_sub(options.context);
+        let last_line_number = usize::min(end_line + options.context, self.line_starts.len() - 1);
+
+        let text = self.lines_between(first_line_number, last_line_number);
+
+        let pos = err.span.start();
+        let line = pos.line() as usize;
+        let general_msg = err.msg.as_str();
+
+        let matrix = err.error_matrix(|line| self.line_length(line as usize));
+
+        let mut errors = iter::repeat_with(Vec::new)
+            .take(start_line - first_line_number)
+            .chain(matrix)
+            .chain(iter::repeat_with(Vec::new).take(last_line_number - end_line))
+            .collect::<Vec<_>>();
+
+        for (line, annotations) in text.split('\n').zip(errors.iter_mut()) {
+            for annotation in annotations.iter_mut() {
+                let start = self.display_col(line, annotation.col_number, options.tab_width);
+                let end = self.display_col(
+                    line,
+                    annotation.col_number + annotation.length,
+                    options.tab_width,
+                );
+
+                annotation.col_number = start;
+                annotation.length = end - start;
+            }
+        }
+
+        let line_content = text.split('\n').nth(line - first_line_number).unwrap_or("");
+        let col = self.display_col(line_content, pos.col() as usize, options.tab_width);
+
+        let text = if options.tab_width != 0 && text.contains('\t') {
+            Cow::Owned(self.expand_tabs(text, options.tab_width))
+        } else {
+            Cow::Borrowed(text)
+        };
+
+        debug_assert_eq!(
+            text.split('\n').count(),
+            errors.len(),
+            "The code snippet and the error matrix have a different number of lines",
+        );
+
+        FormattedError {
+            line,
+            col,
+            first_line_number,
+            general_msg,
+            stream_name,
+            text,
+            errors,
+            ruler: options.ruler,
+            header: options.header,
+            stacked: false,
+            colors: options.colors,
+            index_base: if options.one_indexed { 1 } else { 0 },
+            max_message_width: options.max_message_width,
+            arrow: options.arrow,
+            gutter: match (options.charset, options.gutter) {
+                (Charset::Unicode, '|') => Charset::Unicode.vertical(),
+                (_, gutter) => gutter,
+            },
+            charset: options.charset,
+            padding: options.padding,
+            severity_colors: options.severity_colors,
+            max_annotations_per_line: options.max_annotations_per_line,
+            related: err
+                .related
+                .iter()
+                .map(|related| self.format_error_with(related, options))
+                .collect(),
+            suggestions: err
+                .suggestions
+                .iter()
+                .map(|suggestion| {
+                    let mut formatted = self.format_error_with(suggestion, options);
+
+                    // The help message is already displayed in the header, so
+                    // it is not repeated as the label of the suggested span.
+                    if formatted.primary_label {
+                        formatted
+                            .errors
+                            .iter_mut()
+                            .flatten()
+                            .for_each(|ann| ann.text = "");
+                    }
+                    formatted.help = true;
+
+                    formatted
+                })
+                .collect(),
+            footnotes: err
+                .notes
+                .iter()
+                .map(|note| ("note", note.as_str()))
+                .chain(err.helps.iter().map(|help| ("help", help.as_str())))
+                .collect(),
+            help: false,
+            foreign: false,
+            primary_label: err.is_empty(),
+            severity: err.severity,
+            #[cfg(feature = "serde")]
+            span: err.span,
+            #[cfg(feature = "serde")]
+            annotations: err.annotations().collect(),
+        }
+    }
+}
+
+fn line_starts(content: &str) -> Vec<usize> {
+    let line_returns = content
+        .char_indices()
+        .filter(|(_, c)| *c == '\n')
+        .map(|(idx, _)| idx + 1);
+
+    iter::once(0).chain(line_returns).collect()
+}
+
+// Returns the column that follows unit, assuming that unit is displayed at col.
+fn next_display_col(col: usize, unit: &str, tab_width: usize) -> usize {
+    if tab_width != 0 && unit == "\t" {
+        col + tab_width - col % tab_width
+    } else {
+        col + 1
+    }
+}
+
+// Escapes the message of a GitHub Actions workflow command.
+fn escape_github_data(data: &str) -> String {
+    data.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+// Escapes a property value of a GitHub Actions workflow command.
+fn escape_github_property(value: &str) -> String {
+    escape_github_data(value)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+// ANSI escape code used to reset the style after painted text.
+const RESET_STYLE: &str = "\x1b[0m";
+
+/// A color in which a part of a formatted error can be painted.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Color {
+    /// Black.
+    Black,
+    /// Red.
+    Red,
+    /// Green.
+    Green,
+    /// Yellow.
+    Yellow,
+    /// Blue.
+    Blue,
+    /// Magenta.
+    Magenta,
+    /// Cyan.
+    Cyan,
+    /// White.
+    White,
+}
+
+impl Color {
+    // Returns the ANSI escape code that paints text in bold and in this color.
+    fn ansi_style(self) -> String {
+        let code = match self {
+            Color::Black => 30,
+            Color::Red => 31,
+            Color::Green => 32,
+            Color::Yellow => 33,
+            Color::Blue => 34,
+            Color::Magenta => 35,
+            Color::Cyan => 36,
+            Color::White => 37,
+        };
+
+        format!("\x1b[1;{}m", code)
+    }
+
+    #[cfg(feature = "termcolor")]
+    fn to_termcolor(self) -> termcolor::Color {
+        match self {
+            Color::Black => termcolor::Color::Black,
+            Color::Red => termcolor::Color::Red,
+            Color::Green => termcolor::Color::Green,
+            Color::Yellow => termcolor::Color::Yellow,
+            Color::Blue => termcolor::Color::Blue,
+            Color::Magenta => termcolor::Color::Magenta,
+            Color::Cyan => termcolor::Color::Cyan,
+            Color::White => termcolor::Color::White,
+        }
+    }
+}
+
+/// Whether ANSI escape codes are written by [`FormattedError::write_ansi`].
+///
+/// The mode is resolved with [`ColorMode::enabled`], given whether the
+/// output is a terminal.
+///
+/// This type is available only when the `std` feature is enabled.
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ColorMode {
+    /// Escape codes are always written, even if the target is not a terminal.
+    Always,
+    /// Escape codes are written only if the target is a terminal.
+    Auto,
+    /// Escape codes are never written.
+    Never,
+}
+
+#[cfg(feature = "std")]
+impl ColorMode {
+    /// Returns whether escape codes must be written to an output, given
+    /// whether this output is a terminal.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::io::{self, IsTerminal};
+    ///
+    /// use lisbeth_error::reporter::ColorMode;
+    ///
+    /// let colors = ColorMode::Auto.enabled(io::stderr().is_terminal());
+    ///
+    /// assert!(ColorMode::Always.enabled(false));
+    /// assert!(!ColorMode::Never.enabled(true));
+    /// ```
+    pub fn enabled(self, is_terminal: bool) -> bool {
+        match self {
+            ColorMode::Always => true,
+            ColorMode::Auto => is_terminal,
+            ColorMode::Never => false,
+        }
+    }
+}
+
+/// The set of characters used to draw code snippets.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum Charset {
+    /// Snippets are drawn with ASCII characters only, such as `|`, `-` and
+    /// `'`. This is the default charset.
+    #[default]
+    Ascii,
+    /// Snippets are drawn with Unicode box-drawing characters, such as `│`,
+    /// `─` and `╯`. Like their ASCII counterparts, these characters are one
+    /// column wide.
+    Unicode,
+}
+
+impl Charset {
+    // Returns the character used for vertical lines: the gutter, the
+    // connectors and the underline of single characters.
+    fn vertical(self) -> char {
+        match self {
+            Charset::Ascii => '|',
+            Charset::Unicode => '│',
+        }
+    }
+
+    // Returns the character used for horizontal lines: the line that goes
+    // from a label to its annotation, and the underline of suggestions.
+    fn horizontal(self) -> char {
+        match self {
+            Charset::Ascii => '-',
+            Charset::Unicode => '─',
+        }
+    }
+
+    // Returns the character that ends the line that goes from a label to its
+    // annotation.
+    fn corner(self) -> char {
+        match self {
+            Charset::Ascii => '\'',
+            Charset::Unicode => '╯',
+        }
+    }
+}
+
+/// The color associated to each [`Severity`].
+///
+/// The severity title and the underlines of a report are painted in the color
+/// of its severity. The default colors are the ones used by `rustc`.
+///
+/// # Example
+///
+/// ```rust
+/// use lisbeth_error::{
+///     error::Severity,
+///     reporter::{Color, SeverityColors},
+/// };
+///
+/// let colors = SeverityColors {
+///     warning: Color::Magenta,
+///     ..SeverityColors::default()
+/// };
+///
+/// assert_eq!(colors.get(Severity::Error), Color::Red);
+/// assert_eq!(colors.get(Severity::Warning), Color::Magenta);
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct SeverityColors {
+    /// The color of errors.
+    ///
+    /// Defaults to [`Color::Red`].
+    pub error: Color,
+
+    /// The color of warnings.
+    ///
+    /// Defaults to [`Color::Yellow`].
+    pub warning: Color,
+}
+
+impl SeverityColors {
+    /// Returns the color associated to `severity`.
+    pub fn get(&self, severity: Severity) -> Color {
+        match severity {
+            Severity::Error => self.error,
+            Severity::Warning => self.warning,
+        }
+    }
+}
+
+impl Default for SeverityColors {
+    fn default() -> SeverityColors {
+        SeverityColors {
+            error: Color::Red,
+            warning: Color::Yellow,
+        }
+    }
+}
+
+/// A set of options that control how a [`FormattedError`] is rendered.
+///
+/// The default options reproduce the output of [`ErrorReporter::format_error`].
+/// New options may be added in the future, so this structure can not be
+/// created with a struct literal. Options should be set on a default value
+/// instead.
+///
+/// # Example
+///
+/// ```rust
+/// use lisbeth_error::reporter::FormatOptions;
+///
+/// let mut options = FormatOptions::default();
+/// options.ruler = true;
+/// options.tab_width = 4;
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct FormatOptions {
+    /// Whether the output should be colored with ANSI escape codes.
+    ///
+    /// Defaults to `false`.
+    pub colors: bool,
+
+    /// The number of lines displayed before and after the annotated lines.
+    ///
+    /// Defaults to `0`.
+    pub context: usize,
+
+    /// Whether a column ruler should be displayed above the code snippet. See
+    /// [`FormattedError::with_ruler`].
+    ///
+    /// Defaults to `false`.
+    pub ruler: bool,
+
+    /// Whether the general message and the position should be displayed. See
+    /// [`FormattedError::with_header`].
+    ///
+    /// Defaults to `true`.
+    pub header: bool,
+
+    /// Whether line and column numbers start at one instead of zero.
+    ///
+    /// Defaults to `true`.
+    pub one_indexed: bool,
+
+    /// The distance between two tab stops. Tabulations are expanded to spaces
+    /// up to the next tab stop. Tabulations are left untouched and counted as
+    /// a single column when it is zero.
+    ///
+    /// Defaults to `0`.
+    pub tab_width: usize,
+
+    /// The maximum number of characters of the general message displayed in
+    /// the header. Longer messages are truncated and end with `…`. Messages
+    /// are never truncated when it is `None`.
+    ///
+    /// Defaults to `None`.
+    pub max_message_width: Option<usize>,
+
+    /// The arrow displayed before the position of the error.
+    ///
+    /// Defaults to `"-->"`.
+    pub arrow: &'static str,
+
+    /// The character that separates line numbers from the code snippet.
+    ///
+    /// Defaults to `'|'`.
+    pub gutter: char,
+
+    /// The color of each severity, used when the output is colored.
+    ///
+    /// Defaults to [`SeverityColors::default`].
+    pub severity_colors: SeverityColors,
+
+    /// The maximum number of annotations displayed on each line. The
+    /// annotations that come after are hidden, and a `and N more` note is
+    /// displayed instead. Every annotation is displayed when it is `None`.
+    ///
+    /// Defaults to `None`.
+    pub max_annotations_per_line: Option<usize>,
+
+    /// The characters used to draw the code snippet. When it is
+    /// [`Charset::Unicode`], the default `'|'` gutter is drawn as `'│'`.
+    ///
+    /// Defaults to [`Charset::Ascii`].
+    pub charset: Charset,
+
+    /// Whether a blank line should be displayed before and after the whole
+    /// diagnostic, so that consecutive diagnostics are visually separated.
+    ///
+    /// Defaults to `false`.
+    pub padding: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> FormatOptions {
+        FormatOptions {
+            colors: false,
+            context: 0,
+            ruler: false,
+            header: true,
+            one_indexed: true,
+            tab_width: 0,
+            max_message_width: None,
+            arrow: "-->",
+            gutter: '|',
+            severity_colors: SeverityColors::default(),
+            max_annotations_per_line: None,
+            charset: Charset::Ascii,
+            padding: false,
+        }
+    }
+}
+
+/// An error object that can finally be displayed.
+///
+/// This structure is created by [`ErrorReporter::format_error`], and
+/// implements the [`Display`] trait.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FormattedError<'a> {
+    // Zero-indexed line and column of the error.
+    line: usize,
+    col: usize,
+    general_msg: &'a str,
+    stream_name: Option<&'a str>,
+    first_line_number: usize,
+    // Invariant: text.split('\n').count() == errors.len()
+    text: Cow<'a, str>,
+    errors: Vec<Vec<Annotation<'a>>>,
+    ruler: bool,
+    header: bool,
+    stacked: bool,
+    colors: bool,
+    // Number of the first line and of the first column.
+    index_base: usize,
+    max_message_width: Option<usize>,
+    arrow: &'static str,
+    gutter: char,
+    charset: Charset,
+    // Whether blank lines surround the whole diagnostic. Related errors and
+    // suggestions are part of the diagnostic, so they are never padded.
+    padding: bool,
+    severity_colors: SeverityColors,
+    max_annotations_per_line: Option<usize>,
+    related: Vec<FormattedError<'a>>,
+    suggestions: Vec<FormattedError<'a>>,
+    // The notes and the help messages displayed after the snippet, along with
+    // their kind. Notes come first.
+    footnotes: Vec<(&'static str, &'a str)>,
+    // Whether the error is a suggestion, displayed as a help message.
+    help: bool,
+    // Whether the error is an annotation located in another input, displayed
+    // with its position only.
+    foreign: bool,
+    // Whether the only annotation is the primary span, labelled with the
+    // general message.
+    primary_label: bool,
+    severity: Severity,
+    // The primary span, used to locate the error in LSP diagnostics.
+    #[cfg(feature = "serde")]
+    span: Span,
+    // The spans of the annotations, along with their text. Unlike the columns
+    // stored in errors, they are not affected by the display options.
+    #[cfg(feature = "serde")]
+    annotations: Vec<(Span, &'a str)>,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+impl<'a> FormattedError<'a> {
+    /// Sets whether a column ruler should be displayed above the code snippet.
+    ///
+    /// The ruler is disabled by default. When enabled, it displays the tens
+    /// and the ones of each column number, so that the position of each caret
+    /// can be read easily on long lines.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, reporter::ErrorReporter};
+    ///
+    /// let reporter = ErrorReporter::non_file_input("hello".to_string());
+    /// let report = AnnotatedError::new(reporter.spanned_str().span(), "Greeting found");
+    ///
+    /// let formatted = reporter.format_error(&report).with_ruler(true);
+    /// println!("{}", formatted);
+    /// ```
+    ///
+    /// This will print to STDOUT:
+    ///
+    /// ```none
+    /// Error: Greeting found
+    ///  --> 1:1
+    ///      |
+    ///      |  12345
+    ///    1 |  hello
+    ///      |  ^^^^^
+    ///      |
+    /// ```
+    pub fn with_ruler(mut self, ruler: bool) -> FormattedError<'a> {
+        self.ruler = ruler;
+        self.related = self
+            .related
+            .into_iter()
+            .map(|related| related.with_ruler(ruler))
+            .collect();
+        self.suggestions = self
+            .suggestions
+            .into_iter()
+            .map(|suggestion| suggestion.with_ruler(ruler))
+            .collect();
+        self
+    }
+
+    /// Sets whether the header should be displayed.
+    ///
+    /// The header is composed of the general message and the position of the
+    /// error. It is displayed by default. Hiding it allows to embed the
+    /// annotated code snippet in a larger report.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, reporter::ErrorReporter};
+    ///
+    /// let reporter = ErrorReporter::non_file_input("hello".to_string());
+    /// let report = AnnotatedError::new(reporter.spanned_str().span(), "Greeting found");
+    ///
+    /// let formatted = reporter.format_error(&report).with_header(false);
+    /// assert!(formatted.to_string().starts_with("     |\n"));
+    /// ```
+    pub fn with_header(mut self, header: bool) -> FormattedError<'a> {
+        self.header = header;
+        self
+    }
+
+    /// Sets whether annotations should be laid out in stacked rows.
+    ///
+    /// By default, annotation messages are written in a margin on the left of
+    /// the code snippet. This layout does not work when several annotations
+    /// start on the same column or overlap.
+    ///
+    /// When the stacked layout is enabled, each message is written on its own
+    /// row, below its underline. Messages are written from the rightmost
+    /// annotation to the leftmost one, so that no connector crosses a message.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, reporter::ErrorReporter};
+    ///
+    /// let reporter = ErrorReporter::non_file_input("The cat are on the table.".to_string());
+    /// let file = reporter.spanned_str();
+    ///
+    /// let cat = file.split_at(4).1.split_at(3).0;
+    /// let are = file.split_at(8).1.split_at(3).0;
+    ///
+    /// let report = AnnotatedError::new(are.span(), "Conjugation error")
+    ///     .with_annotation(cat.span(), "`cat` is singular,")
+    ///     .with_annotation(are.span(), "but `are` is used only for plural subject");
+    ///
+    /// let formatted = reporter.format_error(&report).with_stacked_layout(true);
+    /// println!("{}", formatted);
+    /// ```
+    ///
+    /// This will print to STDOUT:
+    ///
+    /// ```none
+    /// Error: Conjugation error
+    ///  --> 1:9
+    ///      |
+    ///    1 |  The cat are on the table.
+    ///      |      ^^^ ^^^
+    ///      |      |   |
+    ///      |      |   but `are` is used only for plural subject
+    ///      |      `cat` is singular,
+    ///      |
+    /// ```
+    pub fn with_stacked_layout(mut self, stacked: bool) -> FormattedError<'a> {
+        self.stacked = stacked;
+        self.related = self
+            .related
+            .into_iter()
+            .map(|related| related.with_stacked_layout(stacked))
+            .collect();
+        self.suggestions = self
+            .suggestions
+            .into_iter()
+            .map(|suggestion| suggestion.with_stacked_layout(stacked))
+            .collect();
+        self
+    }
+
+    /// Returns the severity of the error.
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    /// Returns the GitHub Actions workflow command that displays the error as
+    /// an annotation.
+    ///
+    /// The command has the form `::error file={name},line={line},col={col}::{message}`.
+    /// Errors are reported with the `error` command, warnings with the
+    /// `warning` command and suggestions with the `notice` command. Lines and
+    /// columns always start at one, as expected by GitHub, and the special
+    /// characters of the message and of the file name are escaped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, reporter::ErrorReporter};
+    ///
+    /// let reporter = ErrorReporter::input_file("docs.txt".to_string(), "foo".to_string());
+    /// let report = AnnotatedError::new(reporter.spanned_str().span(), "Unknown word");
+    ///
+    /// assert_eq!(
+    ///     reporter.format_error(&report).to_github_annotation(),
+    ///     "::error file=docs.txt,line=1,col=1::Unknown word",
+    /// );
+    /// ```
+    pub fn to_github_annotation(&self) -> String {
+        let command = match (self.help, self.severity) {
+            (true, _) => "notice",
+            (false, Severity::Error) => "error",
+            (false, Severity::Warning) => "warning",
+        };
+
+        let position = format!("line={},col={}", self.line + 1, self.col + 1);
+        let properties = match self.stream_name {
+            Some(name) => format!("file={},{}", escape_github_property(name), position),
+            None => position,
+        };
+
+        format!(
+            "::{} {}::{}",
+            command,
+            properties,
+            escape_github_data(self.general_msg)
+        )
+    }
+
+    /// Returns a compact representation of the error, without the code
+    /// snippet.
+    ///
+    /// The first line contains the position and the general message of the
+    /// error. It is followed by one line per annotation, containing its position
+    /// and its text. Annotations located in other inputs are reported as notes
+    /// too. This format is suitable for logs.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, reporter::ErrorReporter};
+    ///
+    /// let reporter = ErrorReporter::input_file(
+    ///     "docs.txt".to_string(),
+    ///     "The cat are on the table.".to_string(),
+    /// );
+    /// let file = reporter.spanned_str();
+    ///
+    /// let cat = file.split_at(4).1.split_at(3).0;
+    /// let are = file.split_at(8).1.split_at(3).0;
+    ///
+    /// let report = AnnotatedError::new(are.span(), "Conjugation error")
+    ///     .with_annotation(cat.span(), "`cat` is singular,")
+    ///     .with_annotation(are.span(), "but `are` is used only for plural subject");
+    ///
+    /// assert_eq!(
+    ///     reporter.format_error(&report).to_plain_string(),
+    ///     "docs.txt:1:9: error: Conjugation error\n\
+    ///      docs.txt:1:5: note: `cat` is singular,\n\
+    ///      docs.txt:1:9: note: but `are` is used only for plural subject\n",
+    /// );
+    /// ```
+    pub fn to_plain_string(&self) -> String {
+        let mut buf = String::new();
+
+        let (line, col) = (self.line + self.index_base, self.col + self.index_base);
+        let kind = if self.help {
+            "help"
+        } else if self.foreign {
+            "note"
+        } else {
+            self.severity.name()
+        };
+        self.write_plain_line(&mut buf, line, col, kind, self.general_msg);
+
+        for (idx, annotations) in self.notes().iter().enumerate() {
+            let line = self.first_line_number + idx + self.index_base;
+
+            // Annotations without text are only displayed as underlines.
+            for annotation in annotations.iter().filter(|ann| !ann.text.is_empty()) {
+                let col = annotation.col_number + self.index_base;
+                self.write_plain_line(&mut buf, line, col, "note", annotation.text);
+            }
+        }
+
+        for (kind, msg) in self.footnotes.iter() {
+            let (line, col) = (self.line + self.index_base, self.col + self.index_base);
+            self.write_plain_line(&mut buf, line, col, kind, msg);
+        }
+
+        for suggestion in self.suggestions.iter() {
+            buf.push_str(&suggestion.to_plain_string());
+        }
+
+        for related in self.related.iter() {
+            buf.push_str(&related.to_plain_string());
+        }
+
+        buf
+    }
+
+    /// Appends the rendered error to `buf`.
+    ///
+    /// The output is the same as the one produced by the [`Display`]
+    /// implementation. Appending to an existing buffer allows to format many
+    /// errors with fewer allocations, especially when `buf` is created with a
+    /// sufficient capacity.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, reporter::ErrorReporter};
+    ///
+    /// let reporter = ErrorReporter::non_file_input("hello".to_string());
+    /// let report = AnnotatedError::new(reporter.spanned_str().span(), "Greeting found");
+    /// let formatted = reporter.format_error(&report);
+    ///
+    /// let mut buf = String::with_capacity(1024);
+    /// formatted.render_into(&mut buf);
+    ///
+    /// assert_eq!(buf, formatted.to_string());
+    /// ```
+    pub fn render_into(&self, buf: &mut String) {
+        // Writing to a String never fails.
+        let _ = write!(buf, "{}", self);
+    }
+
+    /// Writes the error to a [`termcolor::WriteColor`] target.
+    ///
+    /// The header, the gutter and the underlines are painted with color
+    /// specifications, which allows colored output on every platform supported
+    /// by `termcolor`, including Windows consoles. Whether colors are actually
+    /// displayed is decided by `w`, so the `colors` field of [`FormatOptions`]
+    /// is ignored. Without colors, the output is the same as the one produced
+    /// by the [`Display`] implementation.
+    ///
+    /// This method is available only when the `termcolor` feature is enabled.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, reporter::ErrorReporter};
+    /// use termcolor::{ColorChoice, StandardStream};
+    ///
+    /// let reporter = ErrorReporter::non_file_input("hello".to_string());
+    /// let report = AnnotatedError::new(reporter.spanned_str().span(), "Greeting found");
+    ///
+    /// let mut stderr = StandardStream::stderr(ColorChoice::Auto);
+    /// reporter.format_error(&report).write_colored(&mut stderr).unwrap();
+    /// ```
+    #[cfg(feature = "termcolor")]
+    pub fn write_colored<W: termcolor::WriteColor>(&self, w: &mut W) -> io::Result<()> {
+        let mut output = ColorOutput { w, error: None };
+
+        match self.render_padded(&mut output) {
+            Ok(()) => Ok(()),
+            Err(_) => Err(output
+                .error
+                .unwrap_or_else(|| IOError::other("formatting error"))),
+        }
+    }
+
+    /// Writes the error to `w`, painted with ANSI escape codes if `colors`
+    /// is `true`.
+    ///
+    /// `colors` is usually obtained from a [`ColorMode`], so that escape codes
+    /// are written only if the output is a terminal and redirecting it to a
+    /// file does not fill it with escape codes. The `colors` field of
+    /// [`FormatOptions`] is ignored.
+    ///
+    /// This method is available only when the `std` feature is enabled.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::io::{self, IsTerminal};
+    ///
+    /// use lisbeth_error::{
+    ///     error::AnnotatedError,
+    ///     reporter::{ColorMode, ErrorReporter},
+    /// };
+    ///
+    /// let reporter = ErrorReporter::non_file_input("hello".to_string());
+    /// let report = AnnotatedError::new(reporter.spanned_str().span(), "Greeting found");
+    ///
+    /// let stderr = io::stderr();
+    /// let colors = ColorMode::Auto.enabled(stderr.is_terminal());
+    ///
+    /// reporter
+    ///     .format_error(&report)
+    ///     .write_ansi(&mut stderr.lock(), colors)
+    ///     .unwrap();
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn write_ansi<W: io::Write>(&self, w: &mut W, colors: bool) -> io::Result<()> {
+        write!(w, "{}", Painted { err: self, colors })
+    }
+
+    /// Returns a JSON representation of the error, suitable for tools that
+    /// consume diagnostics.
+    ///
+    /// The returned object contains the general message, the severity, the
+    /// file path, the position of the error, each annotation and the related
+    /// errors. Lines and columns follow the indexing of the [`FormatOptions`]
+    /// used to format the error.
+    ///
+    /// This method is available only when the `serde` feature is enabled.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, reporter::ErrorReporter};
+    ///
+    /// let reporter = ErrorReporter::non_file_input("foo".to_string());
+    /// let report = AnnotatedError::new(reporter.spanned_str().span(), "Unexpected foo");
+    ///
+    /// assert_eq!(
+    ///     reporter.format_error(&report).to_json(),
+    ///     r#"{"message":"Unexpected foo","severity":"error","file":null,"line":1,"col":1,"annotations":[],"related":[]}"#,
+    /// );
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        // Serializing these structures can not fail.
+        serde_json::to_string(&self.json_diagnostic()).unwrap()
+    }
+
+    /// Returns a diagnostic in the shape of the Language Server Protocol.
+    ///
+    /// The range of the diagnostic is the primary span of the error. The
+    /// annotations and the related errors are reported as related
+    /// information. Lines and characters are zero-indexed, regardless of the
+    /// [`FormatOptions`] used to format the error.
+    ///
+    /// This method is available only when the `serde` feature is enabled.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, reporter::ErrorReporter};
+    ///
+    /// let reporter = ErrorReporter::non_file_input("foo".to_string());
+    /// let report = AnnotatedError::new(reporter.spanned_str().span(), "Unexpected foo");
+    ///
+    /// let diagnostic = reporter.format_error(&report).to_lsp_diagnostic();
+    ///
+    /// assert_eq!(diagnostic.range.end.character, 3);
+    /// assert_eq!(diagnostic.severity, 1);
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn to_lsp_diagnostic(&self) -> lsp::Diagnostic {
+        let uri = self.stream_name.unwrap_or_default();
+
+        let annotations = self
+            .annotations
+            .iter()
+            .filter(|(_, text)| !text.is_empty())
+            .map(|(span, text)| lsp::DiagnosticRelatedInformation {
+                location: lsp::Location {
+                    uri: uri.to_string(),
+                    range: lsp_range(*span),
+                },
+                message: text.to_string(),
+            });
+
+        let related = self
+            .related
+            .iter()
+            .map(|related| lsp::DiagnosticRelatedInformation {
+                location: lsp::Location {
+                    uri: related.stream_name.unwrap_or_default().to_string(),
+                    range: lsp_range(related.span),
+                },
+                message: related.general_msg.to_string(),
+            });
+
+        lsp::Diagnostic {
+            range: lsp_range(self.span),
+            severity: lsp::severity_code(self.severity),
+            message: self.general_msg.to_string(),
+            related_information: annotations.chain(related).collect(),
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    fn json_diagnostic(&self) -> json::Diagnostic<'_> {
+        let annotations = self
+            .notes()
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, annotations)| {
+                let line = self.first_line_number + idx + self.index_base;
+
+                annotations
+                    .iter()
+                    .filter(|ann| !ann.text.is_empty())
+                    .map(move |ann| json::Annotation {
+                        line,
+                        col: ann.col_number + self.index_base,
+                        length: ann.length,
+                        text: ann.text,
+                    })
+            })
+            .collect();
+
+        json::Diagnostic {
+            message: self.general_msg,
+            severity: self.severity.name(),
+            file: self.stream_name,
+            line: self.line + self.index_base,
+            col: self.col + self.index_base,
+            annotations,
+            related: self.related.iter().map(Self::json_diagnostic).collect(),
+        }
+    }
+
+    // Returns the annotations that are reported as notes, outside of the code
+    // snippet. A label that repeats the general message is not a note.
+    fn notes(&self) -> &[Vec<Annotation<'a>>] {
+        if self.primary_label {
+            &[]
+        } else {
+            &self.errors
+        }
+    }
+
+    fn write_plain_line(&self, buf: &mut String, line: usize, col: usize, kind: &str, msg: &str) {
+        // Writing to a String never fails.
+        let _ = match self.stream_name {
+            Some(name) => write!(buf, "{}:{}:{}: {}", name, line, col, kind),
+            None => write!(buf, "{}:{}: {}", line, col, kind),
+        };
+
+        let _ = if msg.is_empty() {
+            writeln!(buf)
+        } else {
+            writeln!(buf, ": {}", msg)
+        };
+    }
+
+    fn write_general_message(&self, f: &mut dyn Output) -> fmt::Result {
+        // When no general message is provided, we print the severity alone
+        // instead of leaving a dangling colon.
+        let title = match self.severity {
+            Severity::Error => "Error",
+            Severity::Warning => "Warning",
+        };
+
+        self.write_painted(title, f)?;
+
+        if self.general_msg.is_empty() {
+            writeln!(f)
+        } else {
+            writeln!(f, ": {}", self.displayed_message())
+        }
+    }
+
+    // Returns the general message, truncated to the maximum message width.
+    fn displayed_message(&self) -> Cow<'a, str> {
+        let max = match self.max_message_width {
+            Some(max) if self.general_msg.chars().count() > max => max,
+            _ => return Cow::Borrowed(self.general_msg),
+        };
+
+        let mut truncated = self
+            .general_msg
+            .chars()
+            .take(max.saturating_sub(1))
+            .collect::<String>();
+        truncated.push('…');
+
+        Cow::Owned(truncated)
+    }
+
+    fn write_position(&self, f: &mut dyn Output) -> fmt::Result {
+        let (line, col) = (self.line + self.index_base, self.col + self.index_base);
+        match self.stream_name {
+            Some(name) => {
+                f.write_styled(Style::Gutter, &format!(" {}", self.arrow))?;
+                writeln!(f, " {}:{}:{}", name, line, col)
+            }
+            None => {
+                f.write_styled(Style::Gutter, &format!(" {}", self.arrow))?;
+                writeln!(f, " {}:{}", line, col)
+            }
+        }
+    }
+
+    fn write_header(&self, f: &mut dyn Output) -> fmt::Result {
+        if self.help {
+            return writeln!(f, "help: {}", self.general_msg);
+        }
+
+        if self.foreign {
+            return self.write_position(f);
+        }
+
+        self.write_general_message(f)?;
+        self.write_position(f)
+    }
+
+    // Returns the character used to underline an annotation. Empty spans of
+    // suggestions are insertion points, which are marked with a caret.
+    fn underline_char(&self, length: usize) -> char {
+        match (self.help, length) {
+            (true, 0) => '^',
+            (true, _) => self.charset.horizontal(),
+            (false, 0) | (false, 1) => self.charset.vertical(),
+            (false, _) => '^',
+        }
+    }
+
+    fn spacing(&self) -> usize {
+        self.errors
+            .iter()
+            .flat_map(|errs| self.displayed_annotations(errs).0)
+            .map(|ann| ann.text.len())
+            .max()
+            .unwrap_or(0)
+    }
+
+    // Unlike str::lines, this returns an empty line when the snippet ends with
+    // a line return, so that there is always one line per error matrix row.
+    fn snippet_lines(&self) -> impl Iterator<Item = &str> + '_ {
+        self.text
+            .split('\n')
+            .map(|line| line.strip_suffix('\r').unwrap_or(line))
+    }
+
+    fn write_ruler(&self, spacing: usize, f: &mut dyn Output) -> fmt::Result {
+        let width = self
+            .snippet_lines()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        let columns = self.index_base..width + self.index_base;
+
+        if width >= 10 {
+            let tens = columns
+                .clone()
+                .map(|col| match col % 10 {
+                    0 => char::from(b'0' + (col / 10 % 10) as u8),
+                    _ => ' ',
+                })
+                .collect::<String>();
+
+            self.write_gutter(None, f)?;
+            writeln!(f, " {} {}", " ".repeat(spacing), tens.trim_end())?;
+        }
+
+        let ones = columns
+            .map(|col| char::from(b'0' + (col % 10) as u8))
+            .collect::<String>();
+
+        self.write_gutter(None, f)?;
+        writeln!(f, " {} {}", " ".repeat(spacing), ones)
+    }
+
+    fn write_line(
+        &self,
+        content: &str,
+        spacing: usize,
+        number: usize,
+        f: &mut dyn Output,
+    ) -> fmt::Result {
+        self.write_gutter(Some(number), f)?;
+        writeln!(f, " {} {}", " ".repeat(spacing), content)
+    }
+
+    // Writes the left part of a snippet row, which contains the line number,
+    // if any, and a pipe.
+    fn write_gutter(&self, number: Option<usize>, f: &mut dyn Output) -> fmt::Result {
+        match number {
+            Some(number) => {
+                f.write_styled(Style::Gutter, &format!(" {:>3} {}", number, self.gutter))
+            }
+            None => f.write_styled(Style::Gutter, &format!("     {}", self.gutter)),
+        }
+    }
+
+    // Writes text in the color of the severity, if colors are enabled.
+    fn write_painted(&self, text: &str, f: &mut dyn Output) -> fmt::Result {
+        f.write_styled(
+            Style::Severity(self.severity_colors.get(self.severity)),
+            text,
+        )
+    }
+
+    fn write_underlines(
+        &self,
+        errs: &[Annotation<'_>],
+        spacing: usize,
+        f: &mut dyn Output,
+    ) -> fmt::Result {
+        self.write_gutter(None, f)?;
+        write!(f, " {} ", " ".repeat(spacing))?;
+
+        // Annotations may overlap, for instance when a multiline annotation
+        // starts on a line that contains other annotations. Later annotations
+        // are drawn over the previous ones.
+        let mut underlines = Vec::new();
+        for annotation in errs {
+            let length = usize::max(1, annotation.length);
+            let chr = self.underline_char(annotation.length);
+
+            let end = annotation.col_number + length;
+            if underlines.len() < end {
+                underlines.resize(end, ' ');
+            }
+
+            underlines[annotation.col_number..end]
+                .iter_mut()
+                .for_each(|c| *c = chr);
+        }
+
+        self.write_painted(&underlines.into_iter().collect::<String>(), f)?;
+        writeln!(f)
+    }
+
+    fn write_error_line(
+        &self,
+        annotation: &Annotation,
+        spacing: usize,
+        other_annotations: &[Annotation],
+        f: &mut dyn Output,
+    ) -> fmt::Result {
+        // The spacing is the length of the longest annotation text, so that
+        // every text fits in the margin.
+        debug_assert!(
+            spacing >= annotation.text.len(),
+            "Annotation text is longer than the spacing",
+        );
+
+        let pipe_len = (spacing + annotation.col_number + 1).saturating_sub(annotation.text.len());
+
+        self.write_gutter(None, f)?;
+        let pipe = iter::repeat_n(self.charset.horizontal(), pipe_len).collect::<String>();
+        write!(f, " {}{}{}", annotation.text, pipe, self.charset.corner())?;
+
+        let mut current_col_number = annotation.col_number;
+
+        for annotation in other_annotations {
+            // Annotations that start on a column that already has a connector
+            // share it.
+            if annotation.col_number <= current_col_number {
+                continue;
+            }
+
+            let delta = annotation.col_number.saturating_sub(current_col_number + 1);
+            write!(f, "{}{}", " ".repeat(delta), self.charset.vertical())?;
+
+            current_col_number = annotation.col_number;
+        }
+
+        writeln!(f)
+    }
+
+    // Returns the annotations of a line that must be displayed, sorted by
+    // column, and the number of annotations that are hidden.
+    fn displayed_annotations(&self, errs: &[Annotation<'a>]) -> (Vec<Annotation<'a>>, usize) {
+        // Annotations must be sorted by column to be rendered.
+        let mut errs = errs.to_vec();
+        errs.sort_by_key(|ann| ann.col_number);
+
+        let hidden = match self.max_annotations_per_line {
+            Some(max) if errs.len() > max => errs.len() - max,
+            _ => 0,
+        };
+
+        errs.truncate(errs.len() - hidden);
+
+        (errs, hidden)
+    }
+
+    fn write_errors(
+        &self,
+        annotations: &[Annotation<'_>],
+        spacing: usize,
+        f: &mut dyn Output,
+    ) -> fmt::Result {
+        self.write_underlines(annotations, spacing, f)?;
+
+        // Annotations without text are only underlined.
+        let annotations = &Self::labelled(annotations)[..];
+
+        for idx in 0..annotations.len() {
+            let annotation = &annotations[idx];
+            let annotations = &annotations[idx + 1..];
+
+            self.write_error_line(annotation, spacing, annotations, f)?;
+        }
+
+        Ok(())
+    }
+
+    fn labelled<'b>(annotations: &[Annotation<'b>]) -> Vec<Annotation<'b>> {
+        annotations
+            .iter()
+            .filter(|annotation| !annotation.text.is_empty())
+            .cloned()
+            .collect()
+    }
+
+    fn write_stacked_underlines(
+        &self,
+        annotations: &[Annotation<'_>],
+        f: &mut dyn Output,
+    ) -> fmt::Result {
+        let mut row = Vec::new();
+
+        for annotation in annotations {
+            let length = usize::max(1, annotation.length);
+            let chr = self.underline_char(annotation.length);
+
+            let end = annotation.col_number + length;
+            if row.len() < end {
+                row.resize(end, ' ');
+            }
+
+            row[annotation.col_number..end]
+                .iter_mut()
+                .for_each(|c| *c = chr);
+        }
+
+        self.write_gutter(None, f)?;
+        write!(f, "  ")?;
+        self.write_painted(&row.into_iter().collect::<String>(), f)?;
+        writeln!(f)
+    }
+
+    // Writes a row containing a connector for each annotation of `connectors`,
+    // optionally followed by the text of `label`.
+    fn write_stacked_row(
+        &self,
+        connectors: &[Annotation<'_>],
+        label: Option<&Annotation<'_>>,
+        f: &mut dyn Output,
+    ) -> fmt::Result {
+        let mut row = Vec::new();
+
+        for connector in connectors {
+            if row.len() <= connector.col_number {
+                row.resize(connector.col_number + 1, ' ');
+            }
+
+            row[connector.col_number] = self.charset.vertical();
+        }
+
+        if let Some(label) = label {
+            // Connectors are sorted by column, so only the ones that start on
+            // the same column as the label can collide with its text.
+            let collides = connectors
+                .iter()
+                .any(|connector| connector.col_number == label.col_number);
+
+            let text_col = if collides {
+                label.col_number + 2
+            } else {
+                label.col_number
+            };
+
+            row.resize(text_col, ' ');
+            row.extend(label.text.chars());
+        }
+
+        self.write_gutter(None, f)?;
+        writeln!(f, "  {}", row.into_iter().collect::<String>())
+    }
+
+    fn write_stacked_errors(
+        &self,
+        annotations: &[Annotation<'_>],
+        f: &mut dyn Output,
+    ) -> fmt::Result {
+        self.write_stacked_underlines(annotations, f)?;
+
+        // Annotations without text are only underlined.
+        let annotations = &Self::labelled(annotations)[..];
+
+        if annotations.is_empty() {
+            return Ok(());
+        }
+
+        self.write_stacked_row(annotations, None, f)?;
+
+        for idx in (0..annotations.len()).rev() {
+            self.write_stacked_row(&annotations[..idx], Some(&annotations[idx]), f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> Display for FormattedError<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Painted {
+            err: self,
+            colors: self.colors,
+        }
+        .fmt(f)
+    }
+}
+
+// Displays an error, overriding whether it is painted.
+struct Painted<'e, 'a> {
+    err: &'e FormattedError<'a>,
+    colors: bool,
+}
+
+impl Display for Painted<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut output = AnsiOutput {
+            f,
+            colors: self.colors,
+        };
+
+        self.err.render_padded(&mut output)
+    }
+}
+
+impl<'a> FormattedError<'a> {
+    fn render(&self, f: &mut dyn Output) -> fmt::Result {
+        if self.header {
+            self.write_header(f)?;
+        }
+
+        // The stacked layout writes messages below the snippet, so no margin
+        // is needed.
+        let spacing = if self.stacked { 0 } else { self.spacing() };
+
+        self.write_gutter(None, f)?;
+        writeln!(f)?;
+
+        if self.ruler {
+            self.write_ruler(spacing, f)?;
+        }
+
+        for (idx, (line, errs)) in self.snippet_lines().zip(self.errors.iter()).enumerate() {
+            let number = idx + self.first_line_number + self.index_base;
+            self.write_line(line, spacing, number, f)?;
+
+            let (errs, hidden) = self.displayed_annotations(errs);
+
+            if self.stacked {
+                self.write_stacked_errors(&errs, f)?;
+            } else {
+                self.write_errors(&errs, spacing, f)?;
+            }
+
+            if hidden != 0 {
+                self.write_gutter(None, f)?;
+                writeln!(f, " {} and {} more", " ".repeat(spacing), hidden)?;
+            }
+
+            self.write_gutter(None, f)?;
+            writeln!(f)?;
+        }
+
+        for (kind, msg) in self.footnotes.iter() {
+            f.write_styled(Style::Gutter, "     =")?;
+            writeln!(f, " {}: {}", kind, msg)?;
+        }
+
+        for suggestion in self.suggestions.iter() {
+            suggestion.render(f)?;
+        }
+
+        for related in self.related.iter() {
+            related.render(f)?;
+        }
+
+        Ok(())
+    }
+
+    // Returns whether other can be rendered in the same snippet block as
+    // self.
+    fn shares_snippet_with(&self, other: &FormattedError<'_>) -> bool {
+        self.first_line_number == other.first_line_number
+            && self.text == other.text
+            && self.severity == other.severity
+            && self.related.is_empty()
+            && other.related.is_empty()
+            && self.suggestions.is_empty()
+            && other.suggestions.is_empty()
+            && self.footnotes.is_empty()
+            && other.footnotes.is_empty()
+            && !self.shares_label_column_with(other)
+    }
+
+    // Returns whether a labelled annotation of self starts on the same line
+    // and column as a labelled annotation of other. Their labels would be
+    // drawn on top of each other in a shared snippet.
+    fn shares_label_column_with(&self, other: &FormattedError<'_>) -> bool {
+        self.errors
+            .iter()
+            .zip(other.errors.iter())
+            .any(|(row, other_row)| {
+                Self::labelled(row).iter().any(|ann| {
+                    Self::labelled(other_row)
+                        .iter()
+                        .any(|other_ann| other_ann.col_number == ann.col_number)
+                })
+            })
+    }
+
+    // Renders the headers of errs, followed by a single snippet block that
+    // contains the annotations of every error. The errors must share their
+    // snippet.
+    fn render_together(errs: &[FormattedError<'a>], f: &mut dyn Output) -> fmt::Result {
+        let (first, others) = match errs {
+            [first] => return first.render_padded(f),
+            [first, others @ ..] => (first, others),
+            [] => return Ok(()),
+        };
+
+        if first.padding {
+            writeln!(f)?;
+        }
+
+        for err in errs.iter().filter(|err| err.header) {
+            err.write_header(f)?;
+        }
+
+        let mut merged = first.clone();
+        merged.header = false;
+
+        for other in others {
+            for (row, other_row) in merged.errors.iter_mut().zip(other.errors.iter()) {
+                row.extend(other_row.iter().cloned());
+            }
+        }
+
+        merged.render(f)?;
+
+        if first.padding {
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+
+    // Renders the error, surrounded by blank lines if padding is enabled.
+    fn render_padded(&self, f: &mut dyn Output) -> fmt::Result {
+        if self.padding {
+            writeln!(f)?;
+        }
+
+        self.render(f)?;
+
+        if self.padding {
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+// The parts of a formatted error that may be painted.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Style {
+    // The severity title and the underlines, painted in the color of the
+    // severity.
+    Severity(Color),
+    // The line numbers, the pipes on the left of the snippet and the position
+    // arrow.
+    Gutter,
+}
+
+// A destination in which a formatted error is rendered.
+trait Output: Write {
+    fn write_styled(&mut self, style: Style, text: &str) -> fmt::Result;
+}
+
+// Renders to a formatter, painting with ANSI escape codes if colors are
+// enabled. The gutter is never painted.
+struct AnsiOutput<'f, 'g> {
+    f: &'f mut fmt::Formatter<'g>,
+    colors: bool,
+}
+
+impl Write for AnsiOutput<'_, '_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.f.write_str(s)
+    }
+}
+
+impl Output for AnsiOutput<'_, '_> {
+    fn write_styled(&mut self, style: Style, text: &str) -> fmt::Result {
+        let color = match style {
+            Style::Severity(color) => color,
+            Style::Gutter => return self.f.write_str(text),
+        };
+
+        if self.colors && !text.is_empty() {
+            write!(self.f, "{}{}{}", color.ansi_style(), text, RESET_STYLE)
+        } else {
+            self.f.write_str(text)
+        }
+    }
+}
+
+// Renders to a termcolor target. As fmt::Write can not carry io errors, the
+// first one is stored so that it can be returned later.
+#[cfg(feature = "termcolor")]
+struct ColorOutput<'w, W> {
+    w: &'w mut W,
+    error: Option<IOError>,
+}
+
+#[cfg(feature = "termcolor")]
+impl<W: termcolor::WriteColor> ColorOutput<'_, W> {
+    fn spec(style: Style) -> termcolor::ColorSpec {
+        let color = match style {
+            Style::Severity(color) => color.to_termcolor(),
+            Style::Gutter => termcolor::Color::Blue,
+        };
+
+        let mut spec = termcolor::ColorSpec::new();
+        spec.set_fg(Some(color)).set_bold(true);
+        spec
+    }
+
+    fn catch(&mut self, res: io::Result<()>) -> fmt::Result {
+        res.map_err(|e| {
+            self.error.get_or_insert(e);
+            fmt::Error
+        })
+    }
+}
+
+#[cfg(feature = "termcolor")]
+impl<W: termcolor::WriteColor> Write for ColorOutput<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let res = self.w.write_all(s.as_bytes());
+        self.catch(res)
+    }
+}
+
+#[cfg(feature = "termcolor")]
+impl<W: termcolor::WriteColor> Output for ColorOutput<'_, W> {
+    fn write_styled(&mut self, style: Style, text: &str) -> fmt::Result {
+        if text.is_empty() {
+            return Ok(());
+        }
+
+        let res = self.w.set_color(&Self::spec(style));
+        self.catch(res)?;
+        self.write_str(text)?;
+        let res = self.w.reset();
+        self.catch(res)
+    }
+}
+
+#[cfg(feature = "serde")]
+fn lsp_range(span: Span) -> lsp::Range {
+    let ((start_line, start_col), (end_line, end_col)) = span.to_lsp_range();
+
+    lsp::Range {
+        start: lsp::Position {
+            line: start_line,
+            character: start_col,
+        },
+        end: lsp::Position {
+            line: end_line,
+            character: end_col,
+        },
+    }
+}
+
+#[cfg(feature = "serde")]
+mod json {
+    use alloc::vec::Vec;
+
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    pub(super) struct Diagnostic<'a> {
+        pub(super) message: &'a str,
+        pub(super) severity: &'static str,
+        pub(super) file: Option<&'a str>,
+        pub(super) line: usize,
+        pub(super) col: usize,
+        pub(super) annotations: Vec<Annotation<'a>>,
+        pub(super) related: Vec<Diagnostic<'a>>,
+    }
+
+    #[derive(Serialize)]
+    pub(super) struct Annotation<'a> {
+        pub(super) line: usize,
+        pub(super) col: usize,
+        pub(super) length: usize,
+        pub(super) text: &'a str,
+    }
+}
+
+/// A sequence of errors that can finally be displayed.
+///
+/// This structure is created by [`ErrorReporter::format_errors`], and
+/// implements the [`Display`] trait. Each error is displayed in order, followed
+/// by a summary line, unless the sequence is empty.
+///
+/// Consecutive errors that have the same severity and that are displayed on
+/// the same lines share a single code snippet: their headers are displayed
+/// first, followed by the snippet that contains all their annotations. Errors
+/// that have related errors or suggestions are always displayed separately.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FormattedErrors<'a> {
+    errors: Vec<FormattedError<'a>>,
+}
+
+impl<'a> FormattedErrors<'a> {
+    /// Returns the formatted errors.
+    pub fn errors(&self) -> &[FormattedError<'a>] {
+        &self.errors
+    }
+
+    /// Returns the number of errors whose severity is `severity`.
+    ///
+    /// Related errors are not counted.
+    pub fn count(&self, severity: Severity) -> usize {
+        self.errors
+            .iter()
+            .filter(|err| err.severity == severity)
+            .count()
+    }
+
+    /// Returns a line that counts the errors and the warnings, such as
+    /// `2 errors, 1 warning emitted`.
+    ///
+    /// Severities that are not encountered are omitted. An empty string is
+    /// returned when the sequence is empty.
+    pub fn summary(&self) -> String {
+        let counts = [Severity::Error, Severity::Warning]
+            .iter()
+            .map(|severity| (severity.name(), self.count(*severity)))
+            .filter(|(_, count)| *count != 0)
+            .map(|(name, count)| match count {
+                1 => format!("1 {}", name),
+                _ => format!("{} {}s", count, name),
+            })
+            .collect::<Vec<_>>();
+
+        if counts.is_empty() {
+            String::new()
+        } else {
+            format!("{} emitted", counts.join(", "))
+        }
+    }
+}
+
+impl<'a> Display for FormattedErrors<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Consecutive errors that share the same snippet are rendered in a
+        // single snippet block.
+        let mut errors = self.errors.as_slice();
+
+        while let Some(first) = errors.first() {
+            let len = 1 + errors[1..]
+                .iter()
+                .enumerate()
+                .take_while(|(idx, err)| {
+                    errors[..=*idx]
+                        .iter()
+                        .all(|taken| taken.shares_snippet_with(err))
+                })
+                .count();
+
+            let mut output = AnsiOutput {
+                f,
+                colors: first.colors,
+            };
+            FormattedError::render_together(&errors[..len], &mut output)?;
+
+            errors = &errors[len..];
+        }
+
+        if !self.errors.is_empty() {
+            writeln!(f, "{}", self.summary())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A group of errors that can finally be displayed.
+///
+/// This structure is created by [`ErrorReporter::format_group`], and
+/// implements the [`Display`] trait. The group message is displayed once,
+/// followed by each child error.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FormattedGroup<'a> {
+    msg: &'a str,
+    severity: Severity,
+    errors: Vec<FormattedError<'a>>,
+}
+
+impl<'a> FormattedGroup<'a> {
+    /// Returns the formatted child errors, in order.
+    pub fn errors(&self) -> &[FormattedError<'a>] {
+        &self.errors
+    }
+}
+
+impl<'a> Display for FormattedGroup<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let title = match self.severity {
+            Severity::Error => "Error",
+            Severity::Warning => "Warning",
+        };
+
+        writeln!(f, "{}: {}", title, self.msg)?;
+
+        for err in self.errors.iter() {
+            write!(f, "{}", err)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// An error bundled with the reporter it must be rendered with.
+///
+/// This structure is created by [`ErrorReporter::diagnostic`]. It implements
+/// the [`Display`] trait, so that the error can be printed where the reporter
+/// is not in scope. It is rendered the same way as [`FormattedError`].
+#[derive(Clone)]
+pub struct Diagnostic<'a> {
+    reporter: &'a ErrorReporter<'a>,
+    err: AnnotatedError,
+}
+
+impl<'a> Diagnostic<'a> {
+    /// Returns the bundled error.
+    pub fn error(&self) -> &AnnotatedError {
+        &self.err
+    }
+
+    /// Returns the bundled error, dropping the reporter.
+    pub fn into_error(self) -> AnnotatedError {
+        self.err
+    }
+}
+
+impl<'a> Display for Diagnostic<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.reporter.format_error(&self.err))
+    }
+}
+
+/// An input whose `\r\n` line endings have been replaced by `\n`.
+///
+/// This structure is created by [`ErrorReporter::normalized_line_endings`].
+/// It keeps track of the removed `\r`, so that its spans can be translated
+/// back to the original input.
+#[derive(Clone, Debug)]
+pub struct NormalizedInput {
+    content: String,
+    // Offset, in the normalized content, of the line return that followed
+    // each removed `\r`. Sorted in increasing order.
+    removed: Vec<usize>,
+}
+
+impl NormalizedInput {
+    /// Returns the [`SpannedStr`] associated to the whole normalized input.
+    pub fn spanned_str(&self) -> SpannedStr<'_> {
+        SpannedStr::input_file(self.content.as_str())
+    }
+
+    /// Translates a span of the normalized input into the corresponding span
+    /// of the original input.
+    ///
+    /// Line and column numbers are left unchanged, as the removed `\r` are
+    /// always located at the end of a line. A span that ends at the end of a
+    /// line does not include the `\r` of the original input.
+    pub fn original_span(&self, span: Span) -> Span {
+        Span::new(
+            self.original_position(span.start()),
+            self.original_position(span.end()),
+        )
+    }
+
+    /// Translates every span of `err` into the corresponding span of the
+    /// original input.
+    ///
+    /// See [`NormalizedInput::original_span`] for more.
+    pub fn original_error(&self, err: AnnotatedError) -> AnnotatedError {
+        err.map_spans(|span| self.original_span(span))
+    }
+
+    fn original_position(&self, pos: Position) -> Position {
+        let offset = pos.offset() as usize;
+        let shift = self.removed.partition_point(|idx| *idx < offset);
+
+        Position::new(pos.line(), pos.col(), (offset + shift) as u32)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Annotation<'a> {
+    pub(crate) col_number: usize,
+    pub(crate) length: usize,
+    pub(crate) text: &'a str,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec;
+
+    mod reporting {
+        // In this module, a set of "correct reports" are checked.
+        use super::*;
+
+        #[test]
+        fn reporting_removal_suggestion() {
+            let input_file = ErrorReporter::non_file_input("The big big cat".to_string());
+            let file = input_file.spanned_str();
+
+            let repeated = file.split_at(7).1.split_at(4).0;
+
+            let report = AnnotatedError::new(repeated.span(), "Repeated word")
+                .with_annotation(repeated.span(), "`big` is repeated")
+                .with_removal_suggestion(repeated.span());
+
+            let formatted = input_file.format_error(&report);
+
+            let left = formatted.to_string();
+            let right = "\
+            Error: Repeated word\n \
+             --> 1:8\n     \
+                 |\n   \
+               1 |                   The big big cat\n     \
+                 |                          ^^^^\n     \
+                 | `big` is repeated--------'\n     \
+                 |\n\
+            help: remove this\n     \
+                 |\n   \
+               1 |  The big big cat\n     \
+                 |         ----\n     \
+                 |\n";
+
+            assert_eq!(left, right);
+
+            assert_eq!(
+                formatted.to_plain_string(),
+                "1:8: error: Repeated word\n\
+                 1:8: note: `big` is repeated\n\
+                 1:8: help: remove this\n",
+            );
+        }
+
+        #[test]
+        fn reporting_insertion_suggestion() {
+            let input_file = ErrorReporter::non_file_input("let a = 42".to_string());
+            let file = input_file.spanned_str();
+
+            let report = AnnotatedError::new(file.span(), "Missing semicolon")
+                .with_insertion(file.span().end(), ";");
+
+            let formatted = input_file.format_error(&report);
+
+            let left = formatted.to_string();
+            let right = "\
+            Error: Missing semicolon\n \
+             --> 1:1\n     \
+                 |\n   \
+               1 |                   let a = 42\n     \
+                 |                   ^^^^^^^^^^\n     \
+                 | Missing semicolon-'\n     \
+                 |\n\
+            help: insert `;` here\n     \
+                 |\n   \
+               1 |  let a = 42\n     \
+                 |            ^\n     \
+                 |\n";
+
+            assert_eq!(left, right);
+
+            assert_eq!(
+                formatted.to_plain_string(),
+                "1:1: error: Missing semicolon\n\
+                 1:11: help: insert `;` here\n",
+            );
+        }
+
+        #[test]
+        fn reporting_note_and_help() {
+            let input_file = ErrorReporter::non_file_input("let a = b;".to_string());
+            let b = input_file.spanned_str().find("b").unwrap();
+
+            let report = AnnotatedError::new(b.span(), "Unknown variable `b`")
+                .with_help("define `b` before this statement")
+                .with_note("variables must be defined before being used");
+
+            let formatted = input_file.format_error(&report);
+
+            let left = formatted.to_string();
+            let right = "\
+            Error: Unknown variable `b`\n \
+             --> 1:9\n     \
+                 |\n   \
+               1 |                      let a = b;\n     \
+                 |                              |\n     \
+                 | Unknown variable `b`---------'\n     \
+                 |\n     \
+                 = note: variables must be defined before being used\n     \
+                 = help: define `b` before this statement\n";
+
+            assert_eq!(left, right);
+
+            assert_eq!(
+                formatted.to_plain_string(),
+                "1:9: error: Unknown variable `b`\n\
+                 1:9: note: variables must be defined before being used\n\
+                 1:9: help: define `b` before this statement\n",
+            );
+        }
+
+        #[test]
+        fn three_annotations_on_one_line() {
+            let input_file = ErrorReporter::non_file_input("foo bar baz".to_string());
+            let file = input_file.spanned_str();
+
+            let foo = file.find("foo").unwrap();
+            let bar = file.find("bar").unwrap();
+            let baz = file.find("baz").unwrap();
+
+            let report = AnnotatedError::new(foo.span(), "Unknown words")
+                .with_annotation(foo.span(), "first")
+                .with_annotation(bar.span(), "second")
+                .with_annotation(baz.span(), "third");
+
+            let left = input_file.format_error(&report).to_string();
+            let right = "\
+            Error: Unknown words\n \
+             --> 1:1\n     \
+                 |\n   \
+               1 |        foo bar baz\n     \
+                 |        ^^^ ^^^ ^^^\n     \
+                 | first--'   |   |\n     \
+                 | second-----'   |\n     \
+                 | third----------'\n     \
+                 |\n";
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn reporting_diagnostic() {
+            let input_file = ErrorReporter::input_file(
+                "docs.txt".to_string(),
+                "The cat are on the table.".to_string(),
+            );
+            let are = input_file.spanned_str().find("are").unwrap();
+
+            let report = AnnotatedError::new(are.span(), "Conjugation error")
+                .with_annotation(are.span(), "`are` is plural");
+
+            let expected = input_file.format_error(&report).to_string();
+            let diagnostic = input_file.diagnostic(report.clone());
+
+            assert_eq!(diagnostic.to_string(), expected);
+            assert_eq!(diagnostic.into_error(), report);
+        }
+
+        #[test]
+        fn reporting_group() {
+            let input_file = ErrorReporter::non_file_input("foo bar\nbaz".to_string());
+            let file = input_file.spanned_str();
+
+            let group = DiagnosticGroup::new("3 errors in this file")
+                .with_error(AnnotatedError::new(
+                    file.find("foo").unwrap().span(),
+                    "Unknown foo",
+                ))
+                .with_error(AnnotatedError::new(
+                    file.find("bar").unwrap().span(),
+                    "Unknown bar",
+                ))
+                .with_error(AnnotatedError::new(
+                    file.find("baz").unwrap().span(),
+                    "Unknown baz",
+                ));
+
+            let left = input_file.format_group(&group).to_string();
+            let right = "\
+            Error: 3 errors in this file\n\
+            Error: Unknown foo\n \
+             --> 1:1\n     \
+                 |\n   \
+               1 |             foo bar\n     \
+                 |             ^^^\n     \
+                 | Unknown foo-'\n     \
+                 |\n\
+            Error: Unknown bar\n \
+             --> 1:5\n     \
+                 |\n   \
+               1 |             foo bar\n     \
+                 |                 ^^^\n     \
+                 | Unknown bar-----'\n     \
+                 |\n\
+            Error: Unknown baz\n \
+             --> 2:1\n     \
+                 |\n   \
+               2 |             baz\n     \
+                 |             ^^^\n     \
+                 | Unknown baz-'\n     \
+                 |\n";
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn reporting_related_error() {
+            let input_file = ErrorReporter::non_file_input("let a;\nlet a;".to_string());
+            let file = input_file.spanned_str();
+
+            let first = file.find("a").unwrap();
+            let second = file.split_at(7).1.find("a").unwrap();
+
+            let report = AnnotatedError::new(second.span(), "`a` is defined twice")
+                .with_annotation(second.span(), "redefined here")
+                .with_related(
+                    AnnotatedError::new(first.span(), "`a` is first defined here")
+                        .with_annotation(first.span(), "first definition"),
+                );
+
+            let left = input_file.format_error(&report).to_string();
+
+            let right = "\
+            Error: `a` is defined twice\n \
+             --> 2:5\n     \
+                 |\n   \
+               2 |                let a;\n     \
+                 |                    |\n     \
+                 | redefined here-----'\n     \
+                 |\n\
+            Error: `a` is first defined here\n \
+             --> 1:5\n     \
+                 |\n   \
+               1 |                  let a;\n     \
+                 |                      |\n     \
+                 | first definition-----'\n     \
+                 |\n\
+            ";
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn reporting_annotation_in_other_source() {
+            let main =
+                ErrorReporter::input_file("a.txt".to_string(), "let x: u32 = f();".to_string());
+            let lib = ErrorReporter::input_file(
+                "b.txt".to_string(),
+                "// Library\nfn f() -> String".to_string(),
+            );
+
+            let call = main.spanned_str().find("f()").unwrap();
+            let ret = lib.spanned_str().find("String").unwrap();
+
+            let report = AnnotatedError::new(call.span(), "Mismatched types")
+                .with_annotation(call.span(), "expected `u32`")
+                .with_annotation_in("b.txt", ret.span(), "expected because of this");
+
+            let left = main.format_error_with_sources(&report, &[&lib]).to_string();
+
+            let right = "\
+            Error: Mismatched types\n \
+             --> a.txt:1:14\n     \
+                 |\n   \
+               1 |                let x: u32 = f();\n     \
+                 |                             ^^^\n     \
+                 | expected `u32`--------------'\n     \
+                 |\n \
+             --> b.txt:2:11\n     \
+                 |\n   \
+               2 |                          fn f() -> String\n     \
+                 |                                    ^^^^^^\n     \
+                 | expected because of this-----------'\n     \
+                 |\n\
+            ";
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn reporting_annotation_in_unknown_source() {
+            let main = ErrorReporter::input_file("a.txt".to_string(), "foo".to_string());
+            let foo = main.spanned_str().span();
+
+            let report = AnnotatedError::new(foo, "Unexpected foo").with_annotation_in(
+                "b.txt",
+                foo,
+                "defined here",
+            );
+
+            let left = main.format_error_with_sources(&report, &[]).to_string();
+
+            assert_eq!(left, main.format_error(&report).to_string());
+        }
+
+        #[test]
+        fn reporting_text_as_long_as_spacing_at_first_column() {
+            let input_file = ErrorReporter::non_file_input("foo bar".to_string());
+            let foo = input_file.spanned_str().split_at(3).0;
+
+            let report = AnnotatedError::new(foo.span(), "Unexpected foo")
+                .with_annotation(foo.span(), "foo");
+
+            let left = input_file.format_error(&report).to_string();
+
+            let right = "\
+            Error: Unexpected foo\n \
+             --> 1:1\n     \
+                 |\n   \
+               1 |     foo bar\n     \
+                 |     ^^^\n     \
+                 | foo-'\n     \
+                 |\n\
+            ";
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn reporting_primary_span_only_labelled_with_message() {
+            let input_file = ErrorReporter::non_file_input("foo bar".to_string());
+            let bar = input_file.spanned_str().split_at(4).1;
+
+            let report = AnnotatedError::new(bar.span(), "Unexpected word");
+
+            let left = input_file.format_error(&report).to_string();
+
+            let right = "\
+            Error: Unexpected word\n \
+             --> 1:5\n     \
+                 |\n   \
+               1 |                 foo bar\n     \
+                 |                     ^^^\n     \
+                 | Unexpected word-----'\n     \
+                 |\n\
+            ";
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn reporting_primary_label() {
+            let input_file = ErrorReporter::non_file_input("foo bar".to_string());
+            let bar = input_file.spanned_str().split_at(4).1;
+
+            let report =
+                AnnotatedError::new_with_label(bar.span(), "Unexpected word", "expected `baz`");
+
+            let left = input_file.format_error(&report).to_string();
+
+            let right = "\
+            Error: Unexpected word\n \
+             --> 1:5\n     \
+                 |\n   \
+               1 |                foo bar\n     \
+                 |                    ^^^\n     \
+                 | expected `baz`-----'\n     \
+                 |\n\
+            ";
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn reporting_simple() {
+            let input_file = ErrorReporter::non_file_input("hello, world".to_string());
+
+            let hello = input_file.spanned_str().split_at(5).0;
+            let comma = input_file.spanned_str().split_at(5).1.split_at(1).0;
+            let world = input_file.spanned_str().split_at(7).1;
+
+            let report = AnnotatedError::new(comma.span(), "Don't you recognize me?")
+                .with_annotation(hello.span(), "Hi sweetie")
+                .with_annotation(world.span(), "I am not a world!")
+                .with_annotation(comma.span(), "Such cute, very comma");
+
+            let left = input_file.format_error(&report).to_string();
+
+            let right = "\
+            Error: Don't you recognize me?\n \
+             --> 1:6\n     \
+                 |\n   \
+               1 |                       hello, world\n     \
+                 |                       ^^^^^| ^^^^^\n     \
+                 | Hi sweetie------------'    | |\n     \
+                 | Such cute, very comma------' |\n     \
+                 | I am not a world!------------'\n     \
+                 |\n\
+            ";
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn conjugaison_error() {
+            let reporter = ErrorReporter::input_file(
+                "docs.txt".to_string(),
+                "The cat are on the table.".to_string(),
+            );
+            let file = reporter.spanned_str();
+
+            let cat = file.split_at(4).1.split_at(3).0;
+            let are = file.split_at(8).1.split_at(3).0;
+
+            let report = AnnotatedError::new(are.span(), "Conjugation error")
+                .with_annotation(cat.span(), "`cat` is singular,")
+                .with_annotation(are.span(), "but `are` is used only for plural subject");
+
+            let left = reporter.format_error(&report).to_string();
+
+            let right = "\
+            Error: Conjugation error\n \
+             --> docs.txt:1:9\n     \
+                 |\n   \
+               1 |                                           The cat are on the table.\n     \
+                 |                                               ^^^ ^^^\n     \
+                 | `cat` is singular,----------------------------'   |\n     \
+                 | but `are` is used only for plural subject---------'\n     \
+                 |\n\
+            ";
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn two_lines_annotation() {
+            let reporter = ErrorReporter::non_file_input("let s = \"foo\nbar\";".to_string());
+            let file = reporter.spanned_str();
+
+            let literal = file.split_at(8).1.split_at(8).0;
+
+            let report = AnnotatedError::new(literal.span(), "Multiline string")
+                .with_annotation(literal.span(), "this string");
+
+            let left = reporter.format_error(&report).to_string();
+            let right = "\
+            Error: Multiline string\n \
+             --> 1:9\n     \
+                 |\n   \
+               1 |             let s = \"foo\n     \
+                 |                     ^^^^\n     \
+                 |\n   \
+               2 |             bar\";\n     \
+                 |             ^^^\n     \
+                 | this string-'\n     \
+                 |\n";
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn three_lines_annotation() {
+            let reporter = ErrorReporter::non_file_input("/* foo\nbar\nbaz */ x".to_string());
+            let file = reporter.spanned_str();
+
+            let comment = file.split_at(16).0;
+
+            let report = AnnotatedError::new(comment.span(), "Unused comment")
+                .with_annotation(comment.span(), "comment");
+
+            let left = reporter.format_error(&report).to_string();
+            let right = "\
+            Error: Unused comment\n \
+             --> 1:1\n     \
+                 |\n   \
+               1 |         /* foo\n     \
+                 |         ^^^^^^\n     \
+                 |\n   \
+               2 |         bar\n     \
+                 |         ^^^\n     \
+                 |\n   \
+               3 |         baz */ x\n     \
+                 |         ^^^^^\n     \
+                 | comment-'\n     \
+                 |\n";
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn multiline_annotation_overlapping_single_line() {
+            let reporter = ErrorReporter::non_file_input("let s = \"foo\nbar\";".to_string());
+            let file = reporter.spanned_str();
+
+            let binding = file.split_at(4).1.split_at(5).0;
+            let literal = file.split_at(8).1.split_at(8).0;
+
+            let report = AnnotatedError::new(literal.span(), "Multiline string")
+                .with_annotation(binding.span(), "binding")
+                .with_annotation(literal.span(), "this string");
+
+            let left = reporter.format_error(&report).to_string();
+            let right = "\
+            Error: Multiline string\n \
+             --> 1:9\n     \
+                 |\n   \
+               1 |             let s = \"foo\n     \
+                 |                 ^^^^^^^^\n     \
+                 | binding---------'\n     \
+                 |\n   \
+               2 |             bar\";\n     \
+                 |             ^^^\n     \
+                 | this string-'\n     \
+                 |\n";
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn multiline_annotation_ending_on_single_line_column() {
+            let reporter = ErrorReporter::non_file_input("let a = (\nb) + c".to_string());
+            let file = reporter.spanned_str();
+
+            let group = file.find("(\nb)").unwrap();
+            let b = file.find("b").unwrap();
+
+            let report = AnnotatedError::new(group.span(), "Unexpected group")
+                .with_annotation(group.span(), "this group")
+                .with_annotation(b.span(), "this b");
+
+            let left = reporter.format_error(&report).to_string();
+            let right = "\
+            Error: Unexpected group\n \
+             --> 1:9\n     \
+                 |\n   \
+               1 |            let a = (\n     \
+                 |                    |\n     \
+                 |\n   \
+               2 |            b) + c\n     \
+                 |            |^\n     \
+                 | this group-'\n     \
+                 | this b-----'\n     \
+                 |\n";
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn multiline_simple() {
+            let reporter = ErrorReporter::non_file_input("Hello\nWorld".into());
+            let content = reporter.spanned_str();
+
+            let hello = content.split_at(5).0;
+            let world = content.split_at(6).1;
+
+            let report = AnnotatedError::new(hello.span(), "Foo")
+                .with_annotation(hello.span(), "bar")
+                .with_annotation(world.span(), "baz");
+
+            let left = reporter.format_error(&report).to_string();
+
+            let right = "\
+            Error: Foo\n \
+             --> 1:1\n     \
+                 |\n   \
+               1 |     Hello\n     \
+                 |     ^^^^^\n     \
+                 | bar-'\n     \
+                 |\n   \
+               2 |     World\n     \
+                 |     ^^^^^\n     \
+                 | baz-'\n     \
+                 |\n\
+            ";
+
+            assert_eq!(left, right);
+        }
+    }
+
+    mod formatted_error {
+        use super::*;
+
+        #[test]
+        #[cfg(feature = "termcolor")]
+        fn write_colored_without_colors_is_plain_output() {
+            let reporter = ErrorReporter::input_file(
+                "docs.txt".to_string(),
+                "The cat are on the table.".to_string(),
+            );
+            let report = conjugation_report(&reporter);
+            let formatted = reporter.format_error(&report);
+
+            let mut buffer = termcolor::Buffer::no_color();
+            formatted.write_colored(&mut buffer).unwrap();
+
+            let left = String::from_utf8(buffer.into_inner()).unwrap();
+
+            assert_eq!(left, formatted.to_string());
+        }
+
+        #[test]
+        #[cfg(feature = "termcolor")]
+        fn write_colored_with_colors() {
+            let reporter = ErrorReporter::non_file_input("hello".to_string());
+            let report = AnnotatedError::new(reporter.spanned_str().span(), "Greeting found");
+
+            let mut buffer = termcolor::Buffer::ansi();
+            reporter
+                .format_error(&report)
+                .write_colored(&mut buffer)
+                .unwrap();
+
+            let left = String::from_utf8(buffer.into_inner()).unwrap();
+
+            assert!(left.starts_with("\x1b[0m\x1b[1m\x1b[31mError\x1b[0m: Greeting found\n"));
+            assert!(left.contains("\x1b[0m\x1b[1m\x1b[34m   1 |\x1b[0m                hello\n"));
+        }
+
+        #[cfg(feature = "std")]
+        fn write_ansi_to_string(formatted: &FormattedError<'_>, mode: ColorMode) -> String {
+            let mut buf = Vec::new();
+            formatted.write_ansi(&mut buf, mode.enabled(false)).unwrap();
+
+            String::from_utf8(buf).unwrap()
+        }
+
+        #[test]
+        #[cfg(feature = "std")]
+        fn write_ansi_auto_strips_codes_when_not_a_terminal() {
+            let reporter = ErrorReporter::non_file_input("hello".to_string());
+            let report = AnnotatedError::new(reporter.spanned_str().span(), "Greeting found");
+            let formatted = reporter.format_error(&report);
+
+            let left = write_ansi_to_string(&formatted, ColorMode::Auto);
+
+            assert!(!left.contains('\x1b'));
+            assert_eq!(left, formatted.to_string());
+        }
+
+        #[test]
+        #[cfg(feature = "std")]
+        fn write_ansi_always_writes_codes() {
+            let reporter = ErrorReporter::non_file_input("hello".to_string());
+            let report = AnnotatedError::new(reporter.spanned_str().span(), "Greeting found");
+            let formatted = reporter.format_error(&report);
+
+            let left = write_ansi_to_string(&formatted, ColorMode::Always);
+
+            assert!(left.starts_with("\x1b[1;31mError\x1b[0m: Greeting found\n"));
+        }
+
+        #[test]
+        #[cfg(feature = "serde")]
+        fn to_lsp_diagnostic_conjugation_error() {
+            let reporter = ErrorReporter::input_file(
+                "docs.txt".to_string(),
+                "The cat are on the table.".to_string(),
+            );
+            let report = conjugation_report(&reporter);
+
+            let diagnostic = reporter.format_error(&report).to_lsp_diagnostic();
+            let left = serde_json::to_string(&diagnostic).unwrap();
+
+            let right = "{\
+                \"range\":{\"start\":{\"line\":0,\"character\":8},\"end\":{\"line\":0,\"character\":11}},\
+                \"severity\":1,\
+                \"message\":\"Conjugation error\",\
+                \"relatedInformation\":[\
+                    {\"location\":{\"uri\":\"docs.txt\",\"range\":{\"start\":{\"line\":0,\"character\":4},\"end\":{\"line\":0,\"character\":7}}},\
+                    \"message\":\"`cat` is singular,\"},\
+                    {\"location\":{\"uri\":\"docs.txt\",\"range\":{\"start\":{\"line\":0,\"character\":8},\"end\":{\"line\":0,\"character\":11}}},\
+                    \"message\":\"but `are` is used only for plural subject\"}\
+                ]\
+            }";
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        #[cfg(feature = "serde")]
+        fn to_lsp_diagnostic_ignores_tab_expansion() {
+            let reporter = ErrorReporter::non_file_input("\tfoo = bar".to_string());
+            let bar = reporter.spanned_str().find("bar").unwrap();
+            let report = AnnotatedError::new(bar.span(), "Unknown bar")
+                .with_annotation(bar.span(), "not defined");
+
+            let options = FormatOptions {
+                tab_width: 4,
+                ..FormatOptions::default()
+            };
+            let diagnostic = reporter
+                .format_error_with(&report, &options)
+                .to_lsp_diagnostic();
+
+            assert_eq!(diagnostic.range.start.character, 7);
+            assert_eq!(diagnostic.related_information.len(), 1);
+            assert_eq!(
+                diagnostic.related_information[0].location.range,
+                diagnostic.range,
+            );
+        }
+
+        #[test]
+        #[cfg(feature = "serde")]
+        fn to_lsp_diagnostic_annotation_in_other_source() {
+            let main = ErrorReporter::input_file("main.rs".to_string(), "f()".to_string());
+            let lib = ErrorReporter::input_file("lib.rs".to_string(), "fn f()".to_string());
+
+            let call = main.spanned_str().span();
+            let def = lib.spanned_str().find("f()").unwrap();
+
+            let report = AnnotatedError::new(call, "Mismatched types").with_annotation_in(
+                "lib.rs",
+                def.span(),
+                "defined here",
+            );
+
+            let diagnostic = main
+                .format_error_with_sources(&report, &[&lib])
+                .to_lsp_diagnostic();
+            let left = serde_json::to_string(&diagnostic.related_information).unwrap();
+
+            let right = "[\
+                {\"location\":{\"uri\":\"lib.rs\",\"range\":{\"start\":{\"line\":0,\"character\":3},\"end\":{\"line\":0,\"character\":6}}},\
+                \"message\":\"defined here\"}\
+            ]";
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        #[cfg(feature = "serde")]
+        fn to_lsp_diagnostic_warning() {
+            let reporter = ErrorReporter::non_file_input("foo\nbar".to_string());
+            let bar = reporter.spanned_str().find("bar").unwrap();
+            let report =
+                AnnotatedError::new(bar.span(), "Unused bar").with_severity(Severity::Warning);
+
+            let options = FormatOptions {
+                one_indexed: true,
+                ..FormatOptions::default()
+            };
+            let diagnostic = reporter
+                .format_error_with(&report, &options)
+                .to_lsp_diagnostic();
+
+            assert_eq!(diagnostic.severity, 2);
+            assert_eq!(diagnostic.range.start.line, 1);
+            assert_eq!(diagnostic.range.start.character, 0);
+            assert_eq!(diagnostic.related_information, Vec::new());
+        }
+
+        #[test]
+        #[cfg(feature = "serde")]
+        fn to_json_conjugation_error() {
+            let reporter = ErrorReporter::input_file(
+                "docs.txt".to_string(),
+                "The cat are on the table.".to_string(),
+            );
+            let report = conjugation_report(&reporter);
+
+            let left = reporter.format_error(&report).to_json();
+
+            let right = "{\
+                \"message\":\"Conjugation error\",\
+                \"severity\":\"error\",\
+                \"file\":\"docs.txt\",\
+                \"line\":1,\
+                \"col\":9,\
+                \"annotations\":[\
+                    {\"line\":1,\"col\":5,\"length\":3,\"text\":\"`cat` is singular,\"},\
+                    {\"line\":1,\"col\":9,\"length\":3,\"text\":\"but `are` is used only for plural subject\"}\
+                ],\
+                \"related\":[]\
+            }";
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn warning_header() {
+            let reporter = ErrorReporter::non_file_input("foo".to_string());
+            let report = AnnotatedError::new(reporter.spanned_str().span(), "Unused foo")
+                .with_severity(Severity::Warning);
+
+            let formatted = reporter.format_error(&report);
+
+            assert_eq!(formatted.severity(), Severity::Warning);
+            assert!(formatted.to_string().starts_with("Warning: Unused foo\n"));
+            assert_eq!(formatted.to_plain_string(), "1:1: warning: Unused foo\n");
+        }
+
+        #[test]
+        fn summary_counts_errors_and_warnings() {
+            let reporter = ErrorReporter::non_file_input("foo bar baz".to_string());
+            let file = reporter.spanned_str();
+
+            let errors = vec![
+                AnnotatedError::new(file.find("foo").unwrap().span(), "Unknown foo"),
+                AnnotatedError::new(file.find("bar").unwrap().span(), "Unused bar")
+                    .with_severity(Severity::Warning),
+                AnnotatedError::new(file.find("baz").unwrap().span(), "Unknown baz"),
+            ];
+
+            let formatted = reporter.format_errors(&errors);
+
+            assert_eq!(formatted.count(Severity::Error), 2);
+            assert_eq!(formatted.count(Severity::Warning), 1);
+            assert_eq!(formatted.summary(), "2 errors, 1 warning emitted");
+            assert!(formatted
+                .to_string()
+                .ends_with("     |\n2 errors, 1 warning emitted\n"));
+        }
+
+        #[test]
+        fn errors_on_same_line_share_snippet() {
+            let reporter = ErrorReporter::non_file_input("foo bar\nbaz".to_string());
+            let file = reporter.spanned_str();
+
+            let foo = file.find("foo").unwrap().span();
+            let bar = file.find("bar").unwrap().span();
+            let baz = file.find("baz").unwrap().span();
+
+            let errors = vec![
+                AnnotatedError::new(foo, "Unknown foo").with_annotation(foo, "foo"),
+                AnnotatedError::new(bar, "Unknown bar").with_annotation(bar, "bar"),
+                AnnotatedError::new(baz, "Unknown baz").with_annotation(baz, "baz"),
+            ];
+
+            let left = reporter.format_errors(&errors).to_string();
+            let right = "\
+            Error: Unknown foo\n \
+             --> 1:1\n\
+            Error: Unknown bar\n \
+             --> 1:5\n     \
+                 |\n   \
+               1 |     foo bar\n     \
+                 |     ^^^ ^^^\n     \
+                 | foo-'   |\n     \
+                 | bar-----'\n     \
+                 |\n\
+            Error: Unknown baz\n \
+             --> 2:1\n     \
+                 |\n   \
+               2 |     baz\n     \
+                 |     ^^^\n     \
+                 | baz-'\n     \
+                 |\n\
+            3 errors emitted\n";
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn errors_with_different_severities_do_not_share_snippet() {
+            let reporter = ErrorReporter::non_file_input("foo bar".to_string());
+            let file = reporter.spanned_str();
+
+            let foo = AnnotatedError::new(file.find("foo").unwrap().span(), "Unknown foo");
+            let bar = AnnotatedError::new(file.find("bar").unwrap().span(), "Unused bar")
+                .with_severity(Severity::Warning);
+
+            let left = reporter
+                .format_errors(&[foo.clone(), bar.clone()])
+                .to_string();
+            let right = format!(
+                "{}{}1 error, 1 warning emitted\n",
+                reporter.format_error(&foo),
+                reporter.format_error(&bar),
+            );
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn errors_on_same_span_do_not_share_snippet() {
+            let reporter = ErrorReporter::non_file_input("foo bar".to_string());
+            let foo = reporter.spanned_str().find("foo").unwrap().span();
+
+            let first = AnnotatedError::new(foo, "first");
+            let second = AnnotatedError::new(foo, "second");
+
+            let left = reporter
+                .format_errors(&[first.clone(), second.clone()])
+                .to_string();
+            let right = format!(
+                "{}{}2 errors emitted\n",
+                reporter.format_error(&first),
+                reporter.format_error(&second),
+            );
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn summary_empty() {
+            let reporter = ErrorReporter::non_file_input("foo".to_string());
+            let formatted = reporter.format_errors(&[]);
+
+            assert_eq!(formatted.summary(), "");
+            assert_eq!(formatted.to_string(), "");
+        }
+
+        fn conjugation_report(reporter: &ErrorReporter<'_>) -> AnnotatedError {
+            let file = reporter.spanned_str();
+
+            let cat = file.split_at(4).1.split_at(3).0;
+            let are = file.split_at(8).1.split_at(3).0;
+
+            AnnotatedError::new(are.span(), "Conjugation error")
+                .with_annotation(cat.span(), "`cat` is singular,")
+                .with_annotation(are.span(), "but `are` is used only for plural subject")
+        }
+
+        #[test]
+        fn to_github_annotation_conjugation_error() {
+            let reporter = ErrorReporter::input_file(
+                "docs.txt".to_string(),
+                "The cat are on the table.".to_string(),
+            );
+            let report = conjugation_report(&reporter);
+
+            let left = reporter.format_error(&report).to_github_annotation();
+
+            assert_eq!(
+                left,
+                "::error file=docs.txt,line=1,col=9::Conjugation error"
+            );
+        }
+
+        #[test]
+        fn to_github_annotation_warning_escaped() {
+            let reporter = ErrorReporter::input_file("a,b:c.txt".to_string(), "foo".to_string());
+            let report = AnnotatedError::new(reporter.spanned_str().span(), "100%\nunused")
+                .with_severity(Severity::Warning);
+
+            let options = FormatOptions {
+                one_indexed: false,
+                ..FormatOptions::default()
+            };
+            let left = reporter
+                .format_error_with(&report, &options)
+                .to_github_annotation();
+
+            assert_eq!(
+                left,
+                "::warning file=a%2Cb%3Ac.txt,line=1,col=1::100%25%0Aunused"
+            );
+        }
+
+        #[test]
+        fn to_github_annotation_suggestion() {
+            let reporter = ErrorReporter::non_file_input("a;;".to_string());
+            let semi = reporter.spanned_str().split_at(2).1;
+            let report = AnnotatedError::new(semi.span(), "Unnecessary semicolon")
+                .with_removal_suggestion(semi.span());
+
+            let formatted = reporter.format_error(&report);
+
+            assert_eq!(
+                formatted.suggestions[0].to_github_annotation(),
+                "::notice line=1,col=3::remove this"
+            );
+        }
+
+        #[test]
+        fn render_into_appends() {
+            let reporter = ErrorReporter::non_file_input("foo bar".to_string());
+            let file = reporter.spanned_str();
+
+            let foo = AnnotatedError::new(file.find("foo").unwrap().span(), "Unknown foo");
+            let bar = AnnotatedError::new(file.find("bar").unwrap().span(), "Unknown bar");
+
+            let (foo, bar) = (reporter.format_error(&foo), reporter.format_error(&bar));
+
+            let mut buf = String::with_capacity(256);
+            foo.render_into(&mut buf);
+            bar.render_into(&mut buf);
+
+            assert_eq!(buf, format!("{}{}", foo, bar));
+        }
+
+        #[test]
+        fn max_message_width() {
+            let reporter = ErrorReporter::non_file_input("foo".to_string());
+            let msg = "a".repeat(300);
+            let report = AnnotatedError::new(reporter.spanned_str().span(), &msg);
+
+            let options = FormatOptions {
+                max_message_width: Some(80),
+                ..FormatOptions::default()
+            };
+
+            let formatted = reporter.format_error_with(&report, &options).to_string();
+            let header = formatted.lines().next().unwrap();
+
+            assert_eq!(header, format!("Error: {}…", "a".repeat(79)));
+            assert_eq!(report.message(), msg);
+        }
+
+        #[test]
+        fn max_message_width_short_message() {
+            let reporter = ErrorReporter::non_file_input("foo".to_string());
+            let report = AnnotatedError::new(reporter.spanned_str().span(), "Unexpected foo");
+
+            let options = FormatOptions {
+                max_message_width: Some(14),
+                ..FormatOptions::default()
+            };
+
+            let formatted = reporter.format_error_with(&report, &options).to_string();
+
+            assert!(formatted.starts_with("Error: Unexpected foo\n"));
+        }
+
+        #[test]
+        fn reverse_ordered_annotations() {
+            let reporter = ErrorReporter::input_file(
+                "docs.txt".to_string(),
+                "The cat are on the table.".to_string(),
+            );
+            let report = conjugation_report(&reporter);
+
+            let formatted = reporter.format_error(&report);
+            let mut reversed = formatted.clone();
+            reversed.errors.iter_mut().for_each(|anns| anns.reverse());
+
+            assert_ne!(reversed.errors, formatted.errors);
+            assert_eq!(reversed.to_string(), formatted.to_string());
+            assert_eq!(
+                reversed.with_stacked_layout(true).to_string(),
+                formatted.with_stacked_layout(true).to_string(),
+            );
+        }
+
+        #[test]
+        fn reverse_ordered_annotations_same_column() {
+            let reporter = ErrorReporter::non_file_input("foo bar".to_string());
+            let file = reporter.spanned_str();
+            let foo = file.find("foo").unwrap().span();
+            let fo = file.find("fo").unwrap().span();
+
+            let report = AnnotatedError::new(foo, "Unexpected foo")
+                .with_annotation(foo, "first")
+                .with_annotation(fo, "second")
+                .with_annotation(file.find("bar").unwrap().span(), "third");
+
+            let formatted = reporter.format_error(&report);
+            let mut reversed = formatted.clone();
+            reversed.errors.iter_mut().for_each(|anns| anns.reverse());
+
+            let right = "\
+            Error: Unexpected foo\n \
+             --> 1:1\n     \
+                 |\n   \
+               1 |        foo bar\n     \
+                 |        ^^^ ^^^\n     \
+                 | first--'   |\n     \
+                 | second-'   |\n     \
+                 | third------'\n     \
+                 |\n";
+
+            assert_eq!(formatted.to_string(), right);
+
+            // Annotations that start on the same column are displayed in the
+            // order in which they are stored.
+            let sorted_lines = |text: String| {
+                let mut lines = text.lines().map(str::to_string).collect::<Vec<_>>();
+                lines.sort();
+                lines
+            };
+
+            assert_eq!(
+                sorted_lines(reversed.to_string()),
+                sorted_lines(formatted.to_string()),
+            );
+            assert_eq!(
+                reversed
+                    .with_stacked_layout(true)
+                    .to_string()
+                    .lines()
+                    .count(),
+                formatted
+                    .with_stacked_layout(true)
+                    .to_string()
+                    .lines()
+                    .count(),
+            );
+        }
+
+        #[test]
+        fn unicode_charset_conjugation_error() {
+            let reporter = ErrorReporter::input_file(
+                "docs.txt".to_string(),
+                "The cat are on the table.".to_string(),
+            );
+            let report = conjugation_report(&reporter);
+
+            let options = FormatOptions {
+                charset: Charset::Unicode,
+                ..FormatOptions::default()
+            };
+            let left = reporter.format_error_with(&report, &options).to_string();
+
+            let right = "\
+            Error: Conjugation error\n \
+             --> docs.txt:1:9\n     \
+                 │\n   \
+               1 │                                           The cat are on the table.\n     \
+                 │                                               ^^^ ^^^\n     \
+                 │ `cat` is singular,────────────────────────────╯   │\n     \
+                 │ but `are` is used only for plural subject─────────╯\n     \
+                 │\n";
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn unicode_charset_suggestion_and_custom_gutter() {
+            let reporter = ErrorReporter::non_file_input("a;;".to_string());
+            let semi = reporter.spanned_str().split_at(1).1;
+            let report = AnnotatedError::new(semi.span(), "Unnecessary semicolons")
+                .with_removal_suggestion(semi.span());
+
+            let options = FormatOptions {
+                charset: Charset::Unicode,
+                gutter: '┃',
+                ..FormatOptions::default()
+            };
+            let left = reporter.format_error_with(&report, &options).to_string();
+
+            assert!(left.contains("   1 ┃  a;;\n     ┃   ──\n"));
+            assert!(!left.contains('|'));
+        }
+
+        #[test]
+        fn options_padding() {
+            let reporter = ErrorReporter::non_file_input("foo bar".to_string());
+            let file = reporter.spanned_str();
+
+            let foo = AnnotatedError::new(file.find("foo").unwrap().span(), "Unknown foo")
+                .with_removal_suggestion(file.find("foo").unwrap().span());
+            let bar = AnnotatedError::new(file.find("bar").unwrap().span(), "Unknown bar");
+
+            let options = FormatOptions {
+                padding: true,
+                ..FormatOptions::default()
+            };
+
+            let mut left = String::new();
+            reporter
+                .format_error_with(&foo, &options)
+                .render_into(&mut left);
+            reporter
+                .format_error_with(&bar, &options)
+                .render_into(&mut left);
+
+            let right = "\n\
+            Error: Unknown foo\n \
+             --> 1:1\n     \
+                 |\n   \
+               1 |             foo bar\n     \
+                 |             ^^^\n     \
+                 | Unknown foo-'\n     \
+                 |\n\
+            help: remove this\n     \
+                 |\n   \
+               1 |  foo bar\n     \
+                 |  ---\n     \
+                 |\n\
+            \n\
+            \n\
+            Error: Unknown bar\n \
+             --> 1:5\n     \
+                 |\n   \
+               1 |             foo bar\n     \
+                 |                 ^^^\n     \
+                 | Unknown bar-----'\n     \
+                 |\n\
+            \n";
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn default_options_match_format_error() {
+            let reporter = ErrorReporter::input_file(
+                "docs.txt".to_string(),
+                "The cat are on the table.".to_string(),
+            );
+            let report = conjugation_report(&reporter);
+
+            let left = reporter
+                .format_error_with(&report, &FormatOptions::default())
+                .to_string();
+            let right = reporter.format_error(&report).to_string();
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn options_context() {
+            let reporter = ErrorReporter::non_file_input("foo\nbar\nbaz\nqux".to_string());
+            let bar = reporter.spanned_str().find("bar").unwrap();
+            let report = AnnotatedError::new(bar.span(), "Unexpected bar");
+
+            let options = FormatOptions {
+                context: 1,
+                ..FormatOptions::default()
+            };
+
+            let left = reporter.format_error_with(&report, &options).to_string();
+
+            let right = "\
+            Error: Unexpected bar\n \
+             --> 2:1\n     \
+                 |\n   \
+               1 |                foo\n     \
+                 |                \n     \
+                 |\n   \
+               2 |                bar\n     \
+                 |                ^^^\n     \
+                 | Unexpected bar-'\n     \
+                 |\n   \
+               3 |                baz\n     \
+                 |                \n     \
+                 |\n\
+            ";
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn options_max_annotations_per_line() {
+            let reporter = ErrorReporter::non_file_input("a b c d e".to_string());
+            let input = reporter.spanned_str();
+            let report = ["a", "b", "c", "d", "e"].iter().fold(
+                AnnotatedError::new(input.span(), "Too many letters"),
+                |report, letter| {
+                    let span = input.find(letter).unwrap().span();
+                    report.with_annotation(span, format!("`{}`", letter))
+                },
+            );
+
+            let options = FormatOptions {
+                max_annotations_per_line: Some(3),
+                ..FormatOptions::default()
+            };
+
+            let left = reporter.format_error_with(&report, &options).to_string();
+
+            let right = "\
+            Error: Too many letters\n \
+             --> 1:1\n     \
+                 |\n   \
+               1 |     a b c d e\n     \
+                 |     | | |\n     \
+                 | `a`-' | |\n     \
+                 | `b`---' |\n     \
+                 | `c`-----'\n     \
+                 |     and 2 more\n     \
+                 |\n\
+            ";
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn options_box_drawing_glyphs() {
+            let reporter = ErrorReporter::non_file_input("foo bar".to_string());
+            let bar = reporter.spanned_str().find("bar").unwrap();
+            let report = AnnotatedError::new(bar.span(), "Unexpected bar")
+                .with_annotation(bar.span(), "here");
+
+            let options = FormatOptions {
+                arrow: "╭─▶",
+                gutter: '│',
+                ..FormatOptions::default()
+            };
+
+            let left = reporter.format_error_with(&report, &options).to_string();
+            let right = "\
+            Error: Unexpected bar\n \
+             ╭─▶ 1:5\n     \
+                 │\n   \
+               1 │      foo bar\n     \
+                 │          ^^^\n     \
+                 │ here-----'\n     \
+                 │\n\
+            ";
+
+            assert_eq!(left, right);
+            assert_ne!(left, reporter.format_error(&report).to_string());
+        }
+
+        #[test]
+        fn options_zero_indexed() {
+            let reporter = ErrorReporter::non_file_input("foo\nbar".to_string());
+            let bar = reporter.spanned_str().find("bar").unwrap();
+            let report = AnnotatedError::new(bar.span(), "Unexpected bar");
+
+            let options = FormatOptions {
+                one_indexed: false,
+                ..FormatOptions::default()
+            };
+
+            let formatted = reporter.format_error_with(&report, &options);
+            let left = formatted.to_string();
+
+            assert!(left.contains(" --> 1:0\n"));
+            assert!(left.contains("   1 |                bar\n"));
+            assert_eq!(formatted.to_plain_string(), "1:0: error: Unexpected bar\n");
+        }
+
+        #[test]
+        fn options_tab_width() {
+            let reporter = ErrorReporter::non_file_input("\tfoo\tbar".to_string());
+            let bar = reporter.spanned_str().find("bar").unwrap();
+            let report = AnnotatedError::new(bar.span(), "Unexpected bar")
+                .with_annotation(bar.span(), "here");
+
+            let options = FormatOptions {
+                tab_width: 4,
+                ..FormatOptions::default()
+            };
+
+            let left = reporter.format_error_with(&report, &options).to_string();
+
+            let right = "\
+            Error: Unexpected bar\n \
+             --> 1:9\n     \
+                 |\n   \
+               1 |          foo bar\n     \
+                 |              ^^^\n     \
+                 | here---------'\n     \
+                 |\n\
+            ";
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn options_colors() {
+            let reporter = ErrorReporter::non_file_input("foo".to_string());
+            let foo = reporter.spanned_str();
+            let report = AnnotatedError::new_with_label(foo.span(), "Unexpected foo", "here");
+
+            let options = FormatOptions {
+                colors: true,
+                ..FormatOptions::default()
+            };
+
+            let left = reporter.format_error_with(&report, &options).to_string();
+
+            assert!(left.starts_with("\x1b[1;31mError\x1b[0m: Unexpected foo\n"));
+            assert!(left.contains("|      \x1b[1;31m^^^\x1b[0m\n"));
+        }
+
+        #[test]
+        fn options_severity_colors() {
+            let reporter = ErrorReporter::non_file_input("foo".to_string());
+            let foo = reporter.spanned_str().span();
+
+            let error = AnnotatedError::new(foo, "Unexpected foo");
+            let warning = error.clone().with_severity(Severity::Warning);
+
+            let options = FormatOptions {
+                colors: true,
+                ..FormatOptions::default()
+            };
+
+            let render = |report: &AnnotatedError, options: &FormatOptions| {
+                reporter.format_error_with(report, options).to_string()
+            };
+
+            assert!(render(&error, &options).starts_with("\x1b[1;31mError\x1b[0m"));
+            assert!(render(&warning, &options).starts_with("\x1b[1;33mWarning\x1b[0m"));
+
+            let options = FormatOptions {
+                severity_colors: SeverityColors {
+                    warning: Color::Cyan,
+                    ..SeverityColors::default()
+                },
+                ..options
+            };
+
+            assert!(render(&error, &options).starts_with("\x1b[1;31mError\x1b[0m"));
+            assert!(render(&warning, &options).starts_with("\x1b[1;36mWarning\x1b[0m"));
+            assert!(render(&warning, &options).contains("\x1b[1;36m^^^\x1b[0m"));
+        }
+
+        #[test]
+        fn stacked_layout_four_annotations() {
+            let reporter = ErrorReporter::non_file_input("let foo = bar + baz;".to_string());
+            let file = reporter.spanned_str();
+
+            let foo = file.split_at(4).1.split_at(3).0;
+            let bar = file.split_at(10).1.split_at(3).0;
+            let plus = file.split_at(14).1.split_at(1).0;
+            let baz = file.split_at(16).1.split_at(3).0;
+
+            let report = AnnotatedError::new(foo.span(), "Type error")
+                .with_annotation(foo.span(), "foo is declared here, with a long message")
+                .with_annotation(bar.span(), "bar is a string")
+                .with_annotation(plus.span(), "addition")
+                .with_annotation(baz.span(), "baz is an integer");
+
+            let left = reporter
+                .format_error(&report)
+                .with_stacked_layout(true)
+                .to_string();
+
+            let right = "\
+            Error: Type error\n \
+             --> 1:5\n     \
+                 |\n   \
+               1 |  let foo = bar + baz;\n     \
+                 |      ^^^   ^^^ | ^^^\n     \
+                 |      |     |   | |\n     \
+                 |      |     |   | baz is an integer\n     \
+                 |      |     |   addition\n     \
+                 |      |     bar is a string\n     \
+                 |      foo is declared here, with a long message\n     \
+                 |\n\
+            ";
 
-    mod reporting {
-        // In this module, a set of "correct reports" are checked.
-        use super::*;
+            assert_eq!(left, right);
+
+            for annotation in [
+                "foo is declared here, with a long message",
+                "bar is a string",
+                "addition",
+                "baz is an integer",
+            ]
+            .iter()
+            {
+                assert!(left.contains(annotation));
+            }
+        }
 
         #[test]
-        fn reporting_simple() {
-            let input_file = ErrorReporter::non_file_input("hello, world".to_string());
+        fn stacked_layout_same_column() {
+            let reporter = ErrorReporter::non_file_input("hello".to_string());
+            let hello = reporter.spanned_str();
+            let h = hello.split_at(1).0;
 
-            let hello = input_file.spanned_str().split_at(5).0;
-            let comma = input_file.spanned_str().split_at(5).1.split_at(1).0;
-            let world = input_file.spanned_str().split_at(7).1;
+            let report = AnnotatedError::new(hello.span(), "Greeting found")
+                .with_annotation(hello.span(), "a word")
+                .with_annotation(h.span(), "a letter");
 
-            let report = AnnotatedError::new(comma.span(), "Don't you recognize me?")
-                .with_annotation(hello.span(), "Hi sweetie")
-                .with_annotation(world.span(), "I am not a world!")
-                .with_annotation(comma.span(), "Such cute, very comma");
+            let left = reporter
+                .format_error(&report)
+                .with_stacked_layout(true)
+                .with_header(false)
+                .to_string();
 
-            let left = input_file.format_error(&report).to_string();
+            let right = "     \
+                 |\n   \
+               1 |  hello\n     \
+                 |  |^^^^\n     \
+                 |  |\n     \
+                 |  | a letter\n     \
+                 |  a word\n     \
+                 |\n\
+            ";
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn ruler_short_line() {
+            let reporter = ErrorReporter::non_file_input("hello".to_string());
+            let hello = reporter.spanned_str();
+
+            let report = AnnotatedError::new(hello.span(), "Greeting found")
+                .with_annotation(hello.span(), "here");
+
+            let left = reporter.format_error(&report).with_ruler(true).to_string();
 
             let right = "\
-            Error: Don't you recognize me?\n \
-             --> 1:6\n     \
+            Error: Greeting found\n \
+             --> 1:1\n     \
+                 |\n     \
+                 |      12345\n   \
+               1 |      hello\n     \
+                 |      ^^^^^\n     \
+                 | here-'\n     \
+                 |\n\
+            ";
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn ruler_long_line() {
+            let reporter = ErrorReporter::non_file_input("hello, world".to_string());
+            let report = AnnotatedError::new(reporter.spanned_str().span(), "Foo");
+
+            let left = reporter.format_error(&report).with_ruler(true).to_string();
+            let lines = left.lines().collect::<Vec<_>>();
+
+            assert_eq!(lines[3], "     |              1");
+            assert_eq!(lines[4], "     |     123456789012");
+            assert_eq!(lines[5], "   1 |     hello, world");
+        }
+
+        #[test]
+        fn empty_general_message() {
+            let reporter = ErrorReporter::non_file_input("hello".to_string());
+            let hello = reporter.spanned_str();
+
+            let report =
+                AnnotatedError::new(hello.span(), "").with_annotation(hello.span(), "Greeting");
+
+            let left = reporter.format_error(&report).to_string();
+
+            let right = "\
+            Error\n \
+             --> 1:1\n     \
                  |\n   \
-               1 |                       hello, world\n     \
-                 |                       ^^^^^| ^^^^^\n     \
-                 | Hi sweetie------------'    | |\n     \
-                 | Such cute, very comma------' |\n     \
-                 | I am not a world!------------'\n     \
+               1 |          hello\n     \
+                 |          ^^^^^\n     \
+                 | Greeting-'\n     \
                  |\n\
             ";
 
             assert_eq!(left, right);
+
+            let left = reporter.format_error(&report).to_plain_string();
+            assert_eq!(left, "1:1: error\n1:1: note: Greeting\n");
         }
 
         #[test]
-        fn conjugaison_error() {
+        fn without_header() {
             let reporter = ErrorReporter::input_file(
                 "docs.txt".to_string(),
                 "The cat are on the table.".to_string(),
@@ -372,24 +4114,57 @@ mod tests {
                 .with_annotation(cat.span(), "`cat` is singular,")
                 .with_annotation(are.span(), "but `are` is used only for plural subject");
 
-            let left = reporter.format_error(&report).to_string();
+            let with_header = reporter.format_error(&report).to_string();
+            let without_header = reporter
+                .format_error(&report)
+                .with_header(false)
+                .to_string();
+
+            assert!(without_header.starts_with("     |\n"));
+            assert!(with_header.ends_with(without_header.as_str()));
+            assert!(!without_header.contains("Error:"));
+            assert!(!without_header.contains("-->"));
+        }
+
+        #[test]
+        fn ruler_disabled() {
+            let reporter = ErrorReporter::non_file_input("hello".to_string());
+            let report = AnnotatedError::new(reporter.spanned_str().span(), "Foo");
+
+            let left = reporter.format_error(&report).with_ruler(false).to_string();
+            let right = reporter.format_error(&report).to_string();
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn to_plain_string_conjugaison_error() {
+            let reporter = ErrorReporter::input_file(
+                "docs.txt".to_string(),
+                "The cat are on the table.".to_string(),
+            );
+            let file = reporter.spanned_str();
+
+            let cat = file.split_at(4).1.split_at(3).0;
+            let are = file.split_at(8).1.split_at(3).0;
+
+            let report = AnnotatedError::new(are.span(), "Conjugation error")
+                .with_annotation(cat.span(), "`cat` is singular,")
+                .with_annotation(are.span(), "but `are` is used only for plural subject");
+
+            let left = reporter.format_error(&report).to_plain_string();
 
             let right = "\
-            Error: Conjugation error\n \
-             --> docs.txt:1:9\n     \
-                 |\n   \
-               1 |                                           The cat are on the table.\n     \
-                 |                                               ^^^ ^^^\n     \
-                 | `cat` is singular,----------------------------'   |\n     \
-                 | but `are` is used only for plural subject---------'\n     \
-                 |\n\
+            docs.txt:1:9: error: Conjugation error\n\
+            docs.txt:1:5: note: `cat` is singular,\n\
+            docs.txt:1:9: note: but `are` is used only for plural subject\n\
             ";
 
             assert_eq!(left, right);
         }
 
         #[test]
-        fn multiline_simple() {
+        fn to_plain_string_multiline_non_file() {
             let reporter = ErrorReporter::non_file_input("Hello\nWorld".into());
             let content = reporter.spanned_str();
 
@@ -400,20 +4175,38 @@ mod tests {
                 .with_annotation(hello.span(), "bar")
                 .with_annotation(world.span(), "baz");
 
-            let left = reporter.format_error(&report).to_string();
+            let left = reporter.format_error(&report).to_plain_string();
 
             let right = "\
-            Error: Foo\n \
-             --> 1:1\n     \
-                 |\n   \
-               1 |     Hello\n     \
-                 |     ^^^^^\n     \
-                 | bar-'\n     \
-                 |\n   \
-               2 |     World\n     \
-                 |     ^^^^^\n     \
-                 | baz-'\n     \
-                 |\n\
+            1:1: error: Foo\n\
+            1:1: note: bar\n\
+            2:1: note: baz\n\
+            ";
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn to_plain_string_annotation_in_other_source() {
+            let main = ErrorReporter::input_file("a.txt".to_string(), "f()".to_string());
+            let lib = ErrorReporter::input_file("b.txt".to_string(), "fn f()".to_string());
+
+            let call = main.spanned_str().span();
+            let def = lib.spanned_str().find("f()").unwrap();
+
+            let report = AnnotatedError::new(call, "Mismatched types").with_annotation_in(
+                "b.txt",
+                def.span(),
+                "defined here",
+            );
+
+            let left = main
+                .format_error_with_sources(&report, &[&lib])
+                .to_plain_string();
+
+            let right = "\
+            a.txt:1:1: error: Mismatched types\n\
+            b.txt:1:4: note: defined here\n\
             ";
 
             assert_eq!(left, right);
@@ -423,6 +4216,184 @@ mod tests {
     mod error_reporter {
         use super::*;
 
+        #[test]
+        fn from_str() {
+            let reporter = "foo bar".parse::<ErrorReporter>().unwrap();
+            let bar = reporter.spanned_str().split_at(4).1;
+            let report = AnnotatedError::new(bar.span(), "Unexpected bar");
+
+            let left = reporter.format_error(&report).to_string();
+
+            assert!(left.starts_with("Error: Unexpected bar\n --> 1:5\n"));
+        }
+
+        #[test]
+        fn set_path_renders_header() {
+            let mut reporter = ErrorReporter::non_file_input("foo bar".to_string());
+            reporter.set_path(Some("main.txt".to_string()));
+
+            let bar = reporter.spanned_str().split_at(4).1;
+            let report = AnnotatedError::new(bar.span(), "Unexpected bar");
+
+            let left = reporter.format_error(&report).to_string();
+
+            assert!(left.starts_with("Error: Unexpected bar\n --> main.txt:1:5\n"));
+        }
+
+        #[test]
+        #[cfg(feature = "unicode")]
+        fn grapheme_columns_align_caret() {
+            let reporter = ErrorReporter::non_file_input("cafe\u{301} bar".to_string())
+                .with_grapheme_columns(true);
+            let bar = reporter.spanned_str().split_at(7).1;
+
+            let report = AnnotatedError::new(bar.span(), "Unexpected word")
+                .with_annotation(bar.span(), "here");
+
+            let left = reporter.format_error(&report).to_string();
+
+            let right = "\
+            Error: Unexpected word\n \
+             --> 1:6\n     \
+                 |\n   \
+               1 |      cafe\u{301} bar\n     \
+                 |           ^^^\n     \
+                 | here------'\n     \
+                 |\n\
+            ";
+
+            assert_eq!(left, right);
+        }
+
+        fn naive_code_snippet(content: &str, start: usize, end: usize) -> &str {
+            let start_idx = content[..start].rfind('\n').map_or(0, |idx| idx + 1);
+            let end_idx = end + content[end..].find('\n').unwrap_or(content.len() - end);
+
+            &content[start_idx..end_idx]
+        }
+
+        #[test]
+        fn code_snippet_matches_naive_extraction() {
+            let content = "first line\nsecond\n\nfourth line\r\nlast";
+            let reporter = ErrorReporter::non_file_input(content.to_string());
+            let file = reporter.spanned_str();
+
+            for start in 0..=content.len() {
+                for end in start..=content.len() {
+                    let start_pos = file.split_at(start).1.span().start();
+                    let end_pos = file.split_at(end).1.span().start();
+
+                    assert_eq!(
+                        reporter.code_snippet_for(start_pos, end_pos),
+                        naive_code_snippet(content, start, end),
+                        "Snippet mismatch for {}..{}",
+                        start,
+                        end,
+                    );
+                }
+            }
+        }
+
+        #[test]
+        #[cfg(feature = "std")]
+        fn from_reader_stdin_name() {
+            let input: &[u8] = b"foo bar";
+            let reporter = ErrorReporter::from_reader("<stdin>".to_string(), input).unwrap();
+
+            let bar = reporter.spanned_str().split_at(4).1;
+            let report = AnnotatedError::new(bar.span(), "Unexpected bar");
+
+            let left = reporter.format_error(&report).to_string();
+
+            assert!(left.starts_with("Error: Unexpected bar\n --> <stdin>:1:5\n"));
+        }
+
+        #[test]
+        #[cfg(feature = "std")]
+        fn from_reader_invalid_utf8() {
+            let input: &[u8] = &[0x66, 0x6f, 0xff, 0x6f];
+            let err = ErrorReporter::from_reader("<stdin>".to_string(), input)
+                .err()
+                .unwrap();
+
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+
+        #[test]
+        fn from_borrowed_formats_identically() {
+            let content = "The cat are on the table.";
+
+            let owned = ErrorReporter::input_file("docs.txt".to_string(), content.to_string());
+            let borrowed = ErrorReporter::from_borrowed(Some("docs.txt"), content);
+
+            assert_eq!(owned.spanned_str(), borrowed.spanned_str());
+
+            let file = borrowed.spanned_str();
+            let cat = file.split_at(4).1.split_at(3).0;
+            let are = file.split_at(8).1.split_at(3).0;
+
+            let report = AnnotatedError::new(are.span(), "Conjugation error")
+                .with_annotation(cat.span(), "`cat` is singular,")
+                .with_annotation(are.span(), "but `are` is used only for plural subject");
+
+            let left = borrowed.format_error(&report).to_string();
+            let right = owned.format_error(&report).to_string();
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn text_of_sub_word() {
+            let input_file = ErrorReporter::non_file_input("foo barbaz\nqux".to_string());
+            let bar = input_file.spanned_str().split_at(4).1.split_at(3).0;
+
+            assert_eq!(input_file.text_of(bar.span()), Some("bar"));
+        }
+
+        #[test]
+        fn text_of_out_of_range() {
+            let input_file = ErrorReporter::non_file_input("foo".to_string());
+            let other = SpannedStr::input_file("foo bar baz");
+            let baz = other.split_at(8).1;
+
+            assert_eq!(input_file.text_of(baz.span()), None);
+        }
+
+        #[test]
+        fn text_of_non_char_boundary() {
+            let input_file = ErrorReporter::non_file_input("é".to_string());
+            let other = SpannedStr::input_file("ab");
+            let a = other.split_at(1).0;
+
+            assert_eq!(input_file.text_of(a.span()), None);
+        }
+
+        #[test]
+        fn line_span_valid() {
+            let input_file = ErrorReporter::non_file_input("foo\nbarbaz\nqux".to_string());
+            let span = input_file.line_span(1).unwrap();
+
+            assert_eq!(span.start(), Position::new(1, 0, 4));
+            assert_eq!(span.end(), Position::new(1, 6, 10));
+            assert_eq!(input_file.text_of(span), Some("barbaz"));
+        }
+
+        #[test]
+        fn line_span_crlf() {
+            let input_file = ErrorReporter::non_file_input("foo\r\nbar".to_string());
+            let span = input_file.line_span(0).unwrap();
+
+            assert_eq!(span.end(), Position::new(0, 3, 3));
+            assert_eq!(input_file.text_of(span), Some("foo"));
+        }
+
+        #[test]
+        fn line_span_out_of_range() {
+            let input_file = ErrorReporter::non_file_input("foo\nbar".to_string());
+
+            assert_eq!(input_file.line_span(2), None);
+        }
+
         #[test]
         fn code_snippet_for_single_line() {
             let foobar = "foo bar";
@@ -457,5 +4428,118 @@ mod tests {
 
             assert_eq!(selected_text, "barbar");
         }
+
+        #[test]
+        fn code_snippet_for_first_and_third_lines() {
+            let input_text = "foo\nbar\nbaz\nqux";
+            let input_file = ErrorReporter::non_file_input(input_text.to_string());
+
+            let foo = input_file.spanned_str().split_at(3).0;
+            let baz = input_file.spanned_str().split_at(8).1.split_at(3).0;
+            assert_eq!(baz.content(), "baz");
+
+            let report = AnnotatedError::new(foo.span(), "Unexpected words")
+                .with_annotation(foo.span(), "first")
+                .with_annotation(baz.span(), "third");
+
+            let (start, end) = report.bounds();
+            let selected_text = input_file.code_snippet_for(start, end);
+
+            assert_eq!(selected_text, "foo\nbar\nbaz");
+
+            let left = input_file.format_error(&report).to_string();
+
+            let right = "\
+            Error: Unexpected words\n \
+             --> 1:1\n     \
+                 |\n   \
+               1 |       foo\n     \
+                 |       ^^^\n     \
+                 | first-'\n     \
+                 |\n   \
+               2 |       bar\n     \
+                 |       \n     \
+                 |\n   \
+               3 |       baz\n     \
+                 |       ^^^\n     \
+                 | third-'\n     \
+                 |\n\
+            ";
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn next_char_at_eof() {
+            for content in ["foo", "foo\n", ""] {
+                let input_file = ErrorReporter::non_file_input(content.to_string());
+                let span = input_file.spanned_str().span();
+
+                for eof in [span.next_char(), span.saturating_next_char(content)] {
+                    let selected_text = input_file.code_snippet_for(eof.start(), eof.end());
+                    assert!(content.ends_with(selected_text));
+
+                    let report = AnnotatedError::new(eof, "Unexpected end of file")
+                        .with_annotation(eof, "here");
+                    let formatted = input_file.format_error(&report).to_string();
+
+                    assert!(formatted.contains("here-"));
+                }
+            }
+        }
+
+        #[test]
+        fn code_snippet_for_trailing_line_return() {
+            let input_file = ErrorReporter::non_file_input("foo\n".to_string());
+            let report = AnnotatedError::new(input_file.spanned_str().span(), "Foo");
+
+            let formatted = input_file.format_error(&report);
+
+            assert_eq!(formatted.snippet_lines().count(), formatted.errors.len());
+            assert_eq!(formatted.snippet_lines().collect::<Vec<_>>(), ["foo", ""]);
+        }
+
+        #[test]
+        fn normalized_line_endings_content() {
+            let input_file = ErrorReporter::non_file_input("a\r\nb\nc\rd\r\n".to_string());
+            let normalized = input_file.normalized_line_endings();
+
+            assert_eq!(normalized.spanned_str().content(), "a\nb\nc\rd\n");
+        }
+
+        #[test]
+        fn normalized_line_endings_maps_back() {
+            let content = "foo\r\nbar\n  baz\r\nqux";
+            let input_file = ErrorReporter::non_file_input(content.to_string());
+            let normalized = input_file.normalized_line_endings();
+
+            let qux = normalized.spanned_str().find("qux").unwrap();
+            let baz = normalized.spanned_str().find("baz").unwrap();
+            let report = AnnotatedError::new(qux.span(), "Unexpected qux")
+                .with_annotation(baz.span(), "After this");
+
+            let report = normalized.original_error(report);
+
+            let expected = SpannedStr::input_file(content).find("qux").unwrap().span();
+            assert_eq!(report.span(), expected);
+            assert_eq!(input_file.text_of(report.span()), Some("qux"));
+
+            let left = input_file.format_error(&report).to_string();
+            assert!(left.starts_with("Error: Unexpected qux\n --> 4:1\n"));
+            assert!(left.contains("After this"));
+        }
+
+        #[test]
+        fn normalized_line_endings_line_end() {
+            let content = "foo\r\nbar";
+            let input_file = ErrorReporter::non_file_input(content.to_string());
+            let normalized = input_file.normalized_line_endings();
+
+            let foo = normalized.spanned_str().split_at(3).0;
+            let span = normalized.original_span(foo.span());
+
+            assert_eq!(input_file.text_of(span), Some("foo"));
+            assert_eq!(span.end().col(), 3);
+        }
     }
 }
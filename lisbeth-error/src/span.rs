@@ -14,7 +14,19 @@
 //! different places are used toghether. This is fine for most use-cases, in
 //! which a single process in invoked for a single input unit.
 
-use std::cmp::{Ord, Ordering};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{
+    cmp::{Ord, Ordering},
+    convert::TryFrom,
+    error::Error,
+    fmt::{self, Debug, Display},
+    iter,
+    ops::{Index, Range, RangeInclusive},
+    str::Chars,
+};
 
 /// Represents a position in the input data.
 ///
@@ -28,12 +40,81 @@ pub struct Position {
 }
 
 impl Position {
-    const BEGINNING: Position = Position {
+    /// The position of the first character of an input unit.
+    pub const BEGINNING: Position = Position {
         line: 0,
         col: 0,
         offset: 0,
     };
 
+    /// Creates a new position from its line, column and offset.
+    ///
+    /// This is mostly useful when spans must be created directly, for instance
+    /// in tests. Positions are usually created from a [`SpannedStr`].
+    ///
+    /// # Warning
+    ///
+    /// The caller is responsible for providing a position that is consistent
+    /// with the input unit it is used with. The consistency note of the module
+    /// documentation applies.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::Position;
+    ///
+    /// let p = Position::new(1, 4, 10);
+    ///
+    /// assert_eq!(p.line(), 1);
+    /// assert_eq!(p.col(), 4);
+    /// assert_eq!(p.offset(), 10);
+    /// ```
+    #[inline]
+    pub const fn new(line: u32, col: u32, offset: u32) -> Position {
+        Position { line, col, offset }
+    }
+
+    /// Creates the position located at a given line and column of `source`.
+    ///
+    /// Both `line` and `col` are zero-indexed, and `col` is counted in
+    /// characters. The offset is computed by scanning `source`. A column equal
+    /// to the length of the line represents the end of that line.
+    ///
+    /// Returns `None` if `source` has no such line, or if the line has no such
+    /// column.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::Position;
+    ///
+    /// let p = Position::from_line_col_in("hello\nworld", 1, 2).unwrap();
+    ///
+    /// assert_eq!(p, Position::new(1, 2, 8));
+    /// assert!(Position::from_line_col_in("hello\nworld", 1, 6).is_none());
+    /// ```
+    pub fn from_line_col_in(source: &str, line: u32, col: u32) -> Option<Position> {
+        let line_idx = usize::try_from(line).ok()?;
+        let col_idx = usize::try_from(col).ok()?;
+
+        let line_start = source
+            .split('\n')
+            .take(line_idx)
+            .map(|text| text.len() + 1)
+            .sum::<usize>();
+        let text = source.split('\n').nth(line_idx)?;
+
+        let col_offset = text
+            .char_indices()
+            .map(|(idx, _)| idx)
+            .chain(iter::once(text.len()))
+            .nth(col_idx)?;
+
+        let offset = u32::try_from(line_start + col_offset).ok()?;
+
+        Some(Position { line, col, offset })
+    }
+
     fn advance_with(self, s: &str) -> Position {
         let Position {
             mut line,
@@ -55,6 +136,118 @@ impl Position {
         Position { line, col, offset }
     }
 
+    // Translates a position of a child input unit, which starts at
+    // parent_start, into the parent coordinate system. Panics if the
+    // translated position does not fit in a u32.
+    fn rebase(self, parent_start: Position) -> Position {
+        let add = |a: u32, b: u32| a.checked_add(b).expect("Rebased position is out of bounds");
+
+        let col = if self.line == 0 {
+            add(parent_start.col, self.col)
+        } else {
+            self.col
+        };
+
+        Position {
+            line: add(parent_start.line, self.line),
+            col,
+            offset: add(parent_start.offset, self.offset),
+        }
+    }
+
+    // Translates a position of a parent input unit into the coordinate system
+    // of the child input unit that starts at base. This is the inverse of
+    // rebase.
+    fn relative_to(self, base: Position) -> Option<Position> {
+        let line = self.line.checked_sub(base.line)?;
+        let offset = self.offset.checked_sub(base.offset)?;
+
+        let col = if line == 0 {
+            self.col.checked_sub(base.col)?
+        } else {
+            self.col
+        };
+
+        Some(Position { line, col, offset })
+    }
+
+    /// Returns the position located after `s`, assuming that `s` starts at the
+    /// current position.
+    ///
+    /// Returns `None` if the line number, the column number or the offset of
+    /// the resulting position can not be represented by a `u32`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let start = SpannedStr::input_file("").span().start();
+    /// let end = start.checked_advance_with("hello\nworld").unwrap();
+    ///
+    /// assert_eq!(end.line(), 1);
+    /// assert_eq!(end.col(), 5);
+    /// assert_eq!(end.offset(), 11);
+    /// ```
+    pub fn checked_advance_with(self, s: &str) -> Option<Position> {
+        let Position {
+            mut line,
+            mut col,
+            offset,
+        } = self;
+
+        let offset = u32::try_from(s.len())
+            .ok()
+            .and_then(|len| offset.checked_add(len))?;
+
+        for c in s.chars() {
+            if c == '\n' {
+                line = line.checked_add(1)?;
+                col = 0;
+            } else {
+                col = col.checked_add(1)?;
+            }
+        }
+
+        Some(Position { line, col, offset })
+    }
+
+    /// Returns the position that comes first between `self` and `other`.
+    ///
+    /// Positions are compared with their [`Ord`] implementation. As such, the
+    /// consistency note of the module documentation applies.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let span = SpannedStr::input_file("hello").span();
+    ///
+    /// assert_eq!(span.start().min(span.end()), span.start());
+    /// ```
+    pub fn min(self, other: Position) -> Position {
+        Ord::min(self, other)
+    }
+
+    /// Returns the position that comes last between `self` and `other`.
+    ///
+    /// Positions are compared with their [`Ord`] implementation. As such, the
+    /// consistency note of the module documentation applies.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let span = SpannedStr::input_file("hello").span();
+    ///
+    /// assert_eq!(span.start().max(span.end()), span.end());
+    /// ```
+    pub fn max(self, other: Position) -> Position {
+        Ord::max(self, other)
+    }
+
     /// Returns the position's line.
     #[inline]
     pub const fn line(self) -> u32 {
@@ -72,6 +265,65 @@ impl Position {
     pub const fn offset(self) -> u32 {
         self.offset
     }
+
+    /// Returns the column at which the position is displayed, once the
+    /// tabulations are expanded.
+    ///
+    /// `line_text` must be the text of the line containing the position.
+    /// Each tabulation moves to the next multiple of `tab_width`. Tabulations
+    /// are counted as a single column when `tab_width` is zero, so that the
+    /// character column is returned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let input = SpannedStr::input_file("\tfoo");
+    /// let foo = input.find("foo").unwrap().span().start();
+    ///
+    /// assert_eq!(foo.col(), 1);
+    /// assert_eq!(foo.visual_col("\tfoo", 4), 4);
+    /// ```
+    pub fn visual_col(self, line_text: &str, tab_width: usize) -> u32 {
+        let tab_width = tab_width as u32;
+
+        line_text
+            .chars()
+            .take(self.col as usize)
+            .fold(0, |col, chr| match chr {
+                '\t' if tab_width != 0 => col + tab_width - col % tab_width,
+                _ => col + 1,
+            })
+    }
+}
+
+impl Default for Position {
+    /// Returns [`Position::BEGINNING`].
+    fn default() -> Position {
+        Position::BEGINNING
+    }
+}
+
+/// Creates a position from a `(line, col, offset)` tuple.
+///
+/// This is equivalent to [`Position::new`]. As such, the caller is responsible
+/// for providing a position that is consistent with the input unit it is used
+/// with.
+///
+/// # Example
+///
+/// ```rust
+/// use lisbeth_error::span::Position;
+///
+/// let p = Position::from((1, 4, 10));
+///
+/// assert_eq!(p, Position::new(1, 4, 10));
+/// ```
+impl From<(u32, u32, u32)> for Position {
+    fn from((line, col, offset): (u32, u32, u32)) -> Position {
+        Position::new(line, col, offset)
+    }
 }
 
 // Note: when the following documentation is modified, remember to update the
@@ -103,7 +355,7 @@ impl PartialOrd for Position {
 /// In debug mode, this function may panic if the two positions are not from the
 /// same input unit. In release mode, this function does not panic.
 impl Ord for Position {
-    #[cfg(debug)]
+    #[cfg(debug_assertions)]
     fn cmp(&self, other: &Position) -> Ordering {
         let offset_provided = self.offset.cmp(&other.offset);
 
@@ -112,15 +364,15 @@ impl Ord for Position {
             any => any,
         };
 
-        assert!(
-            offset_provided != lc_provided,
+        assert_eq!(
+            offset_provided, lc_provided,
             "Attempt to perform an inconsistent span comparaison",
         );
 
         offset_provided
     }
 
-    #[cfg(not(debug))]
+    #[cfg(not(debug_assertions))]
     fn cmp(&self, other: &Position) -> Ordering {
         self.offset.cmp(&other.offset)
     }
@@ -130,13 +382,34 @@ impl Ord for Position {
 ///
 /// A `Span` is represented as the start and end position. Every character that
 /// is between these two position is considered as *inside* the span.
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, PartialEq)]
 pub struct Span {
     start: Position,
     end: Position,
 }
 
 impl Span {
+    /// Creates a new span from its starting and ending positions.
+    ///
+    /// # Warning
+    ///
+    /// The caller is responsible for providing positions that come from the
+    /// same input unit, and for `start` being before `end`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::{Position, Span, SpannedStr};
+    ///
+    /// let span = Span::new(Position::new(0, 0, 0), Position::new(0, 5, 5));
+    ///
+    /// assert_eq!(span, SpannedStr::input_file("hello").span());
+    /// ```
+    #[inline]
+    pub const fn new(start: Position, end: Position) -> Span {
+        Span { start, end }
+    }
+
     /// Returns the span's starting position.
     #[inline]
     pub const fn start(self) -> Position {
@@ -168,307 +441,2494 @@ impl Span {
         (left, right)
     }
 
+    /// Returns the span covering the whole string `s`, starting at
+    /// [`Position::BEGINNING`].
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `s` is larger than `u32::MAX` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::{Position, Span};
+    ///
+    /// let span = Span::of_str("hello\nworld");
+    ///
+    /// assert_eq!(span.start(), Position::BEGINNING);
+    /// assert_eq!(span.end().line(), 1);
+    /// assert_eq!(span.end().col(), 5);
+    /// ```
+    pub fn of_str(s: &str) -> Span {
+        Span::of_file(s)
+    }
+
+    // Panics if the input is too large to be represented by a Span. See
+    // checked_of_file for a non-panicking version.
     pub(crate) fn of_file(input: &str) -> Span {
+        Span::checked_of_file(input).expect("Input is too large to be spanned")
+    }
+
+    pub(crate) fn checked_of_file(input: &str) -> Option<Span> {
         let start = Position::BEGINNING;
-        let end = start.advance_with(input);
+        let end = start.checked_advance_with(input)?;
 
-        Span { start, end }
+        Some(Span { start, end })
     }
 
-    /// Returns the span of the character following the current span, on the
-    /// same line.
+    /// Returns an empty span located at the start of the current span.
     ///
-    /// This function can be used when an unexpected EOF happens.
+    /// # Example
     ///
     /// ```rust
-    /// use lisbeth_error::span::{Span, SpannedStr};
+    /// use lisbeth_error::span::SpannedStr;
     ///
     /// let input = SpannedStr::input_file("foo");
-    /// let after_input = input.span().next_char();
-    ///
-    /// assert_eq!(after_input.start().line(), 0);
-    /// assert_eq!(after_input.start().col(), 3);
+    /// let start = input.span().shrink_to_start();
     ///
-    /// assert_eq!(after_input.end().line(), 0);
-    /// assert_eq!(after_input.end().col(), 4);
+    /// assert_eq!(start.start(), start.end());
+    /// assert_eq!(start.start().col(), 0);
     /// ```
-    pub fn next_char(self) -> Span {
-        let start = self.end;
-        let end = start.advance_with(" ");
+    pub const fn shrink_to_start(self) -> Span {
+        let start = self.start;
 
-        Span { start, end }
+        Span { start, end: start }
     }
-}
-
-/// Represents a portion of input file.
-///
-/// This is represented the same way as [`Span`], but with an additionnal
-/// content field.
-///
-/// It is initially created with the [`input_file`] function, and can then be
-/// splitted at desired index. Its content and span can be accessed with the
-/// [`content`] and [`span`] methods.
-///
-/// # Example
-///
-/// The following code shows how to extract a sequence of numbers separated by
-/// non-digit characters.
-///
-/// ```rust
-/// use lisbeth_error::span::{Span, SpannedStr};
-///
-/// #[derive(Debug)]
-/// struct Number(u32, Span);
-///
-/// // Parses a number from input, if any failure occurs, returns None
-/// fn extract_number<'a>(input: SpannedStr<'a>) -> (Number, SpannedStr<'a>) {
-///     let (matched, tail) = input.take_while(char::is_numeric);
-///
-///     let value = matched.content().parse().unwrap();
-///     let number = Number(value, matched.span());
-///     (number, tail)
-/// }
-///
-/// let input = SpannedStr::input_file("42 or nothing");
-/// let (number, tail) = extract_number(input);
-///
-/// assert_eq!(number.0, 42);
-/// assert_eq!(tail.content(), " or nothing");
-/// ```
-///
-/// [`input_file`]: SpannedStr::input_file
-/// [`content`]: SpannedStr::content
-/// [`span`]: SpannedStr::span
-#[derive(Copy, Clone, Debug, PartialEq)]
-pub struct SpannedStr<'a> {
-    span: Span,
-    content: &'a str,
-}
 
-impl<'a> SpannedStr<'a> {
-    /// Creates a new [`SpannedStr`] from an input file.
+    /// Returns an empty span located at the end of the current span.
     ///
-    /// This returned spanned string can then be splitted at various places
-    /// during the parsing step.
+    /// This can be used to point right after a token, for instance when a
+    /// semicolon is missing.
     ///
     /// # Example
     ///
     /// ```rust
     /// use lisbeth_error::span::SpannedStr;
     ///
-    /// let file_content = "fn main() { println!(\"Hello, world!\"); }";
+    /// let input = SpannedStr::input_file("foo");
+    /// let end = input.span().shrink_to_end();
     ///
-    /// let whole_file = SpannedStr::input_file(file_content);
+    /// assert_eq!(end.start(), end.end());
+    /// assert_eq!(end.start().col(), 3);
     /// ```
-    pub fn input_file(content: &'a str) -> SpannedStr<'a> {
-        let span = Span::of_file(content);
-
-        SpannedStr { span, content }
-    }
-
-    // Note: span must represent the same source as content, otherwise
-    // inconsistent results may occur.
-    //
-    // In debug mode, it is ensured that:
-    //   - span.start == Position::BEGINNING,
-    //   - span.end.offset == content.len().
-    pub(crate) fn assemble(content: &'a str, span: Span) -> SpannedStr<'a> {
-        debug_assert_eq!(
-            span.start,
-            Position::BEGINNING,
-            "Attempt to create a SpannedStr that does not start at the beginning of the file",
-        );
-        debug_assert_eq!(
-            span.end.offset as usize,
-            content.len(),
-            "Attempt to create a SpannedStr with an incorrect length",
-        );
+    pub const fn shrink_to_end(self) -> Span {
+        let end = self.end;
 
-        SpannedStr { content, span }
+        Span { start: end, end }
     }
 
-    /// Returns the contained [`Span`].
+    /// Returns an empty span located in the gap between `a` and `b`, right
+    /// after `a`.
     ///
-    /// The span contains the position at which the content is located in the
-    /// input data.
+    /// This is where something missing between two tokens should be
+    /// inserted, for instance a comma between two arguments. `a` must come
+    /// before `b`.
+    ///
+    /// # Panics
+    ///
+    /// In debug mode, this function panics if `a` ends after the start of
+    /// `b`.
     ///
     /// # Example
     ///
     /// ```rust
-    /// use lisbeth_error::span::SpannedStr;
+    /// use lisbeth_error::span::{Span, SpannedStr};
     ///
-    /// let a = SpannedStr::input_file("foo bar");
-    /// let b = SpannedStr::input_file("baz qux");
+    /// let input = SpannedStr::input_file("f(a b)");
+    /// let a = input.find("a").unwrap().span();
+    /// let b = input.find("b").unwrap().span();
     ///
-    /// // a and b have the same length and the same starting point, so they
-    /// // have the same span.
-    /// assert_eq!(a.span(), b.span());
+    /// let gap = Span::between(a, b);
+    ///
+    /// assert_eq!(gap.start(), gap.end());
+    /// assert_eq!(gap.start(), a.end());
     /// ```
-    pub const fn span(self) -> Span {
-        self.span
+    pub fn between(a: Span, b: Span) -> Span {
+        debug_assert!(a.end <= b.start, "`a` must come before `b`");
+
+        a.shrink_to_end()
     }
 
-    /// Returns the span content.
+    /// Returns the range of lines touched by the span.
+    ///
+    /// Both ends of the range are included, and lines are zero-indexed.
     ///
     /// # Example
     ///
     /// ```rust
-    /// use lisbeth_error::span:: SpannedStr;
+    /// use lisbeth_error::span::SpannedStr;
     ///
-    /// let a = SpannedStr::input_file("hello");
-    /// assert_eq!(a.content(), "hello");
+    /// let input = SpannedStr::input_file("foo\nbar\nbaz");
+    /// assert_eq!(input.span().line_range(), 0..=2);
     /// ```
-    pub const fn content(self) -> &'a str {
-        self.content
+    pub fn line_range(self) -> RangeInclusive<u32> {
+        self.start.line()..=self.end.line()
     }
 
-    /// Splits the spanned string at a given byte index.
+    /// Returns the range of columns covered by the span, if it spans a single
+    /// line.
     ///
-    /// This method works the same way as [str::split_at], but updates the span
-    /// so that it is still correct.
+    /// The start of the range is included and its end is excluded, and columns
+    /// are zero-indexed. Spans that cover more than one line do not have a
+    /// single column range, so `None` is returned for them.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let input = SpannedStr::input_file("let foo = 42;\nfoo");
+    ///
+    /// assert_eq!(input.find("foo").unwrap().span().columns(), Some(4..7));
+    /// assert_eq!(input.span().columns(), None);
+    /// ```
+    pub fn columns(self) -> Option<Range<u32>> {
+        if self.start.line() == self.end.line() {
+            Some(self.start.col()..self.end.col())
+        } else {
+            None
+        }
+    }
+
+    /// Returns whether the span starts at the beginning of a line.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let input = SpannedStr::input_file("foo bar");
+    ///
+    /// assert!(input.find("foo").unwrap().span().at_line_start());
+    /// assert!(!input.find("bar").unwrap().span().at_line_start());
+    /// ```
+    pub fn at_line_start(self) -> bool {
+        self.start.col() == 0
+    }
+
+    /// Returns whether the span ends at the end of a line of `source`.
+    ///
+    /// The line return is not part of the line, so a span ending right
+    /// before a `\n` or a `\r\n` is at the end of its line, as is a span
+    /// ending at the end of `source`. `source` must be the input the span
+    /// comes from.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let source = "foo bar\nbaz";
+    /// let input = SpannedStr::input_file(source);
+    ///
+    /// assert!(input.find("bar").unwrap().span().at_line_end(source));
+    /// assert!(!input.find("foo").unwrap().span().at_line_end(source));
+    /// ```
+    pub fn at_line_end(self, source: &str) -> bool {
+        match source.get(self.end.offset() as usize..) {
+            Some(tail) => tail.is_empty() || tail.starts_with('\n') || tail.starts_with("\r\n"),
+            None => false,
+        }
+    }
+
+    /// Returns the range of bytes covered by the span.
+    ///
+    /// This allows to use spans with crates that work with byte ranges. The
+    /// conversion can be reverted with the [`TryFrom`] implementation on
+    /// `(Range<usize>, &str)`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let source = "let foo = 42;";
+    /// let foo = SpannedStr::input_file(source).find("foo").unwrap().span();
+    ///
+    /// assert_eq!(foo.as_usize_range(), 4..7);
+    /// ```
+    pub fn as_usize_range(self) -> Range<usize> {
+        self.start.offset as usize..self.end.offset as usize
+    }
+
+    /// Returns the zero-indexed `(line, column)` pairs of the start and the
+    /// end of the span.
+    ///
+    /// This is the data needed to build a range in the Language Server
+    /// Protocol. Columns are counted in characters, which matches the
+    /// protocol as long as the text contains no character outside of the
+    /// Basic Multilingual Plane.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let input = SpannedStr::input_file("let a = 42;\nlet b = a;");
+    /// let b = input.find("b").unwrap().span();
+    ///
+    /// assert_eq!(b.to_lsp_range(), ((1, 4), (1, 5)));
+    /// ```
+    pub fn to_lsp_range(self) -> ((u32, u32), (u32, u32)) {
+        (
+            (self.start.line, self.start.col),
+            (self.end.line, self.end.col),
+        )
+    }
+
+    /// Translates a span of a child input unit into the coordinate system of
+    /// its parent.
+    ///
+    /// `parent_start` is the position, in the parent, at which the child input
+    /// unit starts. The span must come from a single child input unit which has
+    /// been created with [`SpannedStr::input_file`]. Columns are shifted only
+    /// on the first line of the child, as the other lines start on a new line
+    /// of the parent.
     ///
     /// # Panics
     ///
-    /// This method panics when one of the condition listed in [`str::split_at`]
-    /// is met.
+    /// This function panics if the rebased span ends more than `u32::MAX`
+    /// bytes after the beginning of the parent.
     ///
     /// # Example
     ///
     /// ```rust
     /// use lisbeth_error::span::SpannedStr;
     ///
-    /// let input = SpannedStr::input_file("helloworld");
-    /// let (left, right) = input.split_at(5);
+    /// let parent = SpannedStr::input_file("let s = {foo\nbar};");
+    /// let interpolated = parent.find("foo\nbar").unwrap();
     ///
-    /// assert_eq!(left.content(), "hello");
-    /// assert_eq!(right.content(), "world");
+    /// let child = SpannedStr::input_file("foo\nbar");
+    /// let bar = child.find("bar").unwrap();
+    ///
+    /// let rebased = bar.span().rebase(interpolated.span().start());
+    /// assert_eq!(rebased, parent.find("bar").unwrap().span());
     /// ```
-    pub fn split_at(self, idx: usize) -> (SpannedStr<'a>, SpannedStr<'a>) {
-        let (left_content, right_content) = self.content.split_at(idx);
+    pub fn rebase(self, parent_start: Position) -> Span {
+        let start = self.start.rebase(parent_start);
+        let end = self.end.rebase(parent_start);
 
-        let mid = self.span.start.advance_with(left_content);
-        let (left_span, right_span) = self.span.split_with(mid);
+        Span { start, end }
+    }
 
-        let left_sstr = SpannedStr {
-            span: left_span,
-            content: left_content,
+    /// Translates a span into the coordinate system of a child input unit
+    /// that starts at `base`.
+    ///
+    /// This is the inverse of [`rebase`](Span::rebase): the line, column and
+    /// offset of `base` are subtracted from both ends of the span. Columns are
+    /// shifted only on the line of `base`. `None` is returned if the span
+    /// starts before `base`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let parent = SpannedStr::input_file("let s = {foo\nbar};");
+    /// let interpolated = parent.find("foo\nbar").unwrap();
+    /// let bar = parent.find("bar").unwrap();
+    ///
+    /// let child = SpannedStr::input_file("foo\nbar");
+    ///
+    /// let relative = bar.span().relative_to(interpolated.span().start());
+    /// assert_eq!(relative, Some(child.find("bar").unwrap().span()));
+    /// ```
+    pub fn relative_to(self, base: Position) -> Option<Span> {
+        let start = self.start.relative_to(base)?;
+        let end = self.end.relative_to(base)?;
+
+        Some(Span { start, end })
+    }
+
+    /// Returns the span covered by both `self` and `other`, or `None` if they
+    /// do not overlap.
+    ///
+    /// Spans that only touch each other, such as a span and the span that
+    /// directly follows it, do not overlap: `None` is returned instead of an
+    /// empty span.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let input = SpannedStr::input_file("foobar");
+    /// let fooba = input.split_at(5).0;
+    /// let obar = input.split_at(2).1;
+    ///
+    /// assert_eq!(fooba.span().intersect(obar.span()), input.find("oba").map(|s| s.span()));
+    /// ```
+    pub fn intersect(self, other: Span) -> Option<Span> {
+        let start = self.start.max(other.start);
+        let end = self.end.min(other.end);
+
+        if start < end {
+            Some(Span { start, end })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the parts of the span that come before and after `inner`.
+    ///
+    /// `inner` must be contained in `self`. A part is `None` when it would be
+    /// empty, which happens when `inner` starts or ends at the same position as
+    /// `self`.
+    ///
+    /// # Panics
+    ///
+    /// In debug mode, this function panics if `inner` is not contained in
+    /// `self`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let input = SpannedStr::input_file("foo bar baz");
+    /// let bar = input.find("bar").unwrap().span();
+    ///
+    /// let (before, after) = input.span().difference(bar);
+    ///
+    /// assert_eq!(before, Some(input.split_at(4).0.span()));
+    /// assert_eq!(after, Some(input.split_at(7).1.span()));
+    /// ```
+    pub fn difference(self, inner: Span) -> (Option<Span>, Option<Span>) {
+        debug_assert!(
+            self.start <= inner.start && inner.end <= self.end,
+            "`inner` must be contained in `self`",
+        );
+
+        let before = if self.start < inner.start {
+            Some(Span {
+                start: self.start,
+                end: inner.start,
+            })
+        } else {
+            None
         };
 
-        let right_sstr = SpannedStr {
-            span: right_span,
-            content: right_content,
+        let after = if inner.end < self.end {
+            Some(Span {
+                start: inner.end,
+                end: self.end,
+            })
+        } else {
+            None
         };
 
-        (left_sstr, right_sstr)
+        (before, after)
     }
 
-    /// Returns the longest prefix of input that match a given a condition.
+    /// Checks whether `self` and `other` touch each other, meaning that one of
+    /// them ends where the other starts.
+    ///
+    /// Positions are compared by offset. As such, the consistency note of the
+    /// module documentation applies.
     ///
     /// # Example
     ///
     /// ```rust
     /// use lisbeth_error::span::SpannedStr;
     ///
-    /// let i = SpannedStr::input_file("42 101");
-    /// let (number, tail) = i.take_while(char::is_numeric);
+    /// let (foo, bar) = SpannedStr::input_file("foobar").split_at(3);
     ///
-    /// assert_eq!(number.content(), "42");
-    /// assert_eq!(tail.content(), " 101");
+    /// assert!(foo.span().is_adjacent_to(bar.span()));
+    /// assert!(bar.span().is_adjacent_to(foo.span()));
     /// ```
-    pub fn take_while<F>(self, mut f: F) -> (SpannedStr<'a>, SpannedStr<'a>)
+    pub fn is_adjacent_to(self, other: Span) -> bool {
+        self.end.offset == other.start.offset || other.end.offset == self.start.offset
+    }
+
+    /// Returns the smallest span that contains both `self` and `other`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let input = SpannedStr::input_file("foo bar");
+    /// let foo = input.split_at(3).0;
+    /// let bar = input.split_at(4).1;
+    ///
+    /// assert_eq!(foo.span().merge(bar.span()), input.span());
+    /// ```
+    pub fn merge(self, other: Span) -> Span {
+        let start = self.start.min(other.start);
+        let end = self.end.max(other.end);
+
+        Span { start, end }
+    }
+
+    /// Returns the smallest span that contains every span of `spans`, or
+    /// `None` if `spans` is empty.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::{Span, SpannedStr};
+    ///
+    /// let input = SpannedStr::input_file("foo bar baz");
+    /// let foo = input.split_at(3).0;
+    /// let baz = input.split_at(8).1;
+    ///
+    /// assert_eq!(Span::join_all(vec![foo.span(), baz.span()]), Some(input.span()));
+    /// assert_eq!(Span::join_all(Vec::new()), None);
+    /// ```
+    pub fn join_all<I>(spans: I) -> Option<Span>
     where
-        F: FnMut(char) -> bool,
+        I: IntoIterator<Item = Span>,
     {
-        let idx = self
-            .content
-            .char_indices()
-            .find(|(_, chr)| !f(*chr))
-            .map(|(idx, _)| idx)
-            .unwrap_or_else(|| self.content.len());
+        spans.into_iter().reduce(Span::merge)
+    }
 
-        self.split_at(idx)
+    /// Returns the span of the character following the current span, on the
+    /// same line.
+    ///
+    /// This function can be used when an unexpected EOF happens. The returned
+    /// span may go past the end of the input, see
+    /// [`Span::saturating_next_char`] for a variant that stops at the end of
+    /// the input.
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::{Span, SpannedStr};
+    ///
+    /// let input = SpannedStr::input_file("foo");
+    /// let after_input = input.span().next_char();
+    ///
+    /// assert_eq!(after_input.start().line(), 0);
+    /// assert_eq!(after_input.start().col(), 3);
+    ///
+    /// assert_eq!(after_input.end().line(), 0);
+    /// assert_eq!(after_input.end().col(), 4);
+    /// ```
+    pub fn next_char(self) -> Span {
+        self.after(1)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Returns a span that starts at the end of `self` and is `n` characters
+    /// wide.
+    ///
+    /// The returned span covers `n` single-byte characters on the same line.
+    /// It is a generalization of [`Span::next_char`], and can be used to
+    /// report that some text is missing after a token.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the end of the returned span is more than
+    /// `u32::MAX` bytes after the beginning of the input.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let input = SpannedStr::input_file("foo");
+    /// let after_input = input.span().after(3);
+    ///
+    /// assert_eq!(after_input.start().col(), 3);
+    /// assert_eq!(after_input.end().col(), 6);
+    /// assert_eq!(after_input.end().offset(), 6);
+    /// ```
+    pub fn after(self, n: usize) -> Span {
+        let start = self.end;
+        let shift = |value: u32| {
+            u32::try_from(n)
+                .ok()
+                .and_then(|n| value.checked_add(n))
+                .expect("Span end is out of bounds")
+        };
 
-    mod position {
-        use super::*;
+        let end = Position {
+            line: start.line,
+            col: shift(start.col),
+            offset: shift(start.offset),
+        };
 
-        #[test]
-        fn advance_with_no_line_return() {
-            let p = Position::BEGINNING.advance_with("hello, world");
+        Span { start, end }
+    }
 
-            assert_eq!(p.line, 0);
-            assert_eq!(p.col, 12);
-            assert_eq!(p.offset, 12);
-        }
+    /// Returns the span of the character following the current span, on the
+    /// same line, without going past the end of `source`.
+    ///
+    /// This is the same as [`Span::next_char`], except that an empty span is
+    /// returned when the span ends at the end of `source` or of a line.
+    /// `source` must be the content of the whole input unit the span comes
+    /// from.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let source = "foo";
+    /// let input = SpannedStr::input_file(source);
+    ///
+    /// let after_f = input.split_at(1).0.span().saturating_next_char(source);
+    /// assert_eq!(after_f.end().offset(), 2);
+    ///
+    /// let after_input = input.span().saturating_next_char(source);
+    /// assert_eq!(after_input.start(), after_input.end());
+    /// ```
+    pub fn saturating_next_char(self, source: &str) -> Span {
+        self.saturating_after(1, source)
+    }
 
-        #[test]
-        fn advance_with_line_return() {
-            let p = Position::BEGINNING.advance_with("\n\n\n");
+    /// Returns a span that starts at the end of `self` and is at most `n`
+    /// characters wide, without going past the end of `source`.
+    ///
+    /// This is the same as [`Span::after`], except that the returned span
+    /// stops at the end of `source` and at the end of the line, so that it
+    /// never covers characters that do not exist. `source` must be the
+    /// content of the whole input unit the span comes from.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let source = "foo";
+    /// let input = SpannedStr::input_file(source);
+    /// let after_f = input.split_at(1).0.span().saturating_after(5, source);
+    ///
+    /// assert_eq!(after_f.end().col(), 3);
+    /// assert_eq!(after_f.end().offset(), 3);
+    /// ```
+    pub fn saturating_after(self, n: usize, source: &str) -> Span {
+        let start = self.end;
+        let rest = source.get(start.offset as usize..).unwrap_or("");
 
-            assert_eq!(p.line, 3);
-            assert_eq!(p.col, 0);
-            assert_eq!(p.offset, 3);
-        }
+        let len = rest
+            .char_indices()
+            .take(n)
+            .take_while(|(_, chr)| *chr != '\n')
+            .last()
+            .map_or(0, |(idx, chr)| idx + chr.len_utf8());
 
-        #[test]
-        fn advance_with_mixed() {
-            let p = Position::BEGINNING.advance_with("Hello,\nworld");
+        let end = start.advance_with(&rest[..len]);
 
-            assert_eq!(p.line, 1);
-            assert_eq!(p.col, 5);
-            assert_eq!(p.offset, 12);
+        Span { start, end }
+    }
+
+    /// Moves the start and the end of the span by a given number of
+    /// characters.
+    ///
+    /// Positive deltas move towards the end of the input, negative deltas
+    /// move towards its beginning. `source` must be the content of the whole
+    /// input unit the span comes from. It is used to compute the line and
+    /// column of the new positions.
+    ///
+    /// `None` is returned if a position goes out of `source`, or if the
+    /// resulting start comes after the resulting end.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let source = "let foo = 42;";
+    /// let input = SpannedStr::input_file(source);
+    ///
+    /// // Oops, the span includes the space that follows `foo`.
+    /// let foo = input.split_at(4).1.split_at(4).0.span();
+    /// let fixed = foo.adjust(0, -1, source).unwrap();
+    ///
+    /// assert_eq!(fixed, input.find("foo").unwrap().span());
+    /// ```
+    pub fn adjust(self, start_delta: i32, end_delta: i32, source: &str) -> Option<Span> {
+        let start = shift_offset(source, self.start.offset as usize, start_delta)?;
+        let end = shift_offset(source, self.end.offset as usize, end_delta)?;
+
+        if start > end {
+            return None;
         }
 
-        #[test]
-        fn advance_with_empty() {
+        let start_pos = Position::BEGINNING.checked_advance_with(&source[..start])?;
+        let end_pos = start_pos.checked_advance_with(&source[start..end])?;
+
+        Some(Span {
+            start: start_pos,
+            end: end_pos,
+        })
+    }
+
+    /// Returns the position located at the middle of the span.
+    ///
+    /// The middle is computed in characters: the returned position is preceded
+    /// by half of the characters of the span, rounded down. `source` must be
+    /// the content of the whole input unit the span comes from, as the line
+    /// and column of the position can not be computed without it.
+    ///
+    /// `None` is returned if the span goes out of `source` or does not start
+    /// and end on character boundaries.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let source = "let foo = 42;";
+    /// let foo = SpannedStr::input_file(source).find("foo").unwrap().span();
+    ///
+    /// assert_eq!(foo.center(source).unwrap().col(), 5);
+    /// ```
+    pub fn center(self, source: &str) -> Option<Position> {
+        let text = source.get(self.start.offset as usize..self.end.offset as usize)?;
+
+        let half = text.chars().count() / 2;
+        let idx = text
+            .char_indices()
+            .nth(half)
+            .map_or(text.len(), |(idx, _)| idx);
+
+        Some(self.start.advance_with(&text[..idx]))
+    }
+
+    /// Expands the span to the paragraph that contains it.
+    ///
+    /// Paragraphs are separated by blank lines, which are lines that are empty
+    /// or that contain only whitespaces. The returned span starts at the
+    /// beginning of the first line of the paragraph and ends at the end of its
+    /// last line, excluding the line return. `source` must be the content of
+    /// the whole input unit the span comes from.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the span goes past the end of `source`, or if it does not
+    /// start and end on character boundaries.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let source = "Title\n\nFirst line,\nsecond line.\n\nEnd";
+    /// let input = SpannedStr::input_file(source);
+    /// let second = input.find("second").unwrap().span();
+    ///
+    /// let paragraph = second.enclosing_paragraph(source);
+    ///
+    /// assert_eq!(&source[paragraph], "First line,\nsecond line.");
+    /// ```
+    pub fn enclosing_paragraph(self, source: &str) -> Span {
+        let is_blank = |line: &str| line.trim().is_empty();
+
+        let start_offset = self.start.offset as usize;
+        let mut start = source[..start_offset].rfind('\n').map_or(0, |idx| idx + 1);
+
+        while start > 0 {
+            let prev_start = source[..start - 1].rfind('\n').map_or(0, |idx| idx + 1);
+
+            if is_blank(&source[prev_start..start - 1]) {
+                break;
+            }
+
+            start = prev_start;
+        }
+
+        let end_offset = self.end.offset as usize;
+        let line_end = |from: usize| {
+            source[from..]
+                .find('\n')
+                .map_or(source.len(), |idx| from + idx)
+        };
+        let mut end = line_end(end_offset);
+
+        while end < source.len() {
+            let next_end = line_end(end + 1);
+
+            if is_blank(&source[end + 1..next_end]) {
+                break;
+            }
+
+            end = next_end;
+        }
+
+        let start_pos = Position::BEGINNING.advance_with(&source[..start]);
+        let end_pos = start_pos.advance_with(&source[start..end]);
+
+        Span::new(start_pos, end_pos)
+    }
+}
+
+// Returns the byte offset located delta characters after offset in source, or
+// None if it goes out of source.
+fn shift_offset(source: &str, offset: usize, delta: i32) -> Option<usize> {
+    if !source.is_char_boundary(offset) {
+        return None;
+    }
+
+    let steps = delta.unsigned_abs() as usize;
+
+    if delta >= 0 {
+        source[offset..]
+            .char_indices()
+            .map(|(idx, _)| offset + idx)
+            .chain(iter::once(source.len()))
+            .nth(steps)
+    } else {
+        source[..offset]
+            .char_indices()
+            .rev()
+            .map(|(idx, _)| idx)
+            .nth(steps - 1)
+    }
+}
+
+/// Prints the span on a single line, as `Span(line:col[offset]..line:col[offset])`.
+///
+/// The lines and columns are zero-indexed.
+impl Debug for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Span { start, end } = self;
+
+        write!(
+            f,
+            "Span({}:{}[{}]..{}:{}[{}])",
+            start.line, start.col, start.offset, end.line, end.col, end.offset,
+        )
+    }
+}
+
+/// Returns the part of the string covered by the span.
+///
+/// The string must be the content of the whole input unit the span comes
+/// from, so that the offsets of the span match.
+///
+/// # Panics
+///
+/// Panics if the span goes past the end of the string, or if it does not
+/// start and end on character boundaries, as string slicing does.
+///
+/// # Example
+///
+/// ```rust
+/// use lisbeth_error::span::SpannedStr;
+///
+/// let source = "let foo = 42;";
+/// let foo = SpannedStr::input_file(source).find("foo").unwrap().span();
+///
+/// assert_eq!(&source[foo], "foo");
+/// ```
+impl Index<Span> for str {
+    type Output = str;
+
+    fn index(&self, span: Span) -> &str {
+        &self[span.start.offset as usize..span.end.offset as usize]
+    }
+}
+
+/// Returns the part of the string covered by the span.
+///
+/// See the implementation for [`str`] for more information.
+impl Index<Span> for String {
+    type Output = str;
+
+    fn index(&self, span: Span) -> &str {
+        &self.as_str()[span]
+    }
+}
+
+/// Creates a span from a range of bytes of a source text.
+///
+/// The source must be the content of the whole input unit, so that the line
+/// and column of each end of the span can be computed. This is the inverse of
+/// [`Span::as_usize_range`].
+///
+/// # Example
+///
+/// ```rust
+/// use std::convert::TryFrom;
+///
+/// use lisbeth_error::span::Span;
+///
+/// let source = "let foo = 42;\nfoo";
+/// let span = Span::try_from((14..17, source)).unwrap();
+///
+/// assert_eq!(span.start().line(), 1);
+/// assert_eq!(&source[span], "foo");
+/// ```
+impl TryFrom<(Range<usize>, &str)> for Span {
+    type Error = RangeError;
+
+    fn try_from((range, source): (Range<usize>, &str)) -> Result<Span, RangeError> {
+        if range.start > range.end {
+            return Err(RangeError::Reversed);
+        }
+
+        if range.end > source.len() {
+            return Err(RangeError::OutOfBounds);
+        }
+
+        if !source.is_char_boundary(range.start) || !source.is_char_boundary(range.end) {
+            return Err(RangeError::NotCharBoundary);
+        }
+
+        let start = Position::BEGINNING
+            .checked_advance_with(&source[..range.start])
+            .ok_or(RangeError::OutOfBounds)?;
+        let end = start
+            .checked_advance_with(&source[range])
+            .ok_or(RangeError::OutOfBounds)?;
+
+        Ok(Span { start, end })
+    }
+}
+
+/// The error returned when a range of bytes can not be converted to a
+/// [`Span`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RangeError {
+    /// The range starts after its end.
+    Reversed,
+    /// The range goes past the end of the source text, or its offsets can not
+    /// be represented by a `u32`.
+    OutOfBounds,
+    /// One end of the range is inside a character.
+    NotCharBoundary,
+}
+
+impl Display for RangeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RangeError::Reversed => write!(f, "range starts after its end"),
+            RangeError::OutOfBounds => write!(f, "range is out of the source bounds"),
+            RangeError::NotCharBoundary => write!(f, "range is not on char boundaries"),
+        }
+    }
+}
+
+impl Error for RangeError {}
+
+/// Represents a portion of input file.
+///
+/// This is represented the same way as [`Span`], but with an additionnal
+/// content field.
+///
+/// It is initially created with the [`input_file`] function, and can then be
+/// splitted at desired index. Its content and span can be accessed with the
+/// [`content`] and [`span`] methods.
+///
+/// # Example
+///
+/// The following code shows how to extract a sequence of numbers separated by
+/// non-digit characters.
+///
+/// ```rust
+/// use lisbeth_error::span::{Span, SpannedStr};
+///
+/// #[derive(Debug)]
+/// struct Number(u32, Span);
+///
+/// // Parses a number from input, if any failure occurs, returns None
+/// fn extract_number<'a>(input: SpannedStr<'a>) -> (Number, SpannedStr<'a>) {
+///     let (matched, tail) = input.take_while(char::is_numeric);
+///
+///     let value = matched.content().parse().unwrap();
+///     let number = Number(value, matched.span());
+///     (number, tail)
+/// }
+///
+/// let input = SpannedStr::input_file("42 or nothing");
+/// let (number, tail) = extract_number(input);
+///
+/// assert_eq!(number.0, 42);
+/// assert_eq!(tail.content(), " or nothing");
+/// ```
+///
+/// [`input_file`]: SpannedStr::input_file
+/// [`content`]: SpannedStr::content
+/// [`span`]: SpannedStr::span
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SpannedStr<'a> {
+    span: Span,
+    content: &'a str,
+}
+
+impl<'a> SpannedStr<'a> {
+    /// Creates a new [`SpannedStr`] from an input file.
+    ///
+    /// This returned spanned string can then be splitted at various places
+    /// during the parsing step.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let file_content = "fn main() { println!(\"Hello, world!\"); }";
+    ///
+    /// let whole_file = SpannedStr::input_file(file_content);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the input is larger than `u32::MAX` bytes. See
+    /// [`try_input_file`] for a non-panicking version.
+    ///
+    /// [`try_input_file`]: SpannedStr::try_input_file
+    pub fn input_file(content: &'a str) -> SpannedStr<'a> {
+        let span = Span::of_file(content);
+
+        SpannedStr { span, content }
+    }
+
+    /// Creates a new [`SpannedStr`] from an input file, or returns `None` if
+    /// the input is too large to be spanned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let whole_file = SpannedStr::try_input_file("hello").unwrap();
+    /// assert_eq!(whole_file.content(), "hello");
+    /// ```
+    pub fn try_input_file(content: &'a str) -> Option<SpannedStr<'a>> {
+        let span = Span::checked_of_file(content)?;
+
+        Some(SpannedStr { span, content })
+    }
+
+    /// Creates a spanned string from arbitrary content and span.
+    ///
+    /// This is meant for synthetic tokens, such as a token inserted at a given
+    /// position during error recovery. Unlike the other constructors, no check
+    /// is performed: `content` does not need to match the text covered by
+    /// `span`, and may even be empty.
+    ///
+    /// # Warning
+    ///
+    /// Splitting a synthetic spanned string computes spans from its content,
+    /// which may not be consistent with the input unit it is used with.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let input = SpannedStr::input_file("let a = 42");
+    /// let missing_semi = SpannedStr::synthetic("", input.span().shrink_to_end());
+    ///
+    /// assert_eq!(missing_semi.content(), "");
+    /// assert_eq!(missing_semi.span().start(), input.span().end());
+    /// ```
+    pub const fn synthetic(content: &'a str, span: Span) -> SpannedStr<'a> {
+        SpannedStr { content, span }
+    }
+
+    // Note: span must represent the same source as content, otherwise
+    // inconsistent results may occur.
+    //
+    // In debug mode, it is ensured that:
+    //   - span.start == Position::BEGINNING,
+    //   - span.end.offset == content.len().
+    pub(crate) fn assemble(content: &'a str, span: Span) -> SpannedStr<'a> {
+        debug_assert_eq!(
+            span.start,
+            Position::BEGINNING,
+            "Attempt to create a SpannedStr that does not start at the beginning of the file",
+        );
+        debug_assert_eq!(
+            span.end.offset as usize,
+            content.len(),
+            "Attempt to create a SpannedStr with an incorrect length",
+        );
+
+        SpannedStr { content, span }
+    }
+
+    /// Returns the contained [`Span`].
+    ///
+    /// The span contains the position at which the content is located in the
+    /// input data.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let a = SpannedStr::input_file("foo bar");
+    /// let b = SpannedStr::input_file("baz qux");
+    ///
+    /// // a and b have the same length and the same starting point, so they
+    /// // have the same span.
+    /// assert_eq!(a.span(), b.span());
+    /// ```
+    pub const fn span(self) -> Span {
+        self.span
+    }
+
+    /// Returns the span content.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span:: SpannedStr;
+    ///
+    /// let a = SpannedStr::input_file("hello");
+    /// assert_eq!(a.content(), "hello");
+    /// ```
+    pub const fn content(self) -> &'a str {
+        self.content
+    }
+
+    /// Returns the span content, with its special characters escaped.
+    ///
+    /// Characters are escaped as [`str::escape_debug`] does. This keeps error
+    /// messages on a single line when the content contains line returns or
+    /// other control characters.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let input = SpannedStr::input_file("foo\nbar");
+    /// assert_eq!(input.escaped_content(), "foo\\nbar");
+    /// ```
+    pub fn escaped_content(self) -> String {
+        self.content.escape_debug().to_string()
+    }
+
+    /// Returns the span content, as a byte slice.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let a = SpannedStr::input_file("hello");
+    /// assert_eq!(a.as_bytes(), b"hello");
+    /// ```
+    pub const fn as_bytes(self) -> &'a [u8] {
+        self.content.as_bytes()
+    }
+
+    /// Returns the length of the span content, in bytes.
+    ///
+    /// This is consistent with the length of [`content`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let a = SpannedStr::input_file("Vélo");
+    /// assert_eq!(a.len(), 5);
+    /// ```
+    ///
+    /// [`content`]: SpannedStr::content
+    pub const fn len(self) -> usize {
+        self.content.len()
+    }
+
+    /// Returns whether the span content is empty.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// assert!(SpannedStr::input_file("").is_empty());
+    /// assert!(!SpannedStr::input_file("hello").is_empty());
+    /// ```
+    pub const fn is_empty(self) -> bool {
+        self.content.is_empty()
+    }
+
+    /// Returns the number of lines of the content, without collecting them.
+    ///
+    /// Every line return starts a new line, so a content ending with a line
+    /// return has an empty last line. An empty content has no line at all.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// assert_eq!(SpannedStr::input_file("").line_count(), 0);
+    /// assert_eq!(SpannedStr::input_file("foo\nbar").line_count(), 2);
+    /// assert_eq!(SpannedStr::input_file("foo\n").line_count(), 2);
+    /// ```
+    pub fn line_count(self) -> usize {
+        if self.content.is_empty() {
+            0
+        } else {
+            1 + self.content.matches('\n').count()
+        }
+    }
+
+    /// Splits the spanned string at a given byte index.
+    ///
+    /// This method works the same way as [str::split_at], but updates the span
+    /// so that it is still correct.
+    ///
+    /// `idx` is a *byte* index, not a character count. Use
+    /// [`split_at_char`](SpannedStr::split_at_char) to split after a given
+    /// number of characters.
+    ///
+    /// # Panics
+    ///
+    /// This method panics when one of the condition listed in [`str::split_at`]
+    /// is met.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let input = SpannedStr::input_file("helloworld");
+    /// let (left, right) = input.split_at(5);
+    ///
+    /// assert_eq!(left.content(), "hello");
+    /// assert_eq!(right.content(), "world");
+    /// ```
+    pub fn split_at(self, idx: usize) -> (SpannedStr<'a>, SpannedStr<'a>) {
+        let (left_content, right_content) = self.content.split_at(idx);
+
+        let mid = self.span.start.advance_with(left_content);
+        let (left_span, right_span) = self.span.split_with(mid);
+
+        let left_sstr = SpannedStr {
+            span: left_span,
+            content: left_content,
+        };
+
+        let right_sstr = SpannedStr {
+            span: right_span,
+            content: right_content,
+        };
+
+        (left_sstr, right_sstr)
+    }
+
+    /// Splits the spanned string after its `n` first characters.
+    ///
+    /// Unlike [`split_at`](SpannedStr::split_at), `n` counts characters, not
+    /// bytes, so this method never splits in the middle of a character. If the
+    /// string has fewer than `n` characters, the whole string is returned on
+    /// the left side, and the right side is empty.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let input = SpannedStr::input_file("Vélo");
+    /// let (left, right) = input.split_at_char(2);
+    ///
+    /// assert_eq!(left.content(), "Vé");
+    /// assert_eq!(right.content(), "lo");
+    /// ```
+    pub fn split_at_char(self, n: usize) -> (SpannedStr<'a>, SpannedStr<'a>) {
+        let idx = self
+            .content
+            .char_indices()
+            .nth(n)
+            .map_or(self.content.len(), |(idx, _)| idx);
+
+        self.split_at(idx)
+    }
+
+    /// Returns the byte offset, relative to the start of the spanned string,
+    /// of its character at index `char_idx`.
+    ///
+    /// The length of the content is returned when `char_idx` is equal to the
+    /// number of characters, so that the result can always be passed to
+    /// [`split_at`](SpannedStr::split_at). `None` is returned when `char_idx`
+    /// is greater than the number of characters.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let input = SpannedStr::input_file("Vélo");
+    ///
+    /// assert_eq!(input.byte_offset_of_char(2), Some(3));
+    /// assert_eq!(input.byte_offset_of_char(4), Some(5));
+    /// assert_eq!(input.byte_offset_of_char(5), None);
+    /// ```
+    pub fn byte_offset_of_char(self, char_idx: usize) -> Option<usize> {
+        self.content
+            .char_indices()
+            .map(|(idx, _)| idx)
+            .chain(iter::once(self.content.len()))
+            .nth(char_idx)
+    }
+
+    /// Returns the range of bytes covered by the spanned string within
+    /// `parent`, or `None` if it is not contained in `parent`.
+    ///
+    /// The range is computed from the offsets of both spans. The contents are
+    /// also checked to point to the same memory, so that a spanned string
+    /// coming from another input is never considered contained, even if its
+    /// offsets fit in `parent`. This is cheap: no character is compared.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let input = SpannedStr::input_file("let foo = 42;");
+    /// let tail = input.split_at(4).1;
+    /// let foo = tail.find("foo").unwrap();
+    ///
+    /// assert_eq!(foo.offset_within(tail), Some(0..3));
+    /// assert_eq!(foo.offset_within(input), Some(4..7));
+    /// assert_eq!(input.offset_within(foo), None);
+    /// ```
+    pub fn offset_within(self, parent: SpannedStr) -> Option<Range<usize>> {
+        let start = self
+            .span
+            .start()
+            .offset()
+            .checked_sub(parent.span.start().offset())? as usize;
+        let end = start + self.content.len();
+
+        let same_memory =
+            parent.content.as_ptr() as usize + start == self.content.as_ptr() as usize;
+
+        if end <= parent.content.len() && same_memory {
+            Some(start..end)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the first occurrence of `pat` in the spanned string, with its
+    /// span, or `None` if `pat` is not found.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let input = SpannedStr::input_file("let foo = 42;\nlet bar = foo;");
+    /// let bar = input.find("bar").unwrap();
+    ///
+    /// assert_eq!(bar.content(), "bar");
+    /// assert_eq!(bar.span().start().line(), 1);
+    /// assert_eq!(bar.span().start().col(), 4);
+    ///
+    /// assert!(input.find("baz").is_none());
+    /// ```
+    pub fn find(self, pat: &str) -> Option<SpannedStr<'a>> {
+        let idx = self.content.find(pat)?;
+        let (_, tail) = self.split_at(idx);
+        let (matched, _) = tail.split_at(pat.len());
+
+        Some(matched)
+    }
+
+    /// Returns the byte index of the first occurrence of `c` in the spanned
+    /// string, relative to its start, and the span of that character, or
+    /// `None` if `c` is not found.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let input = SpannedStr::input_file("foo = 42;");
+    /// let (idx, span) = input.find_char('=').unwrap();
+    ///
+    /// assert_eq!(idx, 4);
+    /// assert_eq!(span.start().col(), 4);
+    ///
+    /// assert!(input.find_char('+').is_none());
+    /// ```
+    pub fn find_char(self, c: char) -> Option<(usize, Span)> {
+        let idx = self.content.find(c)?;
+        let (_, tail) = self.split_at(idx);
+        let (matched, _) = tail.split_at(c.len_utf8());
+
+        Some((idx, matched.span()))
+    }
+
+    /// Splits the spanned string right after its first line return.
+    ///
+    /// The first part contains the first line, including its trailing `\n`.
+    /// The second part contains the rest of the input. If the input contains
+    /// no line return, then the whole input is returned as the first part, and
+    /// the second part is empty.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let input = SpannedStr::input_file("hello\nworld");
+    /// let (first, rest) = input.split_at_first_line();
+    ///
+    /// assert_eq!(first.content(), "hello\n");
+    /// assert_eq!(rest.content(), "world");
+    /// assert_eq!(rest.span().start().line(), 1);
+    /// ```
+    pub fn split_at_first_line(self) -> (SpannedStr<'a>, SpannedStr<'a>) {
+        let idx = self
+            .content
+            .find('\n')
+            .map_or(self.content.len(), |idx| idx + 1);
+
+        self.split_at(idx)
+    }
+
+    /// Splits the spanned string on runs of separator characters.
+    ///
+    /// Each character for which `is_separator` returns `true` is a separator.
+    /// The returned segments contain no separator, and are never empty.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let input = SpannedStr::input_file("foo  bar baz");
+    /// let words = input.tokenize(char::is_whitespace);
+    ///
+    /// assert_eq!(words, ["foo", "bar", "baz"]);
+    /// assert_eq!(words[1].span().start().col(), 5);
+    /// ```
+    pub fn tokenize<F>(self, mut is_separator: F) -> Vec<SpannedStr<'a>>
+    where
+        F: FnMut(char) -> bool,
+    {
+        let mut segments = Vec::new();
+        let mut input = self;
+
+        loop {
+            let (_, tail) = input.take_while(&mut is_separator);
+
+            if tail.content.is_empty() {
+                return segments;
+            }
+
+            let (segment, tail) = tail.take_while(|chr| !is_separator(chr));
+            segments.push(segment);
+            input = tail;
+        }
+    }
+
+    /// Splits the spanned string at each occurrence of `delim`, ignoring a
+    /// trailing `delim`.
+    ///
+    /// This works the same way as [`str::split_terminator`]: unlike a plain
+    /// split, no empty segment is yielded after a trailing delimiter. This is
+    /// useful to parse records that are terminated by a given character, such
+    /// as a line return.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let input = SpannedStr::input_file("foo\nbar\n");
+    /// let records = input.split_terminator('\n').collect::<Vec<_>>();
+    ///
+    /// assert_eq!(records, ["foo", "bar"]);
+    /// assert_eq!(records[1].span().start().line(), 1);
+    /// ```
+    pub fn split_terminator(self, delim: char) -> impl Iterator<Item = SpannedStr<'a>> + 'a {
+        let mut rest = Some(self);
+
+        iter::from_fn(move || {
+            let input = rest.take()?;
+
+            if input.content.is_empty() {
+                return None;
+            }
+
+            match input.content.find(delim) {
+                Some(idx) => {
+                    let (segment, tail) = input.split_at(idx);
+                    rest = Some(tail.split_at(delim.len_utf8()).1);
+                    Some(segment)
+                }
+                None => Some(input),
+            }
+        })
+    }
+
+    /// Repeatedly applies `f` to the rest of the input, yielding the values it
+    /// returns.
+    ///
+    /// `f` is given the part of the input that has not been consumed yet, and
+    /// must advance it past what it consumed, for instance with
+    /// [`split_at`](SpannedStr::split_at). The iteration stops as soon as `f`
+    /// returns `None`. This allows to write simple tokenizers without
+    /// defining terminals and tokens.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let input = SpannedStr::input_file("foo bar");
+    /// let words = input
+    ///     .scan_tokens(|rest| {
+    ///         let (word, tail) = rest.take_while(char::is_whitespace).1.take_while(|c| !c.is_whitespace());
+    ///         *rest = tail;
+    ///         Some(word).filter(|word| !word.content().is_empty())
+    ///     })
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert_eq!(words, ["foo", "bar"]);
+    /// ```
+    pub fn scan_tokens<T, F>(self, mut f: F) -> impl Iterator<Item = T> + 'a
+    where
+        F: FnMut(&mut SpannedStr<'a>) -> Option<T> + 'a,
+    {
+        let mut rest = self;
+
+        iter::from_fn(move || f(&mut rest))
+    }
+
+    /// Returns the last line of the spanned string.
+    ///
+    /// The returned line does not contain any line return. As with
+    /// [`str::lines`], a trailing line return does not start a new line, so
+    /// the line preceding it is returned. If the input is empty, then an empty
+    /// spanned string is returned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let input = SpannedStr::input_file("hello\nworld\n");
+    /// let last = input.last_line();
+    ///
+    /// assert_eq!(last.content(), "world");
+    /// assert_eq!(last.span().start().line(), 1);
+    /// ```
+    pub fn last_line(self) -> SpannedStr<'a> {
+        let content = self.content.strip_suffix('\n').unwrap_or(self.content);
+        let start = content.rfind('\n').map_or(0, |idx| idx + 1);
+
+        let (_, tail) = self.split_at(start);
+        tail.split_at(content.len() - start).0
+    }
+
+    /// Returns an iterator over the characters of the spanned string.
+    ///
+    /// The characters do not carry any span. This is equivalent to calling
+    /// [`str::chars`] on the content.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let input = SpannedStr::input_file("abc");
+    /// assert_eq!(input.chars().rev().collect::<String>(), "cba");
+    /// ```
+    pub fn chars(self) -> Chars<'a> {
+        self.content.chars()
+    }
+
+    /// Returns the first character of the spanned string and its span, or
+    /// `None` if the spanned string is empty.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let input = SpannedStr::input_file("hello");
+    /// let (chr, span) = input.first_char().unwrap();
+    ///
+    /// assert_eq!(chr, 'h');
+    /// assert_eq!(span, input.split_at(1).0.span());
+    ///
+    /// assert!(SpannedStr::input_file("").first_char().is_none());
+    /// ```
+    pub fn first_char(self) -> Option<(char, Span)> {
+        let chr = self.content.chars().next()?;
+        let (matched, _) = self.split_at(chr.len_utf8());
+
+        Some((chr, matched.span()))
+    }
+
+    /// Checks whether the content of the spanned string is equal to `other`,
+    /// ignoring ASCII case.
+    ///
+    /// This is useful when matching case-insensitive keywords.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let i = SpannedStr::input_file("SeLeCt");
+    ///
+    /// assert!(i.eq_ignore_ascii_case("select"));
+    /// assert!(!i.eq_ignore_ascii_case("insert"));
+    /// ```
+    pub fn eq_ignore_ascii_case(self, other: &str) -> bool {
+        self.content.eq_ignore_ascii_case(other)
+    }
+
+    /// Returns the number of leading characters that match a given condition.
+    ///
+    /// The result is counted in characters, not in bytes.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let i = SpannedStr::input_file("ééé!");
+    /// assert_eq!(i.count_while(char::is_alphabetic), 3);
+    /// ```
+    pub fn count_while<F>(self, mut f: F) -> usize
+    where
+        F: FnMut(char) -> bool,
+    {
+        self.content.chars().take_while(|chr| f(*chr)).count()
+    }
+
+    /// Returns the longest prefix of input that match a given a condition.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let i = SpannedStr::input_file("42 101");
+    /// let (number, tail) = i.take_while(char::is_numeric);
+    ///
+    /// assert_eq!(number.content(), "42");
+    /// assert_eq!(tail.content(), " 101");
+    /// ```
+    pub fn take_while<F>(self, mut f: F) -> (SpannedStr<'a>, SpannedStr<'a>)
+    where
+        F: FnMut(char) -> bool,
+    {
+        let idx = self
+            .content
+            .char_indices()
+            .find(|(_, chr)| !f(*chr))
+            .map(|(idx, _)| idx)
+            .unwrap_or_else(|| self.content.len());
+
+        self.split_at(idx)
+    }
+
+    /// Removes the leading characters that match a given condition.
+    ///
+    /// The span of the returned [`SpannedStr`] starts after the removed
+    /// characters. This is equivalent to the second element returned by
+    /// [`SpannedStr::take_while`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::span::SpannedStr;
+    ///
+    /// let i = SpannedStr::input_file("  \n foo");
+    /// let foo = i.trim_start_matches(char::is_whitespace);
+    ///
+    /// assert_eq!(foo.content(), "foo");
+    /// assert_eq!(foo.span().start().line(), 1);
+    /// ```
+    pub fn trim_start_matches<F>(self, f: F) -> SpannedStr<'a>
+    where
+        F: FnMut(char) -> bool,
+    {
+        self.take_while(f).1
+    }
+}
+
+/// Compares the content of the spanned string with a string slice, ignoring
+/// its span.
+impl PartialEq<str> for SpannedStr<'_> {
+    fn eq(&self, other: &str) -> bool {
+        self.content == other
+    }
+}
+
+/// Compares the content of the spanned string with a string slice, ignoring
+/// its span.
+///
+/// # Example
+///
+/// ```rust
+/// use lisbeth_error::span::SpannedStr;
+///
+/// let (foo, _) = SpannedStr::input_file("foo bar").split_at(3);
+/// assert_eq!(foo, "foo");
+/// ```
+impl PartialEq<&str> for SpannedStr<'_> {
+    fn eq(&self, other: &&str) -> bool {
+        self.content == *other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::{format, vec};
+
+    mod position {
+        use super::*;
+
+        #[test]
+        fn default_is_beginning() {
+            assert_eq!(Position::default(), Position::BEGINNING);
+        }
+
+        #[test]
+        fn from_tuple() {
+            let p = Position::from((3, 7, 42));
+
+            assert_eq!(p.line(), 3);
+            assert_eq!(p.col(), 7);
+            assert_eq!(p.offset(), 42);
+
+            let q: Position = (0, 0, 0).into();
+            assert_eq!(q, Position::BEGINNING);
+        }
+
+        #[test]
+        fn new_sets_fields() {
+            let p = Position::new(2, 3, 15);
+
+            assert_eq!(p.line, 2);
+            assert_eq!(p.col, 3);
+            assert_eq!(p.offset, 15);
+        }
+
+        #[test]
+        fn visual_col_without_tabs() {
+            let p = Position::new(0, 3, 3);
+
+            assert_eq!(p.visual_col("foo bar", 4), 3);
+        }
+
+        #[test]
+        fn visual_col_tabs_at_various_positions() {
+            let line = "\ta\tbc\td";
+            let col_of = |chr| line.chars().position(|c| c == chr).unwrap() as u32;
+            let visual =
+                |chr, tab_width| Position::new(0, col_of(chr), 0).visual_col(line, tab_width);
+
+            assert_eq!(visual('a', 4), 4);
+            assert_eq!(visual('b', 4), 8);
+            assert_eq!(visual('d', 4), 12);
+
+            assert_eq!(visual('a', 8), 8);
+            assert_eq!(visual('b', 8), 16);
+            assert_eq!(visual('d', 8), 24);
+
+            assert_eq!(visual('a', 3), 3);
+            assert_eq!(visual('b', 3), 6);
+            assert_eq!(visual('d', 3), 9);
+        }
+
+        #[test]
+        fn visual_col_zero_tab_width() {
+            let p = Position::new(0, 3, 3);
+
+            assert_eq!(p.visual_col("\t\ta", 0), 3);
+        }
+
+        #[test]
+        fn from_line_col_in_valid() {
+            let source = "héllo\nwörld";
+
+            assert_eq!(
+                Position::from_line_col_in(source, 0, 2),
+                Some(Position::new(0, 2, 3)),
+            );
+            assert_eq!(
+                Position::from_line_col_in(source, 1, 3),
+                Some(Position::new(1, 3, 11)),
+            );
+        }
+
+        #[test]
+        fn from_line_col_in_end_of_line() {
+            let p = Position::from_line_col_in("foo\nbar", 0, 3).unwrap();
+
+            assert_eq!(p, Position::new(0, 3, 3));
+        }
+
+        #[test]
+        fn from_line_col_in_out_of_range() {
+            let source = "foo\nbar";
+
+            assert_eq!(Position::from_line_col_in(source, 2, 0), None);
+            assert_eq!(Position::from_line_col_in(source, 0, 4), None);
+        }
+
+        #[test]
+        fn advance_with_no_line_return() {
+            let p = Position::BEGINNING.advance_with("hello, world");
+
+            assert_eq!(p.line, 0);
+            assert_eq!(p.col, 12);
+            assert_eq!(p.offset, 12);
+        }
+
+        #[test]
+        fn advance_with_line_return() {
+            let p = Position::BEGINNING.advance_with("\n\n\n");
+
+            assert_eq!(p.line, 3);
+            assert_eq!(p.col, 0);
+            assert_eq!(p.offset, 3);
+        }
+
+        #[test]
+        fn advance_with_mixed() {
+            let p = Position::BEGINNING.advance_with("Hello,\nworld");
+
+            assert_eq!(p.line, 1);
+            assert_eq!(p.col, 5);
+            assert_eq!(p.offset, 12);
+        }
+
+        #[test]
+        fn advance_with_empty() {
             let p = Position::BEGINNING.advance_with("");
             assert_eq!(p, Position::BEGINNING);
         }
 
         #[test]
-        fn advance_with_two_times() {
-            let p = Position::BEGINNING.advance_with("foo bar");
-            let p = p.advance_with(" baz");
+        fn advance_with_two_times() {
+            let p = Position::BEGINNING.advance_with("foo bar");
+            let p = p.advance_with(" baz");
+
+            assert_eq!(p.line, 0);
+            assert_eq!(p.col, 11);
+            assert_eq!(p.offset, 11);
+        }
+
+        #[test]
+        fn checked_advance_with_consistent() {
+            let p = Position::BEGINNING.checked_advance_with("Hello,\nworld");
+
+            assert_eq!(p, Some(Position::BEGINNING.advance_with("Hello,\nworld")));
+        }
+
+        #[test]
+        fn checked_advance_with_offset_overflow() {
+            let p = Position {
+                line: 0,
+                col: 0,
+                offset: u32::MAX - 2,
+            };
+
+            assert!(p.checked_advance_with("ab").is_some());
+            assert!(p.checked_advance_with("abc").is_none());
+        }
+
+        #[test]
+        fn checked_advance_with_col_overflow() {
+            let p = Position {
+                line: 0,
+                col: u32::MAX,
+                offset: 0,
+            };
+
+            assert!(p.checked_advance_with("\n").is_some());
+            assert!(p.checked_advance_with("a").is_none());
+        }
+
+        #[test]
+        fn ord_simple() {
+            let p = Position::BEGINNING.advance_with("hello, world!");
+            let q = p.advance_with(" How are you?");
+
+            assert!(p < q);
+        }
+
+        #[test]
+        fn ord_consistent() {
+            let p = Position::BEGINNING.advance_with("hello,\nworld");
+            let q = p.advance_with("!");
+
+            assert!(p < q);
+            assert!(q > p);
+            assert_eq!(p.cmp(&p), Ordering::Equal);
+        }
+
+        #[test]
+        #[cfg(debug_assertions)]
+        #[should_panic(expected = "Attempt to perform an inconsistent span comparaison")]
+        fn ord_inconsistent_panics() {
+            let p = Position {
+                line: 10,
+                col: 20,
+                offset: 1000,
+            };
+
+            let q = Position {
+                line: 100,
+                col: 25,
+                offset: 10,
+            };
+
+            let _ = p.cmp(&q);
+        }
+
+        #[test]
+        #[cfg(not(debug_assertions))]
+        fn ord_only_cares_about_offset() {
+            // This is part of the inconsistency paragraph in the module documentation
+            let p = Position {
+                line: 10,
+                col: 20,
+                offset: 1000,
+            };
+
+            let q = Position {
+                line: 100,
+                col: 25,
+                offset: 10,
+            };
+
+            assert!(p > q);
+        }
+
+        #[test]
+        fn min_max() {
+            let (foo, bar) = SpannedStr::input_file("foo\nbar").split_at(4);
+            let (p, q) = (foo.span().start(), bar.span().start());
+
+            assert_eq!(p.min(q), p);
+            assert_eq!(q.min(p), p);
+            assert_eq!(p.max(q), q);
+            assert_eq!(q.max(p), q);
+        }
+
+        #[test]
+        #[cfg(not(debug_assertions))]
+        fn min_max_only_care_about_offset() {
+            // This is part of the inconsistency paragraph in the module documentation
+            let p = Position {
+                line: 10,
+                col: 20,
+                offset: 1000,
+            };
+
+            let q = Position {
+                line: 100,
+                col: 25,
+                offset: 10,
+            };
+
+            assert_eq!(p.min(q), q);
+            assert_eq!(p.max(q), p);
+        }
+    }
+
+    mod span {
+        use super::*;
+
+        const THREE_PARAGRAPHS: &str =
+            "First paragraph.\n\nSecond paragraph,\nwith two lines.\n  \nThird paragraph.\n";
+
+        const THREE_WORDS: &str = "foo bar baz\r\nqux";
+
+        #[test]
+        fn between_separated() {
+            let input = SpannedStr::input_file(THREE_WORDS);
+            let foo = input.find("foo").unwrap().span();
+            let qux = input.find("qux").unwrap().span();
 
-            assert_eq!(p.line, 0);
-            assert_eq!(p.col, 11);
-            assert_eq!(p.offset, 11);
+            let gap = Span::between(foo, qux);
+
+            assert_eq!(gap.start(), gap.end());
+            assert_eq!(gap.start(), Position::new(0, 3, 3));
         }
 
         #[test]
-        fn ord_simple() {
-            let p = Position::BEGINNING.advance_with("hello, world!");
-            let q = p.advance_with(" How are you?");
+        fn between_adjacent() {
+            let (foo, tail) = SpannedStr::input_file(THREE_WORDS).split_at(3);
+
+            let gap = Span::between(foo.span(), tail.span());
+
+            assert_eq!(gap.start(), gap.end());
+            assert_eq!(gap.start(), foo.span().end());
+            assert_eq!(gap.end(), tail.span().start());
+        }
+
+        #[test]
+        #[cfg(debug_assertions)]
+        #[should_panic(expected = "`a` must come before `b`")]
+        fn between_reversed() {
+            let input = SpannedStr::input_file(THREE_WORDS);
+            let foo = input.find("foo").unwrap().span();
+            let bar = input.find("bar").unwrap().span();
+
+            Span::between(bar, foo);
+        }
+
+        #[test]
+        fn at_line_start_and_end_start() {
+            let span = SpannedStr::input_file(THREE_WORDS)
+                .find("foo")
+                .unwrap()
+                .span();
+
+            assert!(span.at_line_start());
+            assert!(!span.at_line_end(THREE_WORDS));
+        }
+
+        #[test]
+        fn at_line_start_and_end_middle() {
+            let span = SpannedStr::input_file(THREE_WORDS)
+                .find("bar")
+                .unwrap()
+                .span();
+
+            assert!(!span.at_line_start());
+            assert!(!span.at_line_end(THREE_WORDS));
+        }
+
+        #[test]
+        fn at_line_start_and_end_end() {
+            let input = SpannedStr::input_file(THREE_WORDS);
+
+            let baz = input.find("baz").unwrap().span();
+            assert!(!baz.at_line_start());
+            assert!(baz.at_line_end(THREE_WORDS));
+
+            let qux = input.find("qux").unwrap().span();
+            assert!(qux.at_line_start());
+            assert!(qux.at_line_end(THREE_WORDS));
+        }
+
+        #[test]
+        fn at_line_start_and_end_whole_line() {
+            let first_line = SpannedStr::input_file(THREE_WORDS).split_at(11).0.span();
+
+            assert!(first_line.at_line_start());
+            assert!(first_line.at_line_end(THREE_WORDS));
+        }
+
+        #[test]
+        fn enclosing_paragraph_middle() {
+            let input = SpannedStr::input_file(THREE_PARAGRAPHS);
+            let with = input.find("with").unwrap().span();
+
+            let paragraph = with.enclosing_paragraph(THREE_PARAGRAPHS);
+
+            assert_eq!(
+                &THREE_PARAGRAPHS[paragraph],
+                "Second paragraph,\nwith two lines."
+            );
+            assert_eq!(paragraph.start().line(), 2);
+            assert_eq!(paragraph.start().col(), 0);
+            assert_eq!(paragraph.end().line(), 3);
+        }
+
+        #[test]
+        fn enclosing_paragraph_first() {
+            let input = SpannedStr::input_file(THREE_PARAGRAPHS);
+            let first = input.find("First").unwrap().span();
+
+            let paragraph = first.enclosing_paragraph(THREE_PARAGRAPHS);
+
+            assert_eq!(&THREE_PARAGRAPHS[paragraph], "First paragraph.");
+        }
+
+        #[test]
+        fn enclosing_paragraph_last() {
+            let input = SpannedStr::input_file(THREE_PARAGRAPHS);
+            let third = input.find("Third").unwrap().span();
+
+            let paragraph = third.enclosing_paragraph(THREE_PARAGRAPHS);
+
+            assert_eq!(&THREE_PARAGRAPHS[paragraph], "Third paragraph.");
+        }
+
+        #[test]
+        fn enclosing_paragraph_across_lines() {
+            let input = SpannedStr::input_file(THREE_PARAGRAPHS);
+            let span = input
+                .find("paragraph,")
+                .unwrap()
+                .span()
+                .merge(input.find("two").unwrap().span());
+
+            let paragraph = span.enclosing_paragraph(THREE_PARAGRAPHS);
+
+            assert_eq!(
+                &THREE_PARAGRAPHS[paragraph],
+                "Second paragraph,\nwith two lines."
+            );
+        }
+
+        #[test]
+        fn debug_is_compact() {
+            let input = SpannedStr::input_file("foo\nbar baz");
+            let span = input.find("baz").unwrap().span();
+
+            assert_eq!(format!("{:?}", span), "Span(1:4[8]..1:7[11])");
+        }
+
+        #[test]
+        fn to_lsp_range_multiline() {
+            let input = SpannedStr::input_file("foo\nbar baz\nqux");
+            let span = input
+                .find("baz")
+                .unwrap()
+                .span()
+                .merge(input.find("qux").unwrap().span());
+
+            assert_eq!(span.to_lsp_range(), ((1, 4), (2, 3)));
+        }
+
+        #[test]
+        fn center_single_char() {
+            let source = "a+b";
+            let plus = SpannedStr::input_file(source).find("+").unwrap().span();
+
+            assert_eq!(plus.center(source), Some(plus.start()));
+        }
+
+        #[test]
+        fn center_multi_char() {
+            let source = "x\nhéllo";
+            let hello = SpannedStr::input_file(source).find("héllo").unwrap().span();
+
+            assert_eq!(hello.center(source), Some(Position::new(1, 2, 5)));
+        }
+
+        #[test]
+        fn center_empty() {
+            let source = "foo";
+            let end = SpannedStr::input_file(source).span().shrink_to_end();
+
+            assert_eq!(end.center(source), Some(end.start()));
+        }
+
+        #[test]
+        fn center_out_of_source() {
+            let span = SpannedStr::input_file("foo bar").span();
+
+            assert_eq!(span.center("foo"), None);
+        }
+
+        #[test]
+        fn usize_range_round_trip() {
+            let source = "héllo\nwörld";
+            let world = SpannedStr::input_file(source).find("wörld").unwrap().span();
+
+            let range = world.as_usize_range();
+            assert_eq!(range, 7..13);
+
+            let span = Span::try_from((range, source)).unwrap();
+            assert_eq!(span, world);
+            assert_eq!(span.start(), Position::new(1, 0, 7));
+            assert_eq!(span.end(), Position::new(1, 5, 13));
+        }
+
+        #[test]
+        fn usize_range_empty() {
+            let span = Span::try_from((3..3, "foo")).unwrap();
+
+            assert_eq!(span.start(), span.end());
+            assert_eq!(span.start().col(), 3);
+        }
+
+        #[test]
+        fn usize_range_out_of_bounds() {
+            assert_eq!(Span::try_from((2..4, "foo")), Err(RangeError::OutOfBounds));
+        }
+
+        #[test]
+        fn usize_range_not_char_boundary() {
+            assert_eq!(
+                Span::try_from((0..1, "éa")),
+                Err(RangeError::NotCharBoundary)
+            );
+        }
+
+        #[test]
+        #[allow(clippy::reversed_empty_ranges)]
+        fn usize_range_reversed() {
+            assert_eq!(Span::try_from((2..1, "foo")), Err(RangeError::Reversed));
+        }
+
+        #[test]
+        fn adjust_shrink() {
+            let source = "(foo)";
+            let input = SpannedStr::input_file(source);
+            let span = input.span();
+
+            let shrunk = span.adjust(1, -1, source).unwrap();
+
+            assert_eq!(shrunk, input.split_at(1).1.split_at(3).0.span());
+        }
+
+        #[test]
+        fn adjust_grow() {
+            let source = "(foo)\n";
+            let input = SpannedStr::input_file(source);
+            let foo = input.split_at(1).1.split_at(3).0.span();
+
+            let grown = foo.adjust(-1, 1, source).unwrap();
+            assert_eq!(grown, input.split_at(5).0.span());
+
+            let grown = grown.adjust(0, 1, source).unwrap();
+            assert_eq!(grown, input.span());
+        }
+
+        #[test]
+        fn adjust_multibyte() {
+            let source = "«é»";
+            let input = SpannedStr::input_file(source);
+            let e = input.find("é").unwrap().span();
+
+            assert_eq!(input.span().adjust(1, -1, source), Some(e));
+            assert_eq!(e.adjust(-1, 1, source), Some(input.span()));
+        }
+
+        #[test]
+        fn adjust_out_of_range() {
+            let source = "foo";
+            let span = SpannedStr::input_file(source).span();
+
+            assert_eq!(span.adjust(-1, 0, source), None);
+            assert_eq!(span.adjust(0, 1, source), None);
+        }
+
+        #[test]
+        fn adjust_start_after_end() {
+            let source = "foo";
+            let span = SpannedStr::input_file(source).split_at(1).0.span();
+
+            assert_eq!(span.adjust(2, 0, source), None);
+        }
+
+        #[test]
+        fn index_string() {
+            let source = String::from("foo\nbarbaz");
+            let bar = SpannedStr::input_file(&source).find("bar").unwrap().span();
+
+            assert_eq!(&source[bar], "bar");
+            assert_eq!(&source.as_str()[bar], "bar");
+        }
+
+        #[test]
+        #[should_panic]
+        fn index_out_of_range() {
+            let span = SpannedStr::input_file("foo bar").span();
+            let _ = &"foo"[span];
+        }
+
+        #[test]
+        #[should_panic]
+        fn index_non_char_boundary() {
+            let span = SpannedStr::input_file("ab").split_at(1).0.span();
+            let _ = &"é"[span];
+        }
+
+        #[test]
+        fn difference_inner_at_start() {
+            let input = SpannedStr::input_file("foo bar");
+            let (foo, tail) = input.split_at(3);
+
+            let left = input.span().difference(foo.span());
+
+            assert_eq!(left, (None, Some(tail.span())));
+        }
+
+        #[test]
+        fn difference_inner_in_middle() {
+            let input = SpannedStr::input_file("foo\nbar\nbaz");
+            let bar = input.find("bar").unwrap();
+
+            let left = input.span().difference(bar.span());
+            let right = (
+                Some(input.split_at(4).0.span()),
+                Some(input.split_at(7).1.span()),
+            );
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn difference_inner_at_end() {
+            let input = SpannedStr::input_file("foo bar");
+            let (head, bar) = input.split_at(4);
+
+            let left = input.span().difference(bar.span());
+
+            assert_eq!(left, (Some(head.span()), None));
+        }
+
+        #[test]
+        fn difference_with_itself() {
+            let span = SpannedStr::input_file("foo").span();
+
+            assert_eq!(span.difference(span), (None, None));
+        }
+
+        #[test]
+        fn is_adjacent_to_adjacent() {
+            let (left, right) = SpannedStr::input_file("foo\nbar").split_at(4);
+
+            assert!(left.span().is_adjacent_to(right.span()));
+            assert!(right.span().is_adjacent_to(left.span()));
+        }
+
+        #[test]
+        fn is_adjacent_to_overlapping() {
+            let input = SpannedStr::input_file("foobar");
+            let left = input.split_at(4).0.span();
+            let right = input.split_at(2).1.span();
+
+            assert!(!left.is_adjacent_to(right));
+            assert!(!right.is_adjacent_to(left));
+        }
+
+        #[test]
+        fn is_adjacent_to_gapped() {
+            let input = SpannedStr::input_file("foo bar");
+            let foo = input.find("foo").unwrap().span();
+            let bar = input.find("bar").unwrap().span();
+
+            assert!(!foo.is_adjacent_to(bar));
+            assert!(!bar.is_adjacent_to(foo));
+        }
+
+        #[test]
+        fn columns_single_line() {
+            let (_, tail) = SpannedStr::input_file("hello world").split_at(6);
+
+            assert_eq!(tail.span().columns(), Some(6..11));
+        }
+
+        #[test]
+        fn columns_empty_span() {
+            let span = SpannedStr::input_file("foo").span().shrink_to_end();
+
+            assert_eq!(span.columns(), Some(3..3));
+        }
+
+        #[test]
+        fn columns_multi_line() {
+            let span = SpannedStr::input_file("foo\nbar").span();
+
+            assert_eq!(span.columns(), None);
+        }
+
+        #[test]
+        fn new_sets_fields() {
+            let start = Position::new(0, 4, 4);
+            let end = Position::new(1, 2, 9);
+            let span = Span::new(start, end);
+
+            assert_eq!(span.start(), start);
+            assert_eq!(span.end(), end);
+        }
+
+        #[test]
+        fn new_matches_spanned_str() {
+            let (_, right) = SpannedStr::input_file("foo\nbar").split_at(2);
+            let span = Span::new(Position::new(0, 2, 2), Position::new(1, 3, 7));
+
+            assert_eq!(span, right.span());
+        }
+
+        #[test]
+        fn after_zero() {
+            let span = SpannedStr::input_file("foo").span();
+            let after = span.after(0);
+
+            assert_eq!(after.start(), span.end());
+            assert_eq!(after.end(), span.end());
+        }
+
+        #[test]
+        fn after_one_is_next_char() {
+            let span = SpannedStr::input_file("foo").span();
+
+            assert_eq!(span.after(1), span.next_char());
+        }
+
+        #[test]
+        fn after_three() {
+            let span = SpannedStr::input_file("foo").span();
+            let right = SpannedStr::input_file("foo   ").split_at(3).1.span();
+
+            assert_eq!(span.after(3), right);
+        }
+
+        #[test]
+        #[should_panic(expected = "Span end is out of bounds")]
+        fn after_overflow() {
+            let span = SpannedStr::input_file("foo").span();
+
+            span.after(u32::MAX as usize);
+        }
+
+        #[test]
+        fn intersect_overlapping() {
+            let input = SpannedStr::input_file("abcdef");
+            let abcd = input.split_at(4).0.span();
+            let cdef = input.split_at(2).1.span();
+
+            assert_eq!(abcd.intersect(cdef), Some(input.find("cd").unwrap().span()));
+            assert_eq!(cdef.intersect(abcd), Some(input.find("cd").unwrap().span()));
+        }
+
+        #[test]
+        fn intersect_nested() {
+            let input = SpannedStr::input_file("abcdef");
+            let bcd = input.find("bcd").unwrap().span();
+
+            assert_eq!(input.span().intersect(bcd), Some(bcd));
+        }
+
+        #[test]
+        fn intersect_disjoint() {
+            let input = SpannedStr::input_file("abcdef");
+            let ab = input.find("ab").unwrap().span();
+            let ef = input.find("ef").unwrap().span();
+
+            assert_eq!(ab.intersect(ef), None);
+        }
+
+        #[test]
+        fn intersect_touching() {
+            let input = SpannedStr::input_file("abcdef");
+            let (abc, def) = input.split_at(3);
+
+            assert_eq!(abc.span().intersect(def.span()), None);
+        }
+
+        #[test]
+        fn rebase_first_line() {
+            let parent = SpannedStr::input_file("ab\ncd{efgh}");
+            let child_start = parent.find("efgh").unwrap().span().start();
+
+            let child = SpannedStr::input_file("efgh");
+            let fg = child.find("fg").unwrap();
+
+            assert_eq!(
+                fg.span().rebase(child_start),
+                parent.find("fg").unwrap().span()
+            );
+        }
+
+        #[test]
+        fn rebase_multiline_parent_offset() {
+            let parent = SpannedStr::input_file("a\nb\nccc{ab\ncd\nef}");
+            let child_start = parent.find("ab\ncd").unwrap().span().start();
+
+            let child = SpannedStr::input_file("ab\ncd\nef");
+            let span = child.find("b\ncd\ne").unwrap().span();
+
+            let rebased = span.rebase(child_start);
+
+            assert_eq!(rebased, parent.find("b\ncd\ne").unwrap().span());
+            assert_eq!(rebased.start().line(), 2);
+            assert_eq!(rebased.start().col(), 5);
+            assert_eq!(rebased.end().line(), 4);
+            assert_eq!(rebased.end().col(), 1);
+        }
+
+        #[test]
+        #[should_panic(expected = "Rebased position is out of bounds")]
+        fn rebase_overflow() {
+            let span = SpannedStr::input_file("foo").span();
+            let parent_start = Position::new(0, u32::MAX - 1, u32::MAX - 1);
+
+            span.rebase(parent_start);
+        }
+
+        #[test]
+        fn saturating_next_char_at_eof() {
+            let source = "foo";
+            let span = SpannedStr::input_file(source).span();
+
+            let next = span.saturating_next_char(source);
+
+            assert_eq!(next.start(), span.end());
+            assert_eq!(next.end(), span.end());
+        }
+
+        #[test]
+        fn saturating_after_stops_at_line_end() {
+            let source = "foo\nbar";
+            let input = SpannedStr::input_file(source);
+            let f = input.split_at(1).0.span();
+
+            let after = f.saturating_after(10, source);
+
+            assert_eq!(after, input.find("oo").unwrap().span());
+        }
+
+        #[test]
+        fn saturating_after_multibyte() {
+            let source = "aé€b";
+            let input = SpannedStr::input_file(source);
+            let a = input.split_at(1).0.span();
+
+            let after = a.saturating_after(2, source);
+
+            assert_eq!(after, input.find("é€").unwrap().span());
+        }
+
+        #[test]
+        fn relative_to_first_line() {
+            let parent = SpannedStr::input_file("ab\ncd{efgh}");
+            let child_start = parent.find("efgh").unwrap().span().start();
+
+            let child = SpannedStr::input_file("efgh");
+            let fg = parent.find("fg").unwrap().span();
+
+            assert_eq!(
+                fg.relative_to(child_start),
+                Some(child.find("fg").unwrap().span())
+            );
+        }
+
+        #[test]
+        fn relative_to_round_trip() {
+            let parent = SpannedStr::input_file("a\nb\nccc{ab\ncd\nef}");
+            let child_start = parent.find("ab\ncd").unwrap().span().start();
+
+            let child = SpannedStr::input_file("ab\ncd\nef");
+            let span = child.find("b\ncd\ne").unwrap().span();
+
+            let rebased = span.rebase(child_start);
+
+            assert_eq!(rebased.relative_to(child_start), Some(span));
+        }
 
-            assert!(p < q);
+        #[test]
+        fn relative_to_before_base() {
+            let input = SpannedStr::input_file("foo\nbar baz");
+            let foo = input.find("foo").unwrap().span();
+            let bar = input.find("bar").unwrap().span();
+            let baz = input.find("baz").unwrap().span();
+
+            assert_eq!(foo.relative_to(bar.start()), None);
+            assert_eq!(bar.relative_to(baz.start()), None);
         }
 
         #[test]
-        fn ord_only_cares_about_offset() {
-            // This is part of the inconsistency paragraph in the module documentation
-            let p = Position {
-                line: 10,
-                col: 20,
-                offset: 1000,
-            };
+        fn line_range_single_line() {
+            let input = SpannedStr::input_file("hello");
+            assert_eq!(input.span().line_range(), 0..=0);
+        }
 
-            let q = Position {
-                line: 100,
-                col: 25,
-                offset: 10,
-            };
+        #[test]
+        fn line_range_three_lines() {
+            let input = SpannedStr::input_file("foo\nbar\nbaz\nqux");
+            let span = input.split_at(2).1.split_at(8).0.span();
 
-            assert!(p > q);
+            assert_eq!(span.line_range(), 0..=2);
         }
-    }
-
-    mod span {
-        use super::*;
 
         #[test]
         fn of_file() {
@@ -514,11 +2974,366 @@ mod tests {
 
             assert_eq!(left, right);
         }
+
+        #[test]
+        fn shrink_to_start() {
+            let input = SpannedStr::input_file("foo\nbar");
+            let bar = input.split_at(4).1.span();
+
+            let left = bar.shrink_to_start();
+
+            assert_eq!(left.start, bar.start);
+            assert_eq!(left.end, bar.start);
+        }
+
+        #[test]
+        fn shrink_to_end() {
+            let input = SpannedStr::input_file("foo\nbar");
+            let bar = input.split_at(4).1.span();
+
+            let left = bar.shrink_to_end();
+
+            assert_eq!(left.start, bar.end);
+            assert_eq!(left.end, bar.end);
+            assert_eq!(left.start.line, 1);
+            assert_eq!(left.start.col, 3);
+            assert_eq!(left.start.offset, 7);
+        }
+
+        #[test]
+        fn merge_disjoint() {
+            let input = SpannedStr::input_file("foo bar");
+            let foo = input.split_at(3).0.span();
+            let bar = input.split_at(4).1.span();
+
+            assert_eq!(foo.merge(bar), input.span());
+            assert_eq!(bar.merge(foo), input.span());
+        }
+
+        #[test]
+        fn merge_nested() {
+            let input = SpannedStr::input_file("foo bar");
+            let oo = input.split_at(1).1.split_at(2).0.span();
+
+            assert_eq!(input.span().merge(oo), input.span());
+        }
+
+        #[test]
+        fn join_all_empty() {
+            assert_eq!(Span::join_all(Vec::new()), None);
+        }
+
+        #[test]
+        fn join_all_single() {
+            let s = SpannedStr::input_file("foo").span();
+
+            assert_eq!(Span::join_all(vec![s]), Some(s));
+        }
+
+        #[test]
+        fn join_all_multiline() {
+            let input = SpannedStr::input_file("foo\nbar\nbaz");
+            let foo = input.split_at(3).0.span();
+            let bar = input.split_at(4).1.split_at(3).0.span();
+            let baz = input.split_at(8).1.span();
+
+            let joined = Span::join_all(vec![bar, baz, foo]).unwrap();
+
+            assert_eq!(joined, input.span());
+            assert_eq!(joined.start.line, 0);
+            assert_eq!(joined.end.line, 2);
+            assert_eq!(joined.end.col, 3);
+        }
     }
 
     mod spanned_str {
         use super::*;
 
+        #[test]
+        fn byte_offset_of_char_multibyte() {
+            let input = SpannedStr::input_file("aé€b");
+
+            assert_eq!(input.byte_offset_of_char(0), Some(0));
+            assert_eq!(input.byte_offset_of_char(1), Some(1));
+            assert_eq!(input.byte_offset_of_char(2), Some(3));
+            assert_eq!(input.byte_offset_of_char(3), Some(6));
+            assert_eq!(input.byte_offset_of_char(4), Some(7));
+        }
+
+        #[test]
+        fn byte_offset_of_char_out_of_range() {
+            let input = SpannedStr::input_file("é€");
+
+            assert_eq!(input.byte_offset_of_char(3), None);
+            assert_eq!(SpannedStr::input_file("").byte_offset_of_char(1), None);
+        }
+
+        #[test]
+        fn byte_offset_of_char_can_split() {
+            let input = SpannedStr::input_file("€uro");
+            let idx = input.byte_offset_of_char(1).unwrap();
+
+            let (left, right) = input.split_at(idx);
+
+            assert_eq!(left.content(), "€");
+            assert_eq!(right.content(), "uro");
+        }
+
+        #[test]
+        fn scan_tokens_numbers() {
+            let input = SpannedStr::input_file("12 34 56");
+
+            let numbers = input
+                .scan_tokens(|rest| {
+                    let (digits, tail) = rest
+                        .take_while(char::is_whitespace)
+                        .1
+                        .take_while(|c| c.is_ascii_digit());
+                    *rest = tail;
+
+                    let value = digits.content().parse::<u32>().ok()?;
+                    Some((value, digits.span()))
+                })
+                .collect::<Vec<_>>();
+
+            let values = numbers.iter().map(|(value, _)| *value).collect::<Vec<_>>();
+            assert_eq!(values, [12, 34, 56]);
+            assert_eq!(numbers[1].1, input.find("34").unwrap().span());
+        }
+
+        #[test]
+        fn scan_tokens_stops_at_none() {
+            let input = SpannedStr::input_file("12 ab 56");
+
+            let count = input
+                .scan_tokens(|rest| {
+                    let (digits, tail) = rest
+                        .take_while(char::is_whitespace)
+                        .1
+                        .take_while(|c| c.is_ascii_digit());
+                    *rest = tail;
+                    digits.content().parse::<u32>().ok()
+                })
+                .count();
+
+            assert_eq!(count, 1);
+        }
+
+        #[test]
+        fn find_char_present() {
+            let input = SpannedStr::input_file("é = 42;");
+            let (idx, span) = input.find_char('=').unwrap();
+
+            assert_eq!(idx, 3);
+            assert_eq!(span, input.split_at(3).1.split_at(1).0.span());
+        }
+
+        #[test]
+        fn find_char_after_newline() {
+            let input = SpannedStr::input_file("foo\nbar;");
+            let (idx, span) = input.find_char(';').unwrap();
+
+            assert_eq!(idx, 7);
+            assert_eq!(span.start().line(), 1);
+            assert_eq!(span.start().col(), 3);
+            assert_eq!(span.end().col(), 4);
+        }
+
+        #[test]
+        fn find_char_relative_to_slice() {
+            let input = SpannedStr::input_file("a;b;");
+            let (_, tail) = input.split_at(2);
+
+            assert_eq!(tail.find_char(';').unwrap().0, 1);
+        }
+
+        #[test]
+        fn find_char_absent() {
+            let input = SpannedStr::input_file("foo\nbar");
+
+            assert!(input.find_char(';').is_none());
+        }
+
+        #[test]
+        fn count_while_full_match() {
+            assert_eq!(
+                SpannedStr::input_file("1234").count_while(char::is_numeric),
+                4
+            );
+        }
+
+        #[test]
+        fn count_while_partial_match() {
+            assert_eq!(
+                SpannedStr::input_file("12ab").count_while(char::is_numeric),
+                2
+            );
+        }
+
+        #[test]
+        fn count_while_empty() {
+            assert_eq!(SpannedStr::input_file("").count_while(char::is_numeric), 0);
+        }
+
+        #[test]
+        fn tokenize_commas_and_spaces() {
+            let input = SpannedStr::input_file("a, b,  c");
+            let segments = input.tokenize(|chr| chr == ',' || chr == ' ');
+
+            assert_eq!(segments, ["a", "b", "c"]);
+
+            let right = [
+                input.split_at(1).0.span(),
+                input.split_at(3).1.split_at(1).0.span(),
+                input.split_at(7).1.span(),
+            ];
+
+            assert_eq!(segments.iter().map(|s| s.span()).collect::<Vec<_>>(), right);
+        }
+
+        #[test]
+        fn tokenize_leading_and_trailing_separators() {
+            let input = SpannedStr::input_file(" foo ");
+
+            assert_eq!(input.tokenize(char::is_whitespace), ["foo"]);
+        }
+
+        #[test]
+        fn tokenize_only_separators() {
+            let input = SpannedStr::input_file(",,,");
+
+            assert!(input.tokenize(|chr| chr == ',').is_empty());
+            assert!(SpannedStr::input_file("")
+                .tokenize(|chr| chr == ',')
+                .is_empty());
+        }
+
+        #[test]
+        fn last_line_without_trailing_line_return() {
+            let input = SpannedStr::input_file("foo\nbar\nbaz");
+            let last = input.last_line();
+
+            assert_eq!(last.content(), "baz");
+            assert_eq!(last.span(), input.split_at(8).1.span());
+        }
+
+        #[test]
+        fn last_line_with_trailing_line_return() {
+            let input = SpannedStr::input_file("foo\nbar\n");
+            let last = input.last_line();
+
+            assert_eq!(last.content(), "bar");
+            assert_eq!(last.span(), input.split_at(4).1.split_at(3).0.span());
+        }
+
+        #[test]
+        fn last_line_single_line() {
+            let input = SpannedStr::input_file("foo");
+
+            assert_eq!(input.last_line(), input);
+        }
+
+        #[test]
+        fn last_line_empty() {
+            let input = SpannedStr::input_file("");
+
+            assert_eq!(input.last_line(), input);
+        }
+
+        #[test]
+        fn eq_str() {
+            assert!(SpannedStr::input_file("foo") == "foo");
+            assert!(SpannedStr::input_file("foo") != "bar");
+            assert!(SpannedStr::input_file("foo") == *"foo");
+        }
+
+        #[test]
+        fn eq_str_ignores_span() {
+            let (_, bar) = SpannedStr::input_file("foo bar").split_at(4);
+
+            assert_eq!(bar, "bar");
+            assert_ne!(bar, SpannedStr::input_file("bar"));
+        }
+
+        #[test]
+        fn eq_ignore_ascii_case_mixed_case() {
+            let i = SpannedStr::input_file("Content-Length");
+
+            assert!(i.eq_ignore_ascii_case("content-length"));
+            assert!(i.eq_ignore_ascii_case("CONTENT-LENGTH"));
+        }
+
+        #[test]
+        fn eq_ignore_ascii_case_not_matching() {
+            let i = SpannedStr::input_file("select");
+
+            assert!(!i.eq_ignore_ascii_case("selection"));
+            assert!(!i.eq_ignore_ascii_case("delete"));
+            assert!(!i.eq_ignore_ascii_case(""));
+        }
+
+        #[test]
+        fn synthetic_empty_content() {
+            let span = Span::new(Position::new(3, 7, 42), Position::new(3, 7, 42));
+            let synthetic = SpannedStr::synthetic("", span);
+
+            assert_eq!(synthetic.content(), "");
+            assert_eq!(synthetic.span(), span);
+        }
+
+        #[test]
+        fn chars_multibyte() {
+            let (_, tail) = SpannedStr::input_file("«héhé»").split_at(2);
+            let chars = tail.chars().collect::<Vec<_>>();
+
+            assert_eq!(chars, ['h', 'é', 'h', 'é', '»']);
+        }
+
+        #[test]
+        fn first_char_ascii() {
+            let (chr, span) = SpannedStr::input_file("abc").first_char().unwrap();
+
+            assert_eq!(chr, 'a');
+            assert_eq!(span.start().offset(), 0);
+            assert_eq!(span.end().offset(), 1);
+            assert_eq!(span.end().col(), 1);
+        }
+
+        #[test]
+        fn first_char_multibyte() {
+            let (chr, span) = SpannedStr::input_file("éa").first_char().unwrap();
+
+            assert_eq!(chr, 'é');
+            assert_eq!(span.end().offset(), 2);
+            assert_eq!(span.end().col(), 1);
+        }
+
+        #[test]
+        fn first_char_empty() {
+            assert!(SpannedStr::input_file("").first_char().is_none());
+        }
+
+        #[test]
+        fn split_at_first_line_with_newline() {
+            let (first, rest) = SpannedStr::input_file("foo\nbar\nbaz").split_at_first_line();
+
+            assert_eq!(first.content(), "foo\n");
+            assert_eq!(rest.content(), "bar\nbaz");
+            assert_eq!(first.span().end(), rest.span().start());
+            assert_eq!(rest.span().start().line(), 1);
+            assert_eq!(rest.span().start().col(), 0);
+        }
+
+        #[test]
+        fn split_at_first_line_without_newline() {
+            let input = SpannedStr::input_file("foo");
+            let (first, rest) = input.split_at_first_line();
+
+            assert_eq!(first, input);
+            assert!(rest.is_empty());
+            assert_eq!(rest.span().start(), input.span().end());
+        }
+
         #[test]
         fn input_file_simple() {
             let sstr = SpannedStr::input_file("hello\nworld");
@@ -551,6 +3366,30 @@ mod tests {
             assert_eq!(sstr.content(), content);
         }
 
+        #[test]
+        fn as_bytes() {
+            let sstr = SpannedStr::input_file("héllo");
+
+            assert_eq!(sstr.as_bytes(), "héllo".as_bytes());
+        }
+
+        #[test]
+        fn len_multibyte() {
+            let sstr = SpannedStr::input_file("éêè");
+
+            assert_eq!(sstr.len(), 6);
+            assert_eq!(sstr.len(), sstr.content().len());
+            assert_eq!(sstr.content().chars().count(), 3);
+        }
+
+        #[test]
+        fn is_empty() {
+            let (left, right) = SpannedStr::input_file("foo").split_at(3);
+
+            assert!(!left.is_empty());
+            assert!(right.is_empty());
+        }
+
         #[test]
         fn split_at_working() {
             let input = SpannedStr::input_file("foobar");
@@ -605,6 +3444,127 @@ mod tests {
             f.split_at(2);
         }
 
+        #[test]
+        fn escaped_content_tab_and_newline() {
+            let input = SpannedStr::input_file("a\tb\nc");
+
+            assert_eq!(input.escaped_content(), r"a\tb\nc");
+        }
+
+        #[test]
+        fn escaped_content_keeps_printable_unicode() {
+            let input = SpannedStr::input_file("«é»");
+
+            assert_eq!(input.escaped_content(), "«é»");
+        }
+
+        #[test]
+        fn split_terminator_trailing_delimiter() {
+            let content = "a\nb\n";
+            let input = SpannedStr::input_file(content);
+
+            let segments = input
+                .split_terminator('\n')
+                .map(SpannedStr::content)
+                .collect::<Vec<_>>();
+
+            assert_eq!(segments, ["a", "b"]);
+            assert_eq!(content.split('\n').collect::<Vec<_>>(), ["a", "b", ""]);
+        }
+
+        #[test]
+        fn split_terminator_matches_std() {
+            for content in ["", "\n", "a", "a\n\nb", "a\n\n", "é;€;"] {
+                let input = SpannedStr::input_file(content);
+                let delim = if content.contains(';') { ';' } else { '\n' };
+
+                let left = input
+                    .split_terminator(delim)
+                    .map(SpannedStr::content)
+                    .collect::<Vec<_>>();
+                let right = content.split_terminator(delim).collect::<Vec<_>>();
+
+                assert_eq!(left, right, "Mismatch for {:?}", content);
+            }
+        }
+
+        #[test]
+        fn split_terminator_spans() {
+            let input = SpannedStr::input_file("foo;bar");
+            let bar = input.split_terminator(';').nth(1).unwrap();
+
+            assert_eq!(bar.span(), input.find("bar").unwrap().span());
+        }
+
+        #[test]
+        fn line_count_single_line() {
+            assert_eq!(SpannedStr::input_file("hello").line_count(), 1);
+        }
+
+        #[test]
+        fn line_count_multi_line() {
+            assert_eq!(SpannedStr::input_file("foo\nbar\nbaz").line_count(), 3);
+        }
+
+        #[test]
+        fn line_count_trailing_newline() {
+            assert_eq!(SpannedStr::input_file("foo\nbar\n").line_count(), 3);
+        }
+
+        #[test]
+        fn line_count_empty() {
+            assert_eq!(SpannedStr::input_file("").line_count(), 0);
+        }
+
+        #[test]
+        fn split_at_counts_bytes() {
+            let input = SpannedStr::input_file("é€");
+            let (left, right) = input.split_at(2);
+
+            assert_eq!(left.content(), "é");
+            assert_eq!(right.content(), "€");
+            assert_eq!(right.span().start().col(), 1);
+        }
+
+        #[test]
+        fn split_at_char_counts_chars() {
+            let input = SpannedStr::input_file("é€");
+            let (left, right) = input.split_at_char(2);
+
+            assert_eq!(left, input);
+            assert!(right.is_empty());
+            assert_eq!(right.span().start(), input.span().end());
+        }
+
+        #[test]
+        fn split_at_char_middle() {
+            let input = SpannedStr::input_file("é€");
+            let (left, right) = input.split_at_char(1);
+
+            assert_eq!(left.content(), "é");
+            assert_eq!(right.content(), "€");
+        }
+
+        #[test]
+        fn find_second_line() {
+            let input = SpannedStr::input_file("foo\nbar baz");
+            let baz = input.find("baz").unwrap();
+
+            assert_eq!(baz.content, "baz");
+            assert_eq!(baz.span.start.line, 1);
+            assert_eq!(baz.span.start.col, 4);
+            assert_eq!(baz.span.start.offset, 8);
+            assert_eq!(baz.span.end.col, 7);
+            assert_eq!(baz.span.end.offset, 11);
+        }
+
+        #[test]
+        fn find_missing() {
+            let input = SpannedStr::input_file("foo\nbar baz");
+
+            assert_eq!(input.find("qux"), None);
+        }
+
         #[test]
         fn take_while() {
             let (left, right) = SpannedStr::input_file("foo bar").take_while(|c| c != ' ');
@@ -622,6 +3582,47 @@ mod tests {
             assert_eq!(right, input);
         }
 
+        #[test]
+        fn offset_within_contained() {
+            let input = SpannedStr::input_file("foo\nbar baz");
+            let (_, bar_baz) = input.split_at_first_line();
+            let baz = bar_baz.find("baz").unwrap();
+
+            assert_eq!(baz.offset_within(input), Some(8..11));
+            assert_eq!(baz.offset_within(bar_baz), Some(4..7));
+            assert_eq!(input.offset_within(input), Some(0..11));
+            assert_eq!(&input.content[baz.offset_within(input).unwrap()], "baz");
+        }
+
+        #[test]
+        fn offset_within_empty_at_end() {
+            let input = SpannedStr::input_file("foo");
+            let end = input.split_at(3).1;
+
+            assert_eq!(end.offset_within(input), Some(3..3));
+        }
+
+        #[test]
+        fn offset_within_not_contained() {
+            let input = SpannedStr::input_file("foo bar");
+            let (foo, bar) = input.split_at(4);
+
+            assert_eq!(input.offset_within(bar), None);
+            assert_eq!(foo.offset_within(bar), None);
+            assert_eq!(bar.offset_within(foo), None);
+        }
+
+        #[test]
+        fn offset_within_unrelated() {
+            let content = String::from("foo bar");
+            let other = SpannedStr::input_file(&content);
+            let input = SpannedStr::input_file("foo bar");
+
+            let bar = other.find("bar").unwrap();
+
+            assert_eq!(bar.offset_within(input), None);
+        }
+
         #[test]
         fn take_while_non_ascii() {
             let (left, right) = SpannedStr::input_file("éêè").take_while(|c| c != 'è');
@@ -629,5 +3630,33 @@ mod tests {
             assert_eq!(left.content, "éê");
             assert_eq!(right.content, "è");
         }
+
+        #[test]
+        fn trim_start_matches_digits() {
+            let input = SpannedStr::input_file("123abc4");
+            let trimmed = input.trim_start_matches(|c| c.is_ascii_digit());
+
+            assert_eq!(trimmed.content, "abc4");
+            assert_eq!(trimmed.span().start(), Position::new(0, 3, 3));
+            assert_eq!(trimmed.span().end(), input.span().end());
+        }
+
+        #[test]
+        fn trim_start_matches_whitespaces() {
+            let input = SpannedStr::input_file(" \t\n\r\n  foo ");
+            let trimmed = input.trim_start_matches(char::is_whitespace);
+
+            assert_eq!(trimmed.content, "foo ");
+            assert_eq!(trimmed.span().start(), Position::new(2, 2, 7));
+        }
+
+        #[test]
+        fn trim_start_matches_everything() {
+            let input = SpannedStr::input_file("   ");
+            let trimmed = input.trim_start_matches(char::is_whitespace);
+
+            assert_eq!(trimmed.content, "");
+            assert_eq!(trimmed.span().start(), input.span().end());
+        }
     }
 }
@@ -0,0 +1,22 @@
+// Ensures that the crate can be used from a crate that does not depend on the
+// standard library.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::string::{String, ToString};
+
+use lisbeth_error::{error::AnnotatedError, reporter::ErrorReporter};
+
+#[test]
+fn format_error() {
+    let reporter = ErrorReporter::non_file_input("foo bar".to_string());
+    let bar = reporter.spanned_str().find("bar").unwrap();
+    let report = AnnotatedError::new(bar.span(), "Unexpected bar");
+
+    let mut left = String::new();
+    reporter.format_error(&report).render_into(&mut left);
+
+    assert!(left.starts_with("Error: Unexpected bar\n"));
+}
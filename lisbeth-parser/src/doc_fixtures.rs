@@ -0,0 +1,30 @@
+//! Terminals shared by the examples of the documentation.
+//!
+//! This module is not part of the public API, and may change at any time.
+
+use std::borrow::Cow;
+
+use lisbeth_error::span::SpannedStr;
+
+use crate::lexer::{LexingResult, Terminal};
+
+/// A terminal that matches a single `.`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dot;
+
+impl Terminal for Dot {
+    const DESCRIPTION: &'static str = "`.`";
+
+    fn specific_description(&self) -> Cow<'static, str> {
+        Cow::Borrowed(Self::DESCRIPTION)
+    }
+
+    fn lex(i: SpannedStr) -> Option<LexingResult<Self>> {
+        if i.content().starts_with('.') {
+            let (matched, tail) = i.split_at(1);
+            Some(Ok((Dot, matched.span(), tail)))
+        } else {
+            None
+        }
+    }
+}
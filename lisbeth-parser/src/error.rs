@@ -0,0 +1,266 @@
+//! Helpers for the errors that are commonly reported while parsing.
+
+use lisbeth_error::{
+    error::AnnotatedError,
+    span::{Span, SpannedStr},
+};
+
+/// Adds parsing-specific constructors to [`AnnotatedError`].
+pub trait AnnotatedErrorExt {
+    /// Constructs a report for a piece of input that does not match what is
+    /// expected.
+    ///
+    /// The general message has the form ``Expected <expected>, found `<found>` ``,
+    /// where `<found>` is the content of `found`. An annotation is added at
+    /// the span of `found`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, span::SpannedStr};
+    /// use lisbeth_parser::error::AnnotatedErrorExt;
+    ///
+    /// let input = SpannedStr::input_file("42 abc");
+    /// let abc = input.split_at(3).1;
+    ///
+    /// let report = AnnotatedError::expected_found(abc.span(), "number", abc);
+    ///
+    /// assert_eq!(report.message(), "Expected number, found `abc`");
+    /// ```
+    fn expected_found(span: Span, expected: &str, found: SpannedStr) -> Self;
+
+    /// Constructs a report located at the first character of `input`.
+    ///
+    /// The general message is `msg`, and an annotation describing the
+    /// character is added at its span. If `input` is empty, the report is
+    /// located at the end of file instead.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, span::SpannedStr};
+    /// use lisbeth_parser::error::AnnotatedErrorExt;
+    ///
+    /// let input = SpannedStr::input_file("?abc");
+    /// let report = AnnotatedError::at_char(input, "Unexpected character");
+    ///
+    /// assert_eq!(report.message(), "Unexpected character");
+    /// assert_eq!(report.span(), input.split_at(1).0.span());
+    /// ```
+    fn at_char<Msg>(input: SpannedStr, msg: Msg) -> Self
+    where
+        Msg: ToString;
+
+    /// Suggests the candidate that is the closest to `found`, if any.
+    ///
+    /// The distance between two names is their Levenshtein distance, counted
+    /// in `char`s. The closest candidate is suggested only if it is at most
+    /// one edit away for every three characters of `found`, with a minimum of
+    /// one edit. The suggestion is displayed as a ``help: did you mean
+    /// `<candidate>`?`` message, and `span` is underlined. When several
+    /// candidates are equally close, the first one is suggested. Candidates
+    /// equal to `found` are ignored.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, span::SpannedStr};
+    /// use lisbeth_parser::error::AnnotatedErrorExt;
+    ///
+    /// let input = SpannedStr::input_file("let a = lenght;");
+    /// let name = input.find("lenght").unwrap();
+    ///
+    /// let report = AnnotatedError::new(name.span(), "Unknown variable `lenght`")
+    ///     .did_you_mean(name.span(), &["width", "length"], name.content());
+    ///
+    /// let right = AnnotatedError::new(name.span(), "Unknown variable `lenght`")
+    ///     .with_suggestion(name.span(), "did you mean `length`?");
+    ///
+    /// assert_eq!(report, right);
+    /// ```
+    fn did_you_mean(self, span: Span, candidates: &[&str], found: &str) -> Self;
+}
+
+impl AnnotatedErrorExt for AnnotatedError {
+    fn expected_found(span: Span, expected: &str, found: SpannedStr) -> AnnotatedError {
+        let msg = format!("Expected {}, found `{}`", expected, found.content());
+
+        AnnotatedError::new(span, msg)
+            .with_annotation(found.span(), format!("Expected {} here", expected))
+    }
+
+    fn at_char<Msg>(input: SpannedStr, msg: Msg) -> AnnotatedError
+    where
+        Msg: ToString,
+    {
+        match input.content().chars().next() {
+            Some(chr) => {
+                let span = input.split_at(chr.len_utf8()).0.span();
+
+                AnnotatedError::new(span, msg).with_annotation(span, format!("Found `{}`", chr))
+            }
+            None => {
+                let span = input.span().shrink_to_start();
+
+                AnnotatedError::new(span, msg).with_annotation(span, "Found end of file")
+            }
+        }
+    }
+
+    fn did_you_mean(self, span: Span, candidates: &[&str], found: &str) -> AnnotatedError {
+        let threshold = usize::max(1, found.chars().count() / 3);
+
+        let closest = candidates
+            .iter()
+            .filter(|candidate| **candidate != found)
+            .map(|candidate| (edit_distance(candidate, found), candidate))
+            .filter(|(distance, _)| *distance <= threshold)
+            .min_by_key(|(distance, _)| *distance);
+
+        match closest {
+            Some((_, candidate)) => {
+                self.with_suggestion(span, format!("did you mean `{}`?", candidate))
+            }
+            None => self,
+        }
+    }
+}
+
+// Returns the Levenshtein distance between a and b, that is the minimal number
+// of chars that must be inserted, removed or substituted to turn a into b.
+//
+// Only two rows of the distance matrix are kept in memory.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, a_chr) in a.chars().enumerate() {
+        current[0] = i + 1;
+
+        for (j, b_chr) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_chr != *b_chr);
+            let deletion = previous[j + 1] + 1;
+            let insertion = current[j] + 1;
+
+            current[j + 1] = substitution.min(deletion).min(insertion);
+        }
+
+        core::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod expected_found {
+        use super::*;
+
+        #[test]
+        fn message_format() {
+            let input = SpannedStr::input_file("let = 42;");
+            let eq = input.find("=").unwrap();
+
+            let report = AnnotatedError::expected_found(eq.span(), "identifier", eq);
+
+            let right = AnnotatedError::new(eq.span(), "Expected identifier, found `=`")
+                .with_annotation(eq.span(), "Expected identifier here");
+
+            assert_eq!(report, right);
+        }
+
+        #[test]
+        fn multibyte_found() {
+            let input = SpannedStr::input_file("x = «é»");
+            let quoted = input.find("«é»").unwrap();
+
+            let report = AnnotatedError::expected_found(input.span(), "string", quoted);
+
+            assert_eq!(report.message(), "Expected string, found `«é»`");
+            assert_eq!(report.span(), input.span());
+            assert_eq!(report.annotation_count(), 1);
+        }
+    }
+
+    mod at_char {
+        use super::*;
+
+        #[test]
+        fn multibyte_char() {
+            let input = SpannedStr::input_file("éa");
+            let e = input.split_at(2).0;
+
+            let report = AnnotatedError::at_char(input, "Unexpected character");
+
+            let right = AnnotatedError::new(e.span(), "Unexpected character")
+                .with_annotation(e.span(), "Found `é`");
+
+            assert_eq!(report, right);
+        }
+
+        #[test]
+        fn empty_input() {
+            let input = SpannedStr::input_file("foo").split_at(3).1;
+
+            let report = AnnotatedError::at_char(input, "Expected expression");
+
+            let right = AnnotatedError::new(input.span(), "Expected expression")
+                .with_annotation(input.span(), "Found end of file");
+
+            assert_eq!(report, right);
+            assert_eq!(report.span().start().offset(), 3);
+        }
+    }
+
+    mod did_you_mean {
+        use super::*;
+
+        fn report(name: SpannedStr) -> AnnotatedError {
+            AnnotatedError::new(name.span(), "Unknown variable")
+        }
+
+        #[test]
+        fn close_match() {
+            let input = SpannedStr::input_file("prnt");
+            let candidates = ["print", "parse", "println"];
+
+            let left = report(input).did_you_mean(input.span(), &candidates, input.content());
+            let right = report(input).with_suggestion(input.span(), "did you mean `print`?");
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn far_off_input() {
+            let input = SpannedStr::input_file("xyz");
+            let candidates = ["print", "parse", "println"];
+
+            let left = report(input).did_you_mean(input.span(), &candidates, input.content());
+
+            assert_eq!(left, report(input));
+        }
+
+        #[test]
+        fn exact_match_is_ignored() {
+            let input = SpannedStr::input_file("print");
+
+            let left = report(input).did_you_mean(input.span(), &["print"], input.content());
+
+            assert_eq!(left, report(input));
+        }
+
+        #[test]
+        fn edit_distances() {
+            assert_eq!(edit_distance("", ""), 0);
+            assert_eq!(edit_distance("abc", ""), 3);
+            assert_eq!(edit_distance("", "abc"), 3);
+            assert_eq!(edit_distance("kitten", "sitting"), 3);
+            assert_eq!(edit_distance("flaw", "lawn"), 2);
+            assert_eq!(edit_distance("café", "cafe"), 1);
+        }
+    }
+}
@@ -0,0 +1,144 @@
+//! Sharing the storage of repeated token texts.
+//!
+//! Inputs with many repeated identifiers contain many copies of the same
+//! text. An [`Interner`] stores each distinct text once and hands out a small
+//! symbol for it, so that equal texts can be compared by comparing their
+//! symbols. The texts of a token sequence are interned with
+//! [`Lexer::intern_texts`].
+//!
+//! Any interner can be plugged in by implementing the [`Interner`] trait.
+//! [`StringInterner`] is a simple implementation backed by a hash map.
+//!
+//! [`Lexer::intern_texts`]: crate::lexer::Lexer::intern_texts
+
+use std::{collections::HashMap, convert::TryFrom, rc::Rc};
+
+/// A storage that maps equal texts to equal symbols.
+pub trait Interner {
+    /// The identifier given to each distinct text.
+    type Symbol;
+
+    /// Returns the symbol associated to `text`, storing `text` if it has not
+    /// been interned before.
+    fn intern(&mut self, text: &str) -> Self::Symbol;
+}
+
+/// The identifier of a text stored in a [`StringInterner`].
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    /// Returns the index of the symbol, which is the number of distinct texts
+    /// interned before it.
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// An [`Interner`] that stores each distinct text once.
+///
+/// # Example
+///
+/// ```rust
+/// use lisbeth_parser::intern::{Interner, StringInterner};
+///
+/// let mut interner = StringInterner::new();
+///
+/// let foo = interner.intern("foo");
+/// let bar = interner.intern("bar");
+///
+/// assert_eq!(interner.intern("foo"), foo);
+/// assert_ne!(foo, bar);
+/// assert_eq!(interner.resolve(bar), Some("bar"));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct StringInterner {
+    // Both collections share the storage of each text.
+    symbols: HashMap<Rc<str>, Symbol>,
+    texts: Vec<Rc<str>>,
+}
+
+impl StringInterner {
+    /// Creates an empty interner.
+    pub fn new() -> StringInterner {
+        StringInterner::default()
+    }
+
+    /// Returns the text associated to `symbol`, or `None` if `symbol` has not
+    /// been created by this interner.
+    pub fn resolve(&self, symbol: Symbol) -> Option<&str> {
+        self.texts.get(symbol.index()).map(AsRef::as_ref)
+    }
+
+    /// Returns the number of distinct texts stored in the interner.
+    pub fn len(&self) -> usize {
+        self.texts.len()
+    }
+
+    /// Returns whether no text has been interned yet.
+    pub fn is_empty(&self) -> bool {
+        self.texts.is_empty()
+    }
+}
+
+impl Interner for StringInterner {
+    type Symbol = Symbol;
+
+    /// # Panics
+    ///
+    /// Panics if more than `u32::MAX` distinct texts are interned.
+    fn intern(&mut self, text: &str) -> Symbol {
+        if let Some(symbol) = self.symbols.get(text) {
+            return *symbol;
+        }
+
+        let id = u32::try_from(self.texts.len()).expect("Too many interned texts");
+        let symbol = Symbol(id);
+        let text = Rc::<str>::from(text);
+
+        self.texts.push(Rc::clone(&text));
+        self.symbols.insert(text, symbol);
+
+        symbol
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod string_interner {
+        use super::*;
+
+        #[test]
+        fn equal_texts_equal_symbols() {
+            let mut interner = StringInterner::new();
+
+            let first = interner.intern("foo");
+            let second = interner.intern("foo");
+
+            assert_eq!(first, second);
+            assert_eq!(interner.len(), 1);
+        }
+
+        #[test]
+        fn symbols_are_indexed_in_order() {
+            let mut interner = StringInterner::new();
+
+            let symbols = ["foo", "bar", "foo", "baz"]
+                .iter()
+                .map(|text| interner.intern(text).index())
+                .collect::<Vec<_>>();
+
+            assert_eq!(symbols, [0, 1, 0, 2]);
+        }
+
+        #[test]
+        fn resolve_unknown_symbol() {
+            let interner = StringInterner::new();
+
+            assert!(interner.is_empty());
+            assert_eq!(interner.resolve(Symbol(0)), None);
+        }
+    }
+}
@@ -19,13 +19,18 @@
 //! allows the lexer to correctly handle it. It also holds the span at which the
 //! terminal was encountered.
 
-use std::convert::TryFrom;
+use std::{
+    any, borrow::Cow, collections::BTreeMap, convert::TryFrom, marker::PhantomData, ops::Deref,
+    vec::Drain,
+};
 
 use lisbeth_error::{
     error::AnnotatedError,
-    span::{Span, SpannedStr},
+    span::{Position, Span, SpannedStr},
 };
 
+use crate::intern::Interner;
+
 /// The result returned when lexing is done.
 ///
 /// If lexing went successfully, then the `Ok` variant is returned. It must
@@ -60,14 +65,91 @@ pub trait Terminal: Sized {
     /// be returned.
     fn lex(i: SpannedStr) -> Option<LexingResult<Self>>;
 
+    /// Returns whether the input starts with the said terminal, without
+    /// building it.
+    ///
+    /// The default implementation calls [`lex`] and checks whether it returns
+    /// `Some`. It can be overridden with a cheaper check.
+    ///
+    /// [`lex`]: Terminal::lex
+    fn can_lex(i: SpannedStr) -> bool {
+        Self::lex(i).is_some()
+    }
+
     /// The general description for the terminal.
     const DESCRIPTION: &'static str;
 
     /// Describes a specific terminal.
-    fn specific_description(&self) -> String;
+    ///
+    /// Terminals whose description does not depend on their content can
+    /// return a borrowed `&'static str`, which avoids an allocation. Others
+    /// can return an owned `String`.
+    ///
+    /// # Migration note
+    ///
+    /// This method used to return a `String`. Existing implementations can be
+    /// updated by wrapping the returned value in [`Cow::Owned`], or by
+    /// returning [`Cow::Borrowed`] when the description is a string literal.
+    fn specific_description(&self) -> Cow<'static, str>;
+}
+
+/// Matches the longest prefix of `input` whose characters all satisfy `pred`.
+///
+/// The span of the matched prefix is returned along with the rest of the
+/// input. The matched prefix may be empty.
+///
+/// # Example
+///
+/// ```rust
+/// use lisbeth_error::span::SpannedStr;
+/// use lisbeth_parser::lexer::lex_zero_or_more;
+///
+/// let input = SpannedStr::input_file("   foo");
+/// let (_, tail) = lex_zero_or_more(input, char::is_whitespace);
+///
+/// assert_eq!(tail.content(), "foo");
+/// ```
+pub fn lex_zero_or_more<F>(input: SpannedStr, pred: F) -> (Span, SpannedStr)
+where
+    F: Fn(char) -> bool,
+{
+    let (matched, tail) = input.take_while(pred);
+    (matched.span(), tail)
+}
+
+/// Matches the longest non-empty prefix of `input` whose characters all
+/// satisfy `pred`.
+///
+/// The span of the matched prefix is returned along with the rest of the
+/// input. If the first character of `input` does not satisfy `pred`, then
+/// `None` is returned.
+///
+/// # Example
+///
+/// ```rust
+/// use lisbeth_error::span::SpannedStr;
+/// use lisbeth_parser::lexer::lex_one_or_more;
+///
+/// let input = SpannedStr::input_file("42 101");
+/// let (span, tail) = lex_one_or_more(input, |c| c.is_ascii_digit()).unwrap();
+///
+/// assert_eq!(span.end().col(), 2);
+/// assert_eq!(tail.content(), " 101");
+/// ```
+pub fn lex_one_or_more<F>(input: SpannedStr, pred: F) -> Option<(Span, SpannedStr)>
+where
+    F: Fn(char) -> bool,
+{
+    let (matched, tail) = input.take_while(pred);
+
+    if matched.is_empty() {
+        None
+    } else {
+        Some((matched.span(), tail))
+    }
 }
 
-fn incorrect_terminal_error(span: Span, expected: &str, got: String) -> AnnotatedError {
+fn incorrect_terminal_error(span: Span, expected: &str, got: &str) -> AnnotatedError {
     AnnotatedError::new(span, format!("Expected {}, found {}", expected, got))
 }
 
@@ -78,12 +160,15 @@ fn incorrect_terminal_error(span: Span, expected: &str, got: String) -> Annotate
 pub trait Tokenizeable<T: Token>: Sized + Terminal {
     fn from_token(tok: &T) -> Option<Self>;
 
+    // Errors are reported as they are everywhere else in the crate, so that
+    // they can be combined with the other reports without conversion.
+    #[allow(clippy::result_large_err)]
     fn from_token_or_error(tok: &T) -> Result<Self, AnnotatedError> {
         match Self::from_token(tok) {
             Some(t) => Ok(t),
             None => {
                 let report =
-                    incorrect_terminal_error(tok.span(), Self::DESCRIPTION, tok.describe());
+                    incorrect_terminal_error(tok.span(), Self::DESCRIPTION, &tok.describe());
                 Err(report)
             }
         }
@@ -112,7 +197,108 @@ pub trait Token: Sized {
     /// [`Terminal`] trait.
     ///
     /// [`specific_description`]: Terminal::specific_description
-    fn describe(&self) -> String;
+    fn describe(&self) -> Cow<'static, str>;
+
+    /// Returns the name of the kind of the token.
+    ///
+    /// Kinds are named after the [`DESCRIPTION`] of the terminal stored in the
+    /// token, and end-of-file tokens are named `end of file`. This is what the
+    /// [`token`] macro and [`Spanned`] return. This allows to count tokens by
+    /// kind, as [`Lexer::kind_histogram`] does.
+    ///
+    /// The default implementation returns the name of the token type, meaning
+    /// that every token is counted as the same kind.
+    ///
+    /// [`DESCRIPTION`]: Terminal::DESCRIPTION
+    fn kind_name(&self) -> &'static str {
+        any::type_name::<Self>()
+    }
+
+    /// Creates an end-of-file token located at `span`, if the token type has
+    /// one.
+    ///
+    /// The [`Lexer`] appends this token at the end of the token sequence when
+    /// lexing succeeds. The default implementation returns `None`, meaning
+    /// that no end-of-file token is appended.
+    fn eof(_span: Span) -> Option<Self> {
+        None
+    }
+}
+
+/// A value associated with the span at which it was encountered.
+///
+/// When the grammar contains a single terminal, `Spanned<T>` can be used as
+/// the token type, instead of a type created with the [`token`] macro. The
+/// value can be read thanks to the [`Deref`] implementation.
+///
+/// # Example
+///
+/// ```rust
+/// use lisbeth_error::span::SpannedStr;
+/// use lisbeth_parser::lexer::Spanned;
+///
+/// let input = SpannedStr::input_file("42");
+/// let number = Spanned::new(42, input.span());
+///
+/// assert_eq!(*number, 42);
+/// assert_eq!(number.span(), input.span());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spanned<T> {
+    value: T,
+    span: Span,
+}
+
+impl<T> Spanned<T> {
+    /// Associates `value` with `span`.
+    pub fn new(value: T, span: Span) -> Spanned<T> {
+        Spanned { value, span }
+    }
+
+    /// Returns the span at which the value was encountered.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Returns the value, dropping its span.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for Spanned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: Terminal> Token for Spanned<T> {
+    fn from_str(
+        input: SpannedStr,
+    ) -> Result<(Self, SpannedStr), (Vec<AnnotatedError>, Option<SpannedStr>)> {
+        match T::lex(input) {
+            Some(rslt) => {
+                let (value, span, tail) = rslt?;
+                Ok((Spanned { value, span }, tail))
+            }
+            None => Err((vec![unknown_token_error(input, &[T::DESCRIPTION])], None)),
+        }
+    }
+
+    #[inline]
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn describe(&self) -> Cow<'static, str> {
+        self.value.specific_description()
+    }
+
+    fn kind_name(&self) -> &'static str {
+        T::DESCRIPTION
+    }
 }
 
 /// Creates a token type and implements [`Token`] for it.
@@ -123,6 +309,15 @@ pub trait Token: Sized {
 /// Documentation and `#[derive(...)]` macros can be added on the token by
 /// passing them before the token name.
 ///
+/// An end-of-file token can be added by appending `with <name>` after the
+/// terminal list. This adds a `<name>` variant to the generated token kind,
+/// which is appended by the [`Lexer`] at the end of the token sequence. Its
+/// span is empty and located at the end of the input.
+///
+/// The text matched by each token can be stored in it by writing `with text`
+/// after the token name. The text is then returned by a `text` method
+/// generated on the token. The text of the end-of-file token is empty.
+///
 /// # Example
 ///
 /// The following example shows how to define a simple token representing the
@@ -131,6 +326,7 @@ pub trait Token: Sized {
 /// [morse_wikipedia]: https://en.wikipedia.org/wiki/Morse_code
 ///
 /// ```rust
+/// # use std::borrow::Cow;
 /// use lisbeth_error::span::{Span, SpannedStr};
 ///
 /// use lisbeth_parser::lexer::{LexingResult, Terminal};
@@ -151,8 +347,8 @@ pub trait Token: Sized {
 /// impl Terminal for Dot {
 /// #     const DESCRIPTION: &'static str = "`.`";
 /// #
-/// #     fn specific_description(&self) -> String {
-/// #         Self::DESCRIPTION.to_string()
+/// #     fn specific_description(&self) -> Cow<'static, str> {
+/// #         Cow::Borrowed(Self::DESCRIPTION)
 /// #     }
 /// #
 /// #     fn lex(i: SpannedStr) -> Option<LexingResult<Self>> {
@@ -170,8 +366,8 @@ pub trait Token: Sized {
 /// impl Terminal for Dash {
 /// #     const DESCRIPTION: &'static str = "`-`";
 /// #
-/// #     fn specific_description(&self) -> String {
-/// #         Self::DESCRIPTION.to_string()
+/// #     fn specific_description(&self) -> Cow<'static, str> {
+/// #         Cow::Borrowed(Self::DESCRIPTION)
 /// #     }
 /// #
 /// #     fn lex(i: SpannedStr) -> Option<LexingResult<Self>> {
@@ -188,6 +384,18 @@ pub trait Token: Sized {
 ///     #[derive(Clone, Debug, PartialEq)]
 ///     Token = Dot | Dash
 /// }
+///
+/// token! {
+///     /// A token for the morse language, with an end-of-file token.
+///     #[derive(Clone, Debug, PartialEq)]
+///     TokenWithEof = Dot | Dash with Eof
+/// }
+///
+/// token! {
+///     /// A token for the morse language, which stores the text it matched.
+///     #[derive(Clone, Debug, PartialEq)]
+///     TokenWithText with text = Dot | Dash
+/// }
 /// ```
 #[macro_export]
 macro_rules! token {
@@ -195,6 +403,41 @@ macro_rules! token {
         $( #[$m:meta] )*
         $token_name: ident =
             $( $term: ident )|* $(,)?
+            $( with $eof: ident )?
+    ) => {
+        $crate::token! {
+            @define
+            [ $( #[$m] )* ]
+            $token_name
+            [ $( $term )* ]
+            [ $( $eof )? ]
+            []
+        }
+    };
+
+    (
+        $( #[$m:meta] )*
+        $token_name: ident with text =
+            $( $term: ident )|* $(,)?
+            $( with $eof: ident )?
+    ) => {
+        $crate::token! {
+            @define
+            [ $( #[$m] )* ]
+            $token_name
+            [ $( $term )* ]
+            [ $( $eof )? ]
+            [ text ]
+        }
+    };
+
+    (
+        @define
+        [ $( #[$m:meta] )* ]
+        $token_name: ident
+        [ $( $term: ident )* ]
+        [ $( $eof: ident )? ]
+        [ $( $text: ident )? ]
     ) => {
         ::paste::paste! {
             // Token type generation
@@ -202,18 +445,49 @@ macro_rules! token {
             struct $token_name {
                 kind: [<$token_name Kind>],
                 span: ::lisbeth_error::span::Span,
+                $( $text: ::std::string::String, )?
+            }
+
+            // Creates a token. The matched text is dropped when the token
+            // does not store it.
+            #[doc(hidden)]
+            #[allow(dead_code)]
+            fn [<__ $token_name:snake _from_parts>](
+                kind: [<$token_name Kind>],
+                span: ::lisbeth_error::span::Span,
+                _text: &str,
+            ) -> $token_name {
+                $token_name {
+                    kind,
+                    span,
+                    $( $text: _text.to_string(), )?
+                }
             }
 
+            $(
+                impl $token_name {
+                    /// Returns the text matched by the token.
+                    #[allow(dead_code)]
+                    fn $text(&self) -> &str {
+                        &self.$text
+                    }
+                }
+            )?
+
             // Token kind type generation
             $( #[$m] )*
             enum [<$token_name Kind>] {
                 $( $term($term), )*
+                $( $eof, )?
             }
 
             // Faillible Token -> Terminal conversion
             $(
                 impl $crate::lexer::Tokenizeable<$token_name> for $term {
                     fn from_token(tok: &$token_name) -> Option<Self> {
+                        // The wildcard is unreachable when the token has a
+                        // single terminal and no end of file.
+                        #[allow(unreachable_patterns)]
                         match &tok.kind {
                             [<$token_name Kind>]::$term(t) => Some(t.clone()),
                             _ => None,
@@ -235,20 +509,17 @@ macro_rules! token {
                         if let Some(rslt) = $term::lex(input) {
                             let (term, span, tail) = rslt?;
                             let kind = [<$token_name Kind>] ::$term(term);
-                            let tok = $token_name { kind, span };
+                            let text = $crate::lexer::matched_text(input, span);
+                            let tok = [<__ $token_name:snake _from_parts>](kind, span, text);
                             return Ok((tok, tail));
                         }
                      )*
 
                     // If no token matched, then a failure is emitted.
-                    let mut first = true;
-                    let (chr, _) = input.take_while(|_| if first { first = false; true } else { false });
-
-                    let report = ::lisbeth_error::error::AnnotatedError::new(chr.span(), format!("Unknown start of token: `{}`", chr.content()))
-                        .with_annotation(chr.span(), "Unknown start of token");
-                    let reports = vec![report];
+                    let expected = [$( <$term as $crate::lexer::Terminal>::DESCRIPTION, )*];
+                    let report = $crate::lexer::unknown_token_error(input, &expected);
 
-                    Err((reports, None))
+                    Err((vec![report], None))
                 }
 
                 #[inline]
@@ -256,13 +527,34 @@ macro_rules! token {
                     self.span
                 }
 
-                fn describe(&self) -> String {
+                fn describe(&self) -> ::std::borrow::Cow<'static, str> {
                     match &self.kind {
                         $(
                             [<$token_name Kind>] ::$term(t) => t.specific_description(),
                         )*
+                        $(
+                            [<$token_name Kind>] ::$eof => ::std::borrow::Cow::Borrowed("end of file"),
+                        )?
+                    }
+                }
+
+                fn kind_name(&self) -> &'static str {
+                    match &self.kind {
+                        $(
+                            [<$token_name Kind>] ::$term(_) => <$term as $crate::lexer::Terminal>::DESCRIPTION,
+                        )*
+                        $(
+                            [<$token_name Kind>] ::$eof => "end of file",
+                        )?
                     }
                 }
+
+                $(
+                    fn eof(span: ::lisbeth_error::span::Span) -> Option<Self> {
+                        let kind = [<$token_name Kind>] ::$eof;
+                        Some([<__ $token_name:snake _from_parts>](kind, span, ""))
+                    }
+                )?
             }
         }
     };
@@ -275,24 +567,330 @@ macro_rules! token {
 pub struct Lexer<Tok>(Vec<Tok>);
 
 impl<Tok: Token> Lexer<Tok> {
-    fn from_spanned_str(mut input: SpannedStr) -> Result<Lexer<Tok>, Vec<AnnotatedError>> {
-        let mut toks = Vec::<Tok>::new();
+    fn from_spanned_str(input: SpannedStr) -> Result<Lexer<Tok>, Vec<AnnotatedError>> {
+        Self::from_spanned_str_with_sync(input, &[])
+    }
+
+    /// Lexes a whole input string.
+    ///
+    /// This is equivalent to creating a [`SpannedStr`] with
+    /// [`SpannedStr::input_file`] and converting it to a [`Lexer`] with
+    /// [`TryFrom`].
+    pub fn from_str_input(input: &str) -> Result<Lexer<Tok>, Vec<AnnotatedError>> {
+        Self::from_spanned_str(SpannedStr::input_file(input))
+    }
+
+    /// Rebuilds the source text from the tokens.
+    ///
+    /// The text covered by each token is taken from `source`, which must be
+    /// the input that has been lexed. The text that is not covered by any
+    /// token, such as skipped whitespaces, is lost: the source is rebuilt
+    /// exactly only if every part of the input is retained as a token.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a token span goes out of `source`, as string slicing does.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use lisbeth_error::span::{Span, SpannedStr};
+    /// # use lisbeth_parser::lexer::{Lexer, Terminal};
+    /// # use lisbeth_parser::{doc_fixtures::Dot, token};
+    /// #
+    /// # token! {
+    /// #     #[derive(Clone, Debug, PartialEq)]
+    /// #     Token = Dot
+    /// # }
+    /// let source = "...";
+    /// let lexer = Lexer::<Token>::from_str_input(source).unwrap();
+    ///
+    /// assert_eq!(lexer.reconstruct(source), source);
+    /// ```
+    pub fn reconstruct(&self, source: &str) -> String {
+        self.0.iter().map(|tok| &source[tok.span()]).collect()
+    }
+
+    /// Returns an iterator over the tokens, each paired with its span.
+    ///
+    /// This allows to read the span of each token without having the
+    /// [`Token`] trait in scope.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use lisbeth_error::span::{Span, SpannedStr};
+    /// # use lisbeth_parser::lexer::{Lexer, Terminal};
+    /// # use lisbeth_parser::{doc_fixtures::Dot, token};
+    /// #
+    /// # token! {
+    /// #     #[derive(Clone, Debug, PartialEq)]
+    /// #     Token = Dot
+    /// # }
+    /// let lexer = Lexer::<Token>::from_str_input("..").unwrap();
+    ///
+    /// let columns = lexer
+    ///     .iter_spanned()
+    ///     .map(|(_, span)| span.start().col())
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert_eq!(columns, [0, 1]);
+    /// ```
+    pub fn iter_spanned(&self) -> impl Iterator<Item = (&Tok, Span)> + '_ {
+        self.0.iter().map(|tok| (tok, tok.span()))
+    }
+
+    /// Counts the tokens of each kind.
+    ///
+    /// Kinds are named by [`Token::kind_name`]. Kinds that do not appear in
+    /// the token sequence are not included in the histogram.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use lisbeth_error::span::{Span, SpannedStr};
+    /// # use lisbeth_parser::lexer::{Lexer, Terminal};
+    /// # use lisbeth_parser::{doc_fixtures::Dot, token};
+    /// #
+    /// # token! {
+    /// #     #[derive(Clone, Debug, PartialEq)]
+    /// #     Token = Dot with Eof
+    /// # }
+    /// let lexer = Lexer::<Token>::from_str_input("...").unwrap();
+    /// let histogram = lexer.kind_histogram();
+    ///
+    /// assert_eq!(histogram["`.`"], 3);
+    /// assert_eq!(histogram["end of file"], 1);
+    /// ```
+    pub fn kind_histogram(&self) -> BTreeMap<&'static str, usize> {
+        let mut histogram = BTreeMap::new();
+
+        for tok in self.0.iter() {
+            *histogram.entry(tok.kind_name()).or_insert(0) += 1;
+        }
+
+        histogram
+    }
+
+    /// Interns the text of each token, returning one symbol per token.
+    ///
+    /// `source` must be the input the tokens have been lexed from. Tokens
+    /// with equal texts get equal symbols, which allows to compare them
+    /// without storing each text. See the [`intern`] module for more.
+    ///
+    /// [`intern`]: crate::intern
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use lisbeth_error::span::{Span, SpannedStr};
+    /// # use lisbeth_parser::lexer::{Lexer, Terminal};
+    /// # use lisbeth_parser::{doc_fixtures::Dot, token};
+    /// #
+    /// # token! {
+    /// #     #[derive(Clone, Debug, PartialEq)]
+    /// #     Token = Dot
+    /// # }
+    /// use lisbeth_parser::intern::StringInterner;
+    ///
+    /// let source = "...";
+    /// let lexer = Lexer::<Token>::from_str_input(source).unwrap();
+    ///
+    /// let mut interner = StringInterner::new();
+    /// let symbols = lexer.intern_texts(source, &mut interner);
+    ///
+    /// assert_eq!(symbols.len(), 3);
+    /// assert_eq!(interner.len(), 1);
+    /// ```
+    pub fn intern_texts<I>(&self, source: &str, interner: &mut I) -> Vec<I::Symbol>
+    where
+        I: Interner,
+    {
+        self.0
+            .iter()
+            .map(|tok| interner.intern(&source[tok.span()]))
+            .collect()
+    }
+
+    /// Merges the runs of consecutive tokens for which `same` returns `true`.
+    ///
+    /// `same` is called with the previous token and the current one. When it
+    /// returns `true`, both tokens are replaced with the token returned by
+    /// `combine`, which is typically the previous token with a span extended
+    /// up to the end of the current one. This is useful for tokens that
+    /// naturally repeat, such as whitespaces.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use lisbeth_error::span::{Span, SpannedStr};
+    /// # use lisbeth_parser::lexer::{Lexer, Terminal};
+    /// # use lisbeth_parser::{doc_fixtures::Dot, token};
+    /// #
+    /// # token! {
+    /// #     #[derive(Clone, Debug, PartialEq)]
+    /// #     Token = Dot
+    /// # }
+    /// let lexer = Lexer::<Token>::from_str_input("...").unwrap();
+    ///
+    /// // The lexer now contains a single token, which spans the whole input.
+    /// let lexer = lexer.coalesce(
+    ///     |_, _| true,
+    ///     |first, last| Token { span: first.span.merge(last.span), ..first },
+    /// );
+    /// ```
+    pub fn coalesce<F, G>(self, same: F, mut combine: G) -> Lexer<Tok>
+    where
+        F: Fn(&Tok, &Tok) -> bool,
+        G: FnMut(Tok, &Tok) -> Tok,
+    {
+        let mut toks = Vec::<Tok>::with_capacity(self.0.len());
+
+        for tok in self.0 {
+            match toks.pop() {
+                Some(last) if same(&last, &tok) => toks.push(combine(last, &tok)),
+                Some(last) => {
+                    toks.push(last);
+                    toks.push(tok);
+                }
+                None => toks.push(tok),
+            }
+        }
+
+        Lexer(toks)
+    }
+
+    /// Lexes the input lazily, collecting the errors instead of stopping at
+    /// the first one.
+    ///
+    /// The returned iterator yields the tokens one at a time, as soon as they
+    /// are lexed. The errors encountered meanwhile are stored in the iterator,
+    /// and can be read or drained at any time, for instance once iteration is
+    /// over. Iteration stops at the end of the input, or when an error that
+    /// can not be recovered from is encountered.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use lisbeth_error::span::{Span, SpannedStr};
+    /// # use lisbeth_parser::lexer::{Lexer, Terminal};
+    /// # use lisbeth_parser::{doc_fixtures::Dot, token};
+    /// #
+    /// # token! {
+    /// #     #[derive(Clone, Debug, PartialEq)]
+    /// #     Token = Dot
+    /// # }
+    /// let mut tokens = Lexer::<Token>::lazy_lossy(SpannedStr::input_file("..?"));
+    ///
+    /// assert_eq!(tokens.by_ref().count(), 2);
+    /// assert_eq!(tokens.drain_errors().count(), 1);
+    /// ```
+    pub fn lazy_lossy(input: SpannedStr<'_>) -> LazyLossyLexer<'_, Tok> {
+        LazyLossyLexer {
+            input,
+            eof_span: input.span().shrink_to_end(),
+            errors: Vec::new(),
+            done: false,
+            _token: PhantomData,
+        }
+    }
+
+    /// Lexes the input, using `sync` as a set of recovery points.
+    ///
+    /// When no terminal can be lexed and the token does not specify where
+    /// lexing should resume, the lexer skips every character up to and
+    /// including the next character contained in `sync`, and continues lexing
+    /// from there. This allows to report more than one error when the input
+    /// contains multiple unknown characters.
+    ///
+    /// If `sync` is empty or if no recovery point is found in the rest of the
+    /// input, lexing stops at the first unrecoverable error.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use lisbeth_error::span::{Span, SpannedStr};
+    /// # use lisbeth_parser::lexer::{Lexer, Terminal};
+    /// # use lisbeth_parser::{doc_fixtures::Dot, token};
+    /// #
+    /// # token! {
+    /// #     #[derive(Clone, Debug, PartialEq)]
+    /// #     Token = Dot
+    /// # }
+    /// let input = SpannedStr::input_file("..?..;..!");
+    /// let errs = Lexer::<Token>::from_spanned_str_with_sync(input, &[';']).unwrap_err();
+    ///
+    /// assert_eq!(errs.len(), 2);
+    /// ```
+    pub fn from_spanned_str_with_sync(
+        input: SpannedStr,
+        sync: &[char],
+    ) -> Result<Lexer<Tok>, Vec<AnnotatedError>> {
+        Self::lex_with_sync(input, sync, 0)
+    }
+
+    /// Lexes the input, allocating room for `capacity` tokens upfront.
+    ///
+    /// This avoids growing the token vector one reallocation at a time when
+    /// lexing large inputs. Since every token covers at least one byte, the
+    /// length of the input is an upper bound of the number of tokens; a
+    /// fraction of it is usually a better estimate. The hint only affects
+    /// performance: the result is the same as when converting the input with
+    /// [`TryFrom`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::convert::TryFrom;
+    /// # use lisbeth_error::span::{Span, SpannedStr};
+    /// # use lisbeth_parser::lexer::{Lexer, Terminal};
+    /// # use lisbeth_parser::{doc_fixtures::Dot, token};
+    /// #
+    /// # token! {
+    /// #     #[derive(Clone, Debug, PartialEq)]
+    /// #     Token = Dot
+    /// # }
+    /// let input = SpannedStr::input_file("....");
+    /// let capacity = input.content().len();
+    ///
+    /// let tokens = Lexer::<Token>::from_spanned_str_with_capacity(input, capacity);
+    ///
+    /// assert_eq!(tokens, Lexer::try_from(input));
+    /// ```
+    pub fn from_spanned_str_with_capacity(
+        input: SpannedStr,
+        capacity: usize,
+    ) -> Result<Lexer<Tok>, Vec<AnnotatedError>> {
+        Self::lex_with_sync(input, &[], capacity)
+    }
+
+    // Lexes input, recovering at sync characters. The token vector is
+    // allocated with room for capacity tokens and the end-of-file token. The
+    // error vector is not allocated until an error is encountered, as errors
+    // are not expected on the hot path.
+    fn lex_with_sync(
+        mut input: SpannedStr,
+        sync: &[char],
+        capacity: usize,
+    ) -> Result<Lexer<Tok>, Vec<AnnotatedError>> {
+        let mut toks = match capacity {
+            0 => Vec::<Tok>::new(),
+            _ => Vec::<Tok>::with_capacity(capacity.saturating_add(1)),
+        };
         let mut errs = Vec::<AnnotatedError>::new();
+        let eof_span = input.span().shrink_to_end();
 
         while !input.content().is_empty() {
-            match Tok::from_str(input) {
-                Ok((tok, tail)) => {
-                    // Everything went correctly
+            match lex_step(input, sync) {
+                Step::Token(tok, tail) => {
                     toks.push(tok);
                     input = tail;
                 }
-                Err((es, Some(tail))) => {
-                    // Some errors, but we still know where to resume
+                Step::Recovered(es, tail) => {
                     errs.extend(es);
                     input = tail;
                 }
-                Err((es, None)) => {
-                    // Some errors, but we don't know where to resume
+                Step::Fatal(es) => {
                     errs.extend(es);
                     return Err(errs);
                 }
@@ -300,83 +898,1050 @@ impl<Tok: Token> Lexer<Tok> {
         }
 
         if errs.is_empty() {
+            toks.extend(Tok::eof(eof_span));
             Ok(Lexer(toks))
         } else {
             Err(errs)
         }
     }
-}
 
-impl<'a, Tok> TryFrom<SpannedStr<'a>> for Lexer<Tok>
-where
-    Tok: Token + 'a,
-{
-    type Error = Vec<AnnotatedError>;
+    /// Lexes the input and returns a summary of what happened.
+    ///
+    /// Unlike [`Lexer::from_spanned_str_with_sync`], the tokens that have
+    /// been lexed are returned even when errors are encountered. The returned
+    /// [`LexReport`] also counts how many times the lexer recovered from an
+    /// error. Lexing stops at the first error that can't be recovered from.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use lisbeth_error::span::{Span, SpannedStr};
+    /// # use lisbeth_parser::lexer::{Lexer, Terminal};
+    /// # use lisbeth_parser::{doc_fixtures::Dot, token};
+    /// #
+    /// # token! {
+    /// #     #[derive(Clone, Debug, PartialEq)]
+    /// #     Token = Dot
+    /// # }
+    /// let input = SpannedStr::input_file("..?..");
+    /// let report = Lexer::<Token>::from_spanned_str_reported(input);
+    ///
+    /// assert_eq!(report.tokens.len(), 2);
+    /// assert_eq!(report.errors.len(), 1);
+    /// assert_eq!(report.recovered, 0);
+    /// ```
+    pub fn from_spanned_str_reported(mut input: SpannedStr) -> LexReport<Tok> {
+        let mut report = LexReport {
+            tokens: Vec::new(),
+            errors: Vec::new(),
+            recovered: 0,
+        };
+        let eof_span = input.span().shrink_to_end();
 
-    fn try_from(input: SpannedStr) -> Result<Self, Self::Error> {
-        Self::from_spanned_str(input)
+        while !input.content().is_empty() {
+            match lex_step(input, &[]) {
+                Step::Token(tok, tail) => {
+                    report.tokens.push(tok);
+                    input = tail;
+                }
+                Step::Recovered(es, tail) => {
+                    report.errors.extend(es);
+                    report.recovered += 1;
+                    input = tail;
+                }
+                Step::Fatal(es) => {
+                    report.errors.extend(es);
+                    return report;
+                }
+            }
+        }
+
+        report.tokens.extend(Tok::eof(eof_span));
+        report
     }
-}
 
-#[cfg(test)]
-mod lexer {
-    use super::*;
+    /// Lexes the input, unless it is longer than `max_bytes` bytes.
+    ///
+    /// The length check happens before any lexing, so that oversized inputs
+    /// are rejected without doing any work. In that case, a single error
+    /// spanning the whole input is returned. This is a shorthand for
+    /// [`Lexer::from_spanned_str_limited`] with only [`LexLimits::max_bytes`]
+    /// set.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use lisbeth_error::span::{Span, SpannedStr};
+    /// # use lisbeth_parser::lexer::{Lexer, Terminal};
+    /// # use lisbeth_parser::{doc_fixtures::Dot, token};
+    /// #
+    /// # token! {
+    /// #     #[derive(Clone, Debug, PartialEq)]
+    /// #     Token = Dot
+    /// # }
+    /// let input = SpannedStr::input_file("....");
+    ///
+    /// assert!(Lexer::<Token>::from_spanned_str_with_limit(input, 4).is_ok());
+    /// assert!(Lexer::<Token>::from_spanned_str_with_limit(input, 3).is_err());
+    /// ```
+    pub fn from_spanned_str_with_limit(
+        input: SpannedStr,
+        max_bytes: usize,
+    ) -> Result<Lexer<Tok>, Vec<AnnotatedError>> {
+        let limits = LexLimits {
+            max_bytes: Some(max_bytes),
+            ..LexLimits::default()
+        };
 
-    mod from_spanned_str {
-        use super::*;
+        Self::from_spanned_str_limited(input, &limits)
+    }
 
-        #[derive(Clone, Debug, PartialEq)]
-        struct Dash;
-        #[derive(Clone, Debug, PartialEq)]
-        struct Dot;
+    /// Lexes the input, stopping as soon as one of the bounds of `limits` is
+    /// exceeded.
+    ///
+    /// When a bound is exceeded, the errors encountered so far are returned,
+    /// followed by a `Limit exceeded` error. This error has a `limit`
+    /// metadata entry, whose value is `bytes`, `tokens` or `errors` depending
+    /// on the bound that has been hit. The input length is checked before any
+    /// lexing happens.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use lisbeth_error::span::{Span, SpannedStr};
+    /// # use lisbeth_parser::lexer::{LexLimits, Lexer, Terminal};
+    /// # use lisbeth_parser::{doc_fixtures::Dot, token};
+    /// #
+    /// # token! {
+    /// #     #[derive(Clone, Debug, PartialEq)]
+    /// #     Token = Dot
+    /// # }
+    /// let limits = LexLimits {
+    ///     max_tokens: Some(3),
+    ///     ..LexLimits::default()
+    /// };
+    ///
+    /// let input = SpannedStr::input_file("....");
+    /// let errs = Lexer::<Token>::from_spanned_str_limited(input, &limits).unwrap_err();
+    ///
+    /// assert_eq!(errs[0].meta("limit"), Some("tokens"));
+    /// ```
+    pub fn from_spanned_str_limited(
+        mut input: SpannedStr,
+        limits: &LexLimits,
+    ) -> Result<Lexer<Tok>, Vec<AnnotatedError>> {
+        if let Some(max_bytes) = limits.max_bytes {
+            if input.content().len() > max_bytes {
+                return Err(vec![limit_exceeded_error(input.span(), "bytes", max_bytes)]);
+            }
+        }
 
-        impl Terminal for Dash {
-            fn lex(i: SpannedStr) -> Option<LexingResult<Self>> {
-                if i.content().starts_with('-') {
-                    let (matched, tail) = i.split_at(1);
-                    let d = Dash;
-                    let s = matched.span();
-                    return Some(Ok((d, s, tail)));
-                }
+        let mut toks = Vec::<Tok>::new();
+        let mut errs = Vec::<AnnotatedError>::new();
+        let eof_span = input.span().shrink_to_end();
 
-                if i.content().starts_with('_') {
-                    let (matched, tail) = i.split_at(1);
-                    let report = AnnotatedError::new(matched.span(), "Expected `-`, found `_`");
-                    return Some(Err((vec![report], Some(tail))));
-                }
+        while !input.content().is_empty() {
+            let (es, tail) = match lex_step::<Tok>(input, &[]) {
+                Step::Token(tok, tail) => {
+                    let span = tok.span();
+                    toks.push(tok);
 
-                return None;
-            }
+                    if let Some(max_tokens) = limits.max_tokens.filter(|max| toks.len() > *max) {
+                        errs.push(limit_exceeded_error(span, "tokens", max_tokens));
+                        return Err(errs);
+                    }
 
-            fn specific_description(&self) -> String {
-                "`-`".to_string()
+                    input = tail;
+                    continue;
+                }
+                Step::Recovered(es, tail) => (es, Some(tail)),
+                Step::Fatal(es) => (es, None),
+            };
+
+            errs.extend(es);
+
+            if let Some(max_errors) = limits.max_errors.filter(|max| errs.len() > *max) {
+                errs.truncate(max_errors);
+                errs.push(limit_exceeded_error(input.span(), "errors", max_errors));
+                return Err(errs);
+            }
+
+            match tail {
+                Some(tail) => input = tail,
+                None => return Err(errs),
+            }
+        }
+
+        if errs.is_empty() {
+            toks.extend(Tok::eof(eof_span));
+            Ok(Lexer(toks))
+        } else {
+            Err(errs)
+        }
+    }
+
+    /// Checks that the tokens that must be separated are not adjacent.
+    ///
+    /// `needs_separator` is called on each pair of consecutive tokens. When it
+    /// returns `true` and nothing separates the two tokens in the input, an
+    /// error is reported. This allows to reject inputs such as `1234` when it
+    /// is meant to be two number literals that can not follow each other.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use lisbeth_error::span::{Span, SpannedStr};
+    /// # use lisbeth_parser::lexer::{Lexer, Terminal};
+    /// # use lisbeth_parser::{doc_fixtures::Dot, token};
+    /// #
+    /// # token! {
+    /// #     #[derive(Clone, Debug, PartialEq)]
+    /// #     Token = Dot
+    /// # }
+    /// let errs = Lexer::<Token>::from_str_input("...")
+    ///     .and_then(|lexer| lexer.require_separators(|_, _| true))
+    ///     .unwrap_err();
+    ///
+    /// assert_eq!(errs.len(), 2);
+    /// ```
+    pub fn require_separators<F>(
+        self,
+        needs_separator: F,
+    ) -> Result<Lexer<Tok>, Vec<AnnotatedError>>
+    where
+        F: Fn(&Tok, &Tok) -> bool,
+    {
+        let errs = self
+            .0
+            .windows(2)
+            .filter(|pair| pair[0].span().is_adjacent_to(pair[1].span()))
+            .filter(|pair| needs_separator(&pair[0], &pair[1]))
+            .map(|pair| {
+                let (left, right) = (&pair[0], &pair[1]);
+                let msg = format!(
+                    "Missing separator between {} and {}",
+                    left.describe(),
+                    right.describe(),
+                );
+
+                AnnotatedError::new(left.span().merge(right.span()), msg)
+                    .with_annotation(right.span(), "Expected a separator before this token")
+            })
+            .collect::<Vec<_>>();
+
+        if errs.is_empty() {
+            Ok(self)
+        } else {
+            Err(errs)
+        }
+    }
+}
+
+impl<Tok> Lexer<Tok> {
+    /// Transforms each token of the lexer with `f`.
+    ///
+    /// This allows to post-process the tokens once lexing is done, for
+    /// instance to tell keywords apart from identifiers. The order of the
+    /// tokens is preserved.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use lisbeth_error::span::{Span, SpannedStr};
+    /// # use lisbeth_parser::lexer::{Lexer, Terminal};
+    /// # use lisbeth_parser::{doc_fixtures::Dot, token};
+    /// #
+    /// # token! {
+    /// #     #[derive(Clone, Debug, PartialEq)]
+    /// #     Token = Dot
+    /// # }
+    /// let lexer = Lexer::<Token>::from_str_input("...").unwrap();
+    /// let spans: Lexer<Span> = lexer.map(|tok| tok.span);
+    /// ```
+    pub fn map<U, F>(self, f: F) -> Lexer<U>
+    where
+        F: FnMut(Tok) -> U,
+    {
+        Lexer(self.0.into_iter().map(f).collect())
+    }
+
+    /// Transforms each token of the lexer with `f`, which is given the tokens
+    /// that precede and follow it.
+    ///
+    /// This allows to take decisions that depend on the context of a token,
+    /// such as telling a unary minus apart from a binary minus. `f` is called
+    /// with the previous token, the token to transform and the next token, in
+    /// that order. The neighbors are the tokens as they were before any
+    /// transformation.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use lisbeth_error::span::{Span, SpannedStr};
+    /// # use lisbeth_parser::lexer::{Lexer, Terminal};
+    /// # use lisbeth_parser::{doc_fixtures::Dot, token};
+    /// #
+    /// # token! {
+    /// #     #[derive(Clone, Debug, PartialEq)]
+    /// #     Token = Dot
+    /// # }
+    /// let lexer = Lexer::<Token>::from_str_input("...").unwrap();
+    ///
+    /// // Empties the span of the tokens that are on the edges of the input.
+    /// let lexer = lexer.contextualize(|prev, tok, next| {
+    ///     let mut tok = tok.clone();
+    ///     if prev.is_none() || next.is_none() {
+    ///         tok.span = tok.span.shrink_to_start();
+    ///     }
+    ///     tok
+    /// });
+    ///
+    /// let spans: Lexer<Span> = lexer.map(|tok| tok.span);
+    /// ```
+    pub fn contextualize<F>(self, mut f: F) -> Lexer<Tok>
+    where
+        F: FnMut(Option<&Tok>, &Tok, Option<&Tok>) -> Tok,
+    {
+        let toks = &self.0;
+
+        let contextualized = toks
+            .iter()
+            .enumerate()
+            .map(|(idx, tok)| {
+                let prev = idx.checked_sub(1).map(|prev| &toks[prev]);
+                f(prev, tok, toks.get(idx + 1))
+            })
+            .collect();
+
+        Lexer(contextualized)
+    }
+
+    /// Combines the result of lexing with the result of an other step,
+    /// accumulating the errors of both.
+    ///
+    /// If both `lexed` and `other` are successful, then their values are
+    /// returned. Otherwise, the errors of `lexed` are returned, followed by the
+    /// errors of `other`. This allows to report lexing and parsing errors at
+    /// once instead of stopping at the first failing step.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use lisbeth_error::{error::AnnotatedError, span::{Span, SpannedStr}};
+    /// # use lisbeth_parser::lexer::{Lexer, Terminal};
+    /// # use lisbeth_parser::{doc_fixtures::Dot, token};
+    /// #
+    /// # token! {
+    /// #     #[derive(Clone, Debug, PartialEq)]
+    /// #     Token = Dot
+    /// # }
+    /// let input = SpannedStr::input_file("..?");
+    /// let lexed = Lexer::<Token>::from_spanned_str_with_sync(input, &[]);
+    ///
+    /// let parse_error = AnnotatedError::new(input.span(), "Expected an even number of dots");
+    /// let parsed: Result<(), _> = Err(vec![parse_error]);
+    ///
+    /// let errs = Lexer::errors_or(lexed, parsed).unwrap_err();
+    /// assert_eq!(errs.len(), 2);
+    /// ```
+    pub fn errors_or<T>(
+        lexed: Result<Lexer<Tok>, Vec<AnnotatedError>>,
+        other: Result<T, Vec<AnnotatedError>>,
+    ) -> Result<(Lexer<Tok>, T), Vec<AnnotatedError>> {
+        match (lexed, other) {
+            (Ok(lexer), Ok(value)) => Ok((lexer, value)),
+            (Ok(_), Err(errs)) | (Err(errs), Ok(_)) => Err(errs),
+            (Err(mut errs), Err(other_errs)) => {
+                errs.extend(other_errs);
+                Err(errs)
+            }
+        }
+    }
+}
+
+/// The resource bounds enforced by [`Lexer::from_spanned_str_limited`].
+///
+/// Each bound is disabled when it is `None`, which is the default.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LexLimits {
+    /// The maximum length of the input, in bytes.
+    pub max_bytes: Option<usize>,
+    /// The maximum number of tokens, excluding the end of file token.
+    pub max_tokens: Option<usize>,
+    /// The maximum number of errors.
+    pub max_errors: Option<usize>,
+}
+
+/// A summary of a lexing session.
+///
+/// This structure is created by [`Lexer::from_spanned_str_reported`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct LexReport<Tok> {
+    /// The tokens that have been lexed.
+    pub tokens: Vec<Tok>,
+    /// The errors that have been encountered.
+    pub errors: Vec<AnnotatedError>,
+    /// The number of times the lexer recovered from an error.
+    pub recovered: usize,
+}
+
+impl<Tok> LexReport<Tok> {
+    /// Removes the consecutive duplicated errors.
+    ///
+    /// When the lexer recovers from an error, it may report the same error
+    /// several times in a row. Only the first error of each run of identical
+    /// errors is kept, and the order of the errors is preserved. The lexer
+    /// keeps every error it encounters, so this method must be called
+    /// explicitly.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lisbeth_error::{error::AnnotatedError, span::SpannedStr};
+    /// use lisbeth_parser::lexer::LexReport;
+    ///
+    /// let input = SpannedStr::input_file("?");
+    /// let err = AnnotatedError::new(input.span(), "Unknown start of token: `?`");
+    ///
+    /// let report = LexReport::<()> {
+    ///     tokens: Vec::new(),
+    ///     errors: vec![err.clone(), err],
+    ///     recovered: 1,
+    /// };
+    ///
+    /// assert_eq!(report.dedup_errors().errors.len(), 1);
+    /// ```
+    pub fn dedup_errors(mut self) -> LexReport<Tok> {
+        self.errors.dedup();
+        self
+    }
+}
+
+/// A lexer that yields tokens lazily and collects the errors.
+///
+/// This structure is created by [`Lexer::lazy_lossy`].
+#[derive(Clone, Debug)]
+pub struct LazyLossyLexer<'a, Tok> {
+    input: SpannedStr<'a>,
+    eof_span: Span,
+    errors: Vec<AnnotatedError>,
+    done: bool,
+    _token: PhantomData<Tok>,
+}
+
+impl<'a, Tok> LazyLossyLexer<'a, Tok> {
+    /// Returns the part of the input that has not been lexed yet.
+    ///
+    /// This allows to hand the rest of the input to another lexer, for
+    /// instance to lex a string literal body with different rules. When
+    /// lexing stopped at an unrecoverable error, the returned input starts
+    /// where the error was encountered.
+    pub fn remaining(&self) -> SpannedStr<'a> {
+        self.input
+    }
+
+    /// Returns the errors encountered so far.
+    pub fn errors(&self) -> &[AnnotatedError] {
+        &self.errors
+    }
+
+    /// Removes the errors encountered so far and returns them.
+    pub fn drain_errors(&mut self) -> Drain<'_, AnnotatedError> {
+        self.errors.drain(..)
+    }
+}
+
+impl<'a, Tok: Token> Iterator for LazyLossyLexer<'a, Tok> {
+    type Item = Tok;
+
+    fn next(&mut self) -> Option<Tok> {
+        while !self.done {
+            if self.input.content().is_empty() {
+                self.done = true;
+                return Tok::eof(self.eof_span);
+            }
+
+            match lex_step(self.input, &[]) {
+                Step::Token(tok, tail) => {
+                    self.input = tail;
+                    return Some(tok);
+                }
+                Step::Recovered(es, tail) => {
+                    self.errors.extend(es);
+                    self.input = tail;
+                }
+                Step::Fatal(es) => {
+                    self.errors.extend(es);
+                    self.done = true;
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// Allows to lex a [`SpannedStr`] in a single method call.
+pub trait LexExt {
+    /// Lexes the whole input, and returns both the tokens and the errors
+    /// encountered.
+    ///
+    /// This is equivalent to collecting the tokens of [`Lexer::lazy_lossy`],
+    /// and then draining its errors.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use lisbeth_error::span::{Span, SpannedStr};
+    /// # use lisbeth_parser::lexer::{Terminal};
+    /// # use lisbeth_parser::{doc_fixtures::Dot, token};
+    /// #
+    /// # token! {
+    /// #     #[derive(Clone, Debug, PartialEq)]
+    /// #     Token = Dot
+    /// # }
+    /// use lisbeth_parser::lexer::LexExt;
+    ///
+    /// let (tokens, errors) = SpannedStr::input_file("..?").lex::<Token>();
+    ///
+    /// assert_eq!(tokens.len(), 2);
+    /// assert_eq!(errors.len(), 1);
+    /// ```
+    fn lex<Tok: Token>(self) -> (Vec<Tok>, Vec<AnnotatedError>);
+}
+
+impl LexExt for SpannedStr<'_> {
+    fn lex<Tok: Token>(self) -> (Vec<Tok>, Vec<AnnotatedError>) {
+        let mut lexer = Lexer::<Tok>::lazy_lossy(self);
+
+        let toks = lexer.by_ref().collect();
+        let errs = lexer.drain_errors().collect();
+
+        (toks, errs)
+    }
+}
+
+/// A lexer that is fed its input chunk by chunk.
+///
+/// This is useful when the input arrives in several parts, for instance when
+/// it is read from a stream. Each chunk must continue the previous one: chunks
+/// can be created with [`StreamingLexer::chunk`]. A token can not span across
+/// two chunks, so chunks must be split between tokens.
+///
+/// Errors encountered while lexing are stored in the lexer, and can be read
+/// or drained at any time. When an error that can not be recovered from is
+/// encountered, the rest of the chunk is skipped.
+///
+/// # Example
+///
+/// ```rust
+/// # use lisbeth_error::span::{Span, SpannedStr};
+/// # use lisbeth_parser::lexer::{StreamingLexer, Terminal, Token as _};
+/// # use lisbeth_parser::{doc_fixtures::Dot, token};
+/// #
+/// # token! {
+/// #     #[derive(Clone, Debug, PartialEq)]
+/// #     Token = Dot
+/// # }
+/// let mut lexer = StreamingLexer::<Token>::new();
+///
+/// let first = lexer.feed(lexer.chunk(".."));
+/// let second = lexer.feed(lexer.chunk("."));
+///
+/// assert_eq!(first.len(), 2);
+/// assert_eq!(second[0].span().start().offset(), 2);
+/// assert!(lexer.errors().is_empty());
+/// ```
+#[derive(Clone, Debug)]
+pub struct StreamingLexer<Tok> {
+    position: Position,
+    errors: Vec<AnnotatedError>,
+    _token: PhantomData<Tok>,
+}
+
+impl<Tok> StreamingLexer<Tok> {
+    /// Creates a lexer that expects the beginning of the input.
+    pub fn new() -> StreamingLexer<Tok> {
+        StreamingLexer {
+            position: Position::BEGINNING,
+            errors: Vec::new(),
+            _token: PhantomData,
+        }
+    }
+
+    /// Returns the position at which the next chunk must start.
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    /// Creates the chunk that continues the input fed so far.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the position of the end of the chunk can not be represented,
+    /// as described in [`Position::checked_advance_with`].
+    pub fn chunk<'a>(&self, content: &'a str) -> SpannedStr<'a> {
+        let end = self
+            .position
+            .checked_advance_with(content)
+            .expect("Chunk is too large to be spanned");
+
+        SpannedStr::synthetic(content, Span::new(self.position, end))
+    }
+
+    /// Returns the errors encountered so far.
+    pub fn errors(&self) -> &[AnnotatedError] {
+        &self.errors
+    }
+
+    /// Removes the errors encountered so far and returns them.
+    pub fn drain_errors(&mut self) -> Drain<'_, AnnotatedError> {
+        self.errors.drain(..)
+    }
+}
+
+impl<Tok: Token> StreamingLexer<Tok> {
+    /// Lexes a chunk of input and returns the tokens it contains.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk` does not start at the position at which the
+    /// previous chunk ended.
+    pub fn feed(&mut self, chunk: SpannedStr) -> Vec<Tok> {
+        assert_eq!(
+            chunk.span().start(),
+            self.position,
+            "Chunk does not continue the previous input",
+        );
+
+        let mut input = chunk;
+        let mut toks = Vec::new();
+
+        while !input.content().is_empty() {
+            match lex_step(input, &[]) {
+                Step::Token(tok, tail) => {
+                    toks.push(tok);
+                    input = tail;
+                }
+                Step::Recovered(es, tail) => {
+                    self.errors.extend(es);
+                    input = tail;
+                }
+                Step::Fatal(es) => {
+                    self.errors.extend(es);
+                    break;
+                }
+            }
+        }
+
+        self.position = chunk.span().end();
+        toks
+    }
+
+    /// Ends the input, and returns the end of file token, if the token has
+    /// one.
+    pub fn finish(self) -> Option<Tok> {
+        Tok::eof(Span::new(self.position, self.position))
+    }
+}
+
+impl<Tok> Default for StreamingLexer<Tok> {
+    fn default() -> StreamingLexer<Tok> {
+        StreamingLexer::new()
+    }
+}
+
+// Returns the text of input covered by span, or an empty string if span does
+// not lie in input. This is used by the token macro.
+#[doc(hidden)]
+pub fn matched_text(input: SpannedStr<'_>, span: Span) -> &str {
+    let base = input.span().start().offset();
+    let start = span.start().offset().checked_sub(base);
+    let end = span.end().offset().checked_sub(base);
+
+    match (start, end) {
+        (Some(start), Some(end)) => input
+            .content()
+            .get(start as usize..end as usize)
+            .unwrap_or(""),
+        _ => "",
+    }
+}
+
+// Reports that no terminal can be lexed at the beginning of input. This is
+// used by the token macro.
+#[doc(hidden)]
+pub fn unknown_token_error(input: SpannedStr, expected: &[&str]) -> AnnotatedError {
+    let len = input.content().chars().next().map_or(0, char::len_utf8);
+    let (chr, _) = input.split_at(len);
+
+    AnnotatedError::new(
+        chr.span(),
+        format!("Unknown start of token: `{}`", chr.content()),
+    )
+    .with_annotation(chr.span(), expected_one_of(expected))
+}
+
+// Describes the terminals that can be lexed, such as "Expected one of `.`,
+// `-`".
+fn expected_one_of(descriptions: &[&str]) -> String {
+    match descriptions {
+        [] => "Unknown start of token".to_string(),
+        [description] => format!("Expected {}", description),
+        _ => format!("Expected one of {}", descriptions.join(", ")),
+    }
+}
+
+// Returns the error reported when a bound of LexLimits is exceeded. The name
+// of the bound is stored in the limit metadata entry.
+fn limit_exceeded_error(span: Span, limit: &str, max: usize) -> AnnotatedError {
+    AnnotatedError::new(span, format!("Limit exceeded: more than {} {}", max, limit))
+        .with_meta("limit", limit)
+}
+
+// The outcome of lexing a single token.
+enum Step<'a, Tok> {
+    // A token has been lexed, lexing continues at the tail.
+    Token(Tok, SpannedStr<'a>),
+    // Errors have been encountered, lexing resumes at the tail.
+    Recovered(Vec<AnnotatedError>, SpannedStr<'a>),
+    // Errors have been encountered, and there is no point to resume from.
+    Fatal(Vec<AnnotatedError>),
+}
+
+// Lexes a single token at the beginning of input. This is the step shared by
+// every lexing loop. When the terminal gives no recovery point, lexing
+// resumes after the first character contained in sync, if any.
+fn lex_step<'a, Tok: Token>(input: SpannedStr<'a>, sync: &[char]) -> Step<'a, Tok> {
+    match Tok::from_str(input) {
+        Ok((tok, tail)) => Step::Token(tok, tail),
+        Err((es, Some(tail))) => {
+            debug_assert_advances(input, tail);
+            Step::Recovered(es, tail)
+        }
+        Err((es, None)) => match skip_past_sync_char(input, sync) {
+            Some(tail) => Step::Recovered(es, tail),
+            None => Step::Fatal(es),
+        },
+    }
+}
+
+// Checks that error recovery resumes after the beginning of the input. A
+// terminal that returns its own input as recovery point would make the lexer
+// loop forever.
+fn debug_assert_advances(input: SpannedStr, tail: SpannedStr) {
+    debug_assert!(
+        tail.span().start().offset() > input.span().start().offset(),
+        "Error recovery did not advance past offset {}",
+        input.span().start().offset(),
+    );
+}
+
+// Returns the input that follows the first character contained in sync, if
+// any.
+fn skip_past_sync_char<'a>(input: SpannedStr<'a>, sync: &[char]) -> Option<SpannedStr<'a>> {
+    let idx = input
+        .content()
+        .char_indices()
+        .find(|(_, chr)| sync.contains(chr))
+        .map(|(idx, chr)| idx + chr.len_utf8())?;
+
+    Some(input.split_at(idx).1)
+}
+
+impl<'a, Tok> TryFrom<SpannedStr<'a>> for Lexer<Tok>
+where
+    Tok: Token + 'a,
+{
+    type Error = Vec<AnnotatedError>;
+
+    fn try_from(input: SpannedStr) -> Result<Self, Self::Error> {
+        Self::from_spanned_str(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod repetition {
+        use super::*;
+
+        #[test]
+        fn one_or_more_digits() {
+            let input = SpannedStr::input_file("123abc");
+            let (span, tail) = lex_one_or_more(input, |c| c.is_ascii_digit()).unwrap();
+
+            assert_eq!(span, input.split_at(3).0.span());
+            assert_eq!(tail.content(), "abc");
+        }
+
+        #[test]
+        fn one_or_more_rejects_non_matching() {
+            let input = SpannedStr::input_file("abc");
+
+            assert!(lex_one_or_more(input, |c| c.is_ascii_digit()).is_none());
+        }
+
+        #[test]
+        fn zero_or_more_accepts_non_matching() {
+            let input = SpannedStr::input_file("abc");
+            let (span, tail) = lex_zero_or_more(input, |c| c.is_ascii_digit());
+
+            assert_eq!(span.start(), span.end());
+            assert_eq!(tail, input);
+        }
+    }
+
+    mod intern_texts {
+        use super::*;
+
+        use crate::intern::StringInterner;
+
+        #[derive(Clone, Debug, PartialEq)]
+        struct Word;
+        #[derive(Clone, Debug, PartialEq)]
+        struct Space;
+
+        impl Terminal for Word {
+            fn lex(i: SpannedStr) -> Option<LexingResult<Self>> {
+                let (span, tail) = lex_one_or_more(i, char::is_alphabetic)?;
+                Some(Ok((Word, span, tail)))
+            }
+
+            fn specific_description(&self) -> Cow<'static, str> {
+                Cow::Borrowed("word")
             }
 
-            const DESCRIPTION: &'static str = "`-`";
+            const DESCRIPTION: &'static str = "word";
+        }
+
+        impl Terminal for Space {
+            fn lex(i: SpannedStr) -> Option<LexingResult<Self>> {
+                let (span, tail) = lex_one_or_more(i, |c| c == ' ')?;
+                Some(Ok((Space, span, tail)))
+            }
+
+            fn specific_description(&self) -> Cow<'static, str> {
+                Cow::Borrowed("space")
+            }
+
+            const DESCRIPTION: &'static str = "space";
+        }
+
+        token! {
+            #[derive(Debug, PartialEq)]
+            WordToken = Word | Space
+        }
+
+        #[test]
+        fn repeated_identifiers() {
+            let source = "foo bar foo baz bar";
+            let l = Lexer::<WordToken>::from_str_input(source).unwrap();
+
+            let mut interner = StringInterner::new();
+            let symbols = l.intern_texts(source, &mut interner);
+
+            let words = symbols.iter().step_by(2).copied().collect::<Vec<_>>();
+            let (foo, bar, baz) = (words[0], words[1], words[3]);
+
+            assert_eq!(words, [foo, bar, foo, baz, bar]);
+            assert_ne!(foo, bar);
+            assert_ne!(bar, baz);
+
+            assert_eq!(interner.resolve(foo), Some("foo"));
+            assert_eq!(interner.resolve(symbols[1]), Some(" "));
+            // Three distinct words and a single space.
+            assert_eq!(interner.len(), 4);
+        }
+    }
+
+    mod from_spanned_str {
+        use super::*;
+
+        #[derive(Clone, Debug, PartialEq)]
+        struct Dash;
+        #[derive(Clone, Debug, PartialEq)]
+        struct Dot;
+
+        impl Terminal for Dash {
+            fn lex(i: SpannedStr) -> Option<LexingResult<Self>> {
+                if i.content().starts_with('-') {
+                    let (matched, tail) = i.split_at(1);
+                    let d = Dash;
+                    let s = matched.span();
+                    return Some(Ok((d, s, tail)));
+                }
+
+                if i.content().starts_with('_') {
+                    let (matched, tail) = i.split_at(1);
+                    let report = AnnotatedError::new(matched.span(), "Expected `-`, found `_`");
+                    return Some(Err((vec![report], Some(tail))));
+                }
+
+                None
+            }
+
+            fn specific_description(&self) -> Cow<'static, str> {
+                Cow::Borrowed("`-`")
+            }
+
+            const DESCRIPTION: &'static str = "`-`";
+        }
+
+        impl Terminal for Dot {
+            fn lex(i: SpannedStr) -> Option<LexingResult<Self>> {
+                if i.content().starts_with('.') {
+                    let (matched, tail) = i.split_at(1);
+                    let d = Dot;
+                    let s = matched.span();
+                    return Some(Ok((d, s, tail)));
+                }
+
+                None
+            }
+
+            fn specific_description(&self) -> Cow<'static, str> {
+                Cow::Borrowed("`.`")
+            }
+
+            const DESCRIPTION: &'static str = "`.`";
+        }
+
+        token! {
+            #[derive(Clone, Debug, PartialEq)]
+            MorseToken = Dash | Dot
+        }
+
+        token! {
+            #[derive(Debug, PartialEq)]
+            MorseTokenWithEof = Dash | Dot with Eof
+        }
+
+        token! {
+            #[derive(Debug, PartialEq)]
+            MorseTokenWithText with text = Dash | Dot with Eof
+        }
+
+        #[test]
+        fn stores_matched_text() {
+            let input = SpannedStr::input_file("-.");
+            let l = Lexer::<MorseTokenWithText>::from_spanned_str(input).unwrap();
+
+            let left = l.0.iter().map(MorseTokenWithText::text).collect::<Vec<_>>();
+            let right = vec!["-", ".", ""];
+
+            assert_eq!(left, right);
+            assert_eq!(l.0[1].kind, MorseTokenWithTextKind::Dot(Dot));
+            assert_eq!(l.0[1].text(), ".");
         }
 
-        impl Terminal for Dot {
-            fn lex(i: SpannedStr) -> Option<LexingResult<Self>> {
-                if i.content().starts_with('.') {
-                    let (matched, tail) = i.split_at(1);
-                    let d = Dot;
-                    let s = matched.span();
-                    return Some(Ok((d, s, tail)));
-                }
+        #[test]
+        fn matched_text_outside_input() {
+            let (head, tail) = SpannedStr::input_file("-.").split_at(1);
 
-                return None;
-            }
+            assert_eq!(matched_text(tail, head.span()), "");
+            assert_eq!(matched_text(tail, tail.span()), ".");
+        }
+
+        #[test]
+        fn working_case_with_eof() {
+            let input = SpannedStr::input_file(".--");
+            let l = Lexer::<MorseTokenWithEof>::from_spanned_str(input).unwrap();
+
+            let left_parsed_tokens = l.0.iter().map(|t| &t.kind).collect::<Vec<_>>();
+
+            let right_parsed_tokens = vec![
+                &MorseTokenWithEofKind::Dot(Dot),
+                &MorseTokenWithEofKind::Dash(Dash),
+                &MorseTokenWithEofKind::Dash(Dash),
+                &MorseTokenWithEofKind::Eof,
+            ];
+
+            assert_eq!(left_parsed_tokens, right_parsed_tokens);
+
+            let eof_span = l.0.last().unwrap().span;
+
+            assert_eq!(eof_span.start(), eof_span.end());
+            assert_eq!(eof_span.start(), input.span().end());
+        }
+
+        #[test]
+        fn iter_spanned() {
+            let input = SpannedStr::input_file(".--.");
+            let l = Lexer::<MorseTokenWithEof>::from_spanned_str(input).unwrap();
+
+            let pairs = l.iter_spanned().collect::<Vec<_>>();
+
+            assert_eq!(pairs.len(), 5);
+            assert!(pairs.iter().all(|(tok, span)| tok.span == *span));
+            assert!(pairs
+                .windows(2)
+                .all(|pair| pair[0].1.end() <= pair[1].1.start()));
+            assert_eq!(pairs[3].0.kind, MorseTokenWithEofKind::Dot(Dot));
+        }
+
+        #[test]
+        fn kind_histogram() {
+            let input = SpannedStr::input_file("-..-.");
+            let l = Lexer::<MorseTokenWithEof>::from_spanned_str(input).unwrap();
+
+            let left = l.kind_histogram();
+            let right = vec![("`-`", 2), ("`.`", 3), ("end of file", 1)]
+                .into_iter()
+                .collect::<BTreeMap<_, _>>();
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn kind_histogram_spanned() {
+            let l = Lexer::<Spanned<Dot>>::from_str_input("...").unwrap();
 
-            fn specific_description(&self) -> String {
-                "`.`".to_string()
+            let left = l.kind_histogram();
+            let right = vec![("`.`", 3)].into_iter().collect::<BTreeMap<_, _>>();
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn with_capacity_same_result() {
+            for content in [".--.", ".-_.", ""] {
+                let input = SpannedStr::input_file(content);
+
+                let left = Lexer::<MorseTokenWithEof>::from_spanned_str_with_capacity(input, 16);
+                let right = Lexer::<MorseTokenWithEof>::from_spanned_str(input);
+
+                assert_eq!(left, right);
             }
+        }
 
-            const DESCRIPTION: &'static str = "`.`";
+        #[test]
+        fn with_capacity_preallocates() {
+            let input = SpannedStr::input_file(".--.");
+            let l = Lexer::<MorseTokenWithEof>::from_spanned_str_with_capacity(input, 16).unwrap();
+
+            // Room is reserved for the requested tokens and the end-of-file
+            // token, even though fewer tokens are lexed.
+            assert_eq!(l.0.len(), 5);
+            assert!(l.0.capacity() >= 17);
         }
 
-        token! {
-            #[derive(Debug, PartialEq)]
-            MorseToken = Dash | Dot
+        #[test]
+        fn describe_does_not_allocate() {
+            let input = SpannedStr::input_file(".");
+            let l = Lexer::<MorseTokenWithEof>::from_spanned_str(input).unwrap();
+
+            let descriptions = l.0.iter().map(Token::describe).collect::<Vec<_>>();
+
+            assert_eq!(descriptions, vec!["`.`", "end of file"]);
+            assert!(descriptions.iter().all(|d| matches!(d, Cow::Borrowed(_))));
+        }
+
+        #[test]
+        fn can_lex() {
+            assert!(Dot::can_lex(SpannedStr::input_file(".")));
+            assert!(!Dot::can_lex(SpannedStr::input_file("-")));
         }
 
         #[test]
@@ -403,6 +1968,302 @@ mod lexer {
             assert_eq!(left_parsed_tokens, right_parsed_tokens);
         }
 
+        #[test]
+        fn from_str_input() {
+            let left = Lexer::<MorseToken>::from_str_input("...---...").unwrap();
+            let right = Lexer::<MorseToken>::try_from(SpannedStr::input_file("...---...")).unwrap();
+
+            assert_eq!(left, right);
+            assert_eq!(left.0.len(), 9);
+        }
+
+        #[test]
+        fn dedup_identical_errors() {
+            let input = SpannedStr::input_file(".-_.");
+
+            let mut report = Lexer::<MorseTokenWithEof>::from_spanned_str_reported(input);
+            let err = report.errors[0].clone();
+            report.errors.push(err.clone());
+            assert_eq!(report.errors.len(), 2);
+
+            let deduped = report.dedup_errors();
+            assert_eq!(deduped.errors, [err]);
+            assert_eq!(deduped.recovered, 1);
+        }
+
+        #[test]
+        fn dedup_keeps_non_consecutive_errors() {
+            let input = SpannedStr::input_file("_._");
+
+            let report = Lexer::<MorseTokenWithEof>::from_spanned_str_reported(input);
+            let errors = report.errors.clone();
+            assert_eq!(errors.len(), 2);
+
+            assert_eq!(report.dedup_errors().errors, errors);
+        }
+
+        #[test]
+        fn reported_with_one_recovery() {
+            let input = SpannedStr::input_file(".-_.");
+            let report = Lexer::<MorseTokenWithEof>::from_spanned_str_reported(input);
+
+            assert_eq!(report.tokens.len(), 4);
+            assert_eq!(report.errors.len(), 1);
+            assert_eq!(report.recovered, 1);
+        }
+
+        #[test]
+        fn reported_stops_at_unrecoverable_error() {
+            let input = SpannedStr::input_file(".|.");
+            let report = Lexer::<MorseTokenWithEof>::from_spanned_str_reported(input);
+
+            assert_eq!(report.tokens.len(), 1);
+            assert_eq!(report.errors.len(), 1);
+            assert_eq!(report.recovered, 0);
+        }
+
+        #[test]
+        fn reconstruct_round_trip() {
+            let source = "..--.-";
+            let l = Lexer::<MorseTokenWithEof>::from_str_input(source).unwrap();
+
+            assert_eq!(l.reconstruct(source), source);
+        }
+
+        #[test]
+        fn coalesce_dots() {
+            let input = SpannedStr::input_file("..-...");
+            let l = Lexer::<MorseToken>::from_spanned_str(input).unwrap();
+
+            let coalesced = l.coalesce(
+                |left, right| {
+                    matches!(
+                        (&left.kind, &right.kind),
+                        (MorseTokenKind::Dot(_), MorseTokenKind::Dot(_))
+                    )
+                },
+                |first, last| MorseToken {
+                    span: first.span.merge(last.span),
+                    ..first
+                },
+            );
+
+            let left = coalesced
+                .0
+                .iter()
+                .map(|tok| (tok.describe(), tok.span))
+                .collect::<Vec<_>>();
+
+            let right = vec![
+                (Cow::Borrowed("`.`"), input.split_at(2).0.span()),
+                (
+                    Cow::Borrowed("`-`"),
+                    input.split_at(2).1.split_at(1).0.span(),
+                ),
+                (Cow::Borrowed("`.`"), input.split_at(3).1.span()),
+            ];
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn coalesce_nothing() {
+            let l = Lexer::<MorseToken>::from_str_input(".-.-").unwrap();
+            let coalesced = l.coalesce(|_, _| false, |first, _| first);
+
+            assert_eq!(coalesced, Lexer::from_str_input(".-.-").unwrap());
+        }
+
+        #[test]
+        fn spanned_value_and_span() {
+            let input = SpannedStr::input_file(".");
+            let dot = Spanned::new(Dot, input.span());
+
+            assert_eq!(*dot, Dot);
+            assert_eq!(dot.span(), input.span());
+            assert_eq!(dot.into_inner(), Dot);
+        }
+
+        #[test]
+        fn spanned_lexer() {
+            let input = SpannedStr::input_file("...");
+            let l = Lexer::<Spanned<Dot>>::from_spanned_str(input).unwrap();
+
+            let right = (0..3)
+                .map(|idx| Spanned::new(Dot, input.split_at(idx).1.split_at(1).0.span()))
+                .collect::<Vec<_>>();
+
+            assert_eq!(l.0, right);
+        }
+
+        #[test]
+        fn spanned_lexer_unknown_token() {
+            let input = SpannedStr::input_file("-");
+            let errs = Lexer::<Spanned<Dot>>::from_spanned_str(input).unwrap_err();
+
+            let right = AnnotatedError::new(input.span(), "Unknown start of token: `-`")
+                .with_annotation(input.span(), "Expected `.`");
+
+            assert_eq!(errs, vec![right]);
+        }
+
+        #[test]
+        fn map_preserves_order() {
+            #[derive(Debug, PartialEq)]
+            enum Signal {
+                Short,
+                Long,
+            }
+
+            let l = Lexer::<MorseToken>::from_str_input(".-.").unwrap();
+            let mapped = l.map(|tok| match tok.kind {
+                MorseTokenKind::Dot(_) => Signal::Short,
+                MorseTokenKind::Dash(_) => Signal::Long,
+            });
+
+            assert_eq!(mapped.0, vec![Signal::Short, Signal::Long, Signal::Short]);
+        }
+
+        #[test]
+        fn contextualize_relabels_dash_after_dot() {
+            let l = Lexer::<MorseToken>::from_str_input("-.--").unwrap();
+
+            // A dash that directly follows a dot is turned into a dot.
+            let contextualized = l.contextualize(|prev, tok, _| match (prev, &tok.kind) {
+                (
+                    Some(MorseToken {
+                        kind: MorseTokenKind::Dot(_),
+                        ..
+                    }),
+                    MorseTokenKind::Dash(_),
+                ) => MorseToken {
+                    kind: MorseTokenKind::Dot(Dot),
+                    span: tok.span,
+                },
+                _ => tok.clone(),
+            });
+
+            let left = contextualized
+                .0
+                .iter()
+                .map(|tok| tok.describe())
+                .collect::<Vec<_>>();
+
+            assert_eq!(left, vec!["`-`", "`.`", "`.`", "`-`"]);
+        }
+
+        #[test]
+        fn contextualize_passes_neighbors() {
+            let l = Lexer::<MorseToken>::from_str_input(".-").unwrap();
+            let mut calls = Vec::new();
+
+            l.contextualize(|prev, tok, next| {
+                calls.push((prev.is_some(), next.is_some()));
+                tok.clone()
+            });
+
+            assert_eq!(calls, vec![(false, true), (true, false)]);
+        }
+
+        #[test]
+        fn errors_or_both_successful() {
+            let lexed = Lexer::<MorseToken>::from_str_input(".-");
+            let (l, value) = Lexer::errors_or(lexed, Ok::<_, Vec<AnnotatedError>>(42)).unwrap();
+
+            assert_eq!(l.0.len(), 2);
+            assert_eq!(value, 42);
+        }
+
+        #[test]
+        fn errors_or_accumulates_errors() {
+            let input = SpannedStr::input_file("_|");
+            let lexed = Lexer::<MorseToken>::from_spanned_str(input);
+
+            let parse_error = AnnotatedError::new(input.span(), "Expected a letter");
+            let parsed: Result<(), _> = Err(vec![parse_error.clone()]);
+
+            let errs = Lexer::errors_or(lexed, parsed).unwrap_err();
+
+            assert_eq!(errs.len(), 3);
+            assert_eq!(errs.last(), Some(&parse_error));
+        }
+
+        #[test]
+        fn errors_or_lexing_errors_only() {
+            let lexed = Lexer::<MorseToken>::from_str_input("|");
+            let errs = Lexer::errors_or(lexed, Ok::<_, Vec<AnnotatedError>>(())).unwrap_err();
+
+            assert_eq!(errs.len(), 1);
+        }
+
+        #[test]
+        fn lazy_lossy_recoverable_error() {
+            let input = SpannedStr::input_file("._-");
+            let mut tokens = Lexer::<MorseTokenWithEof>::lazy_lossy(input);
+
+            let first = tokens.next().unwrap();
+            assert_eq!(first.kind, MorseTokenWithEofKind::Dot(Dot));
+            assert!(tokens.errors().is_empty());
+
+            let second = tokens.next().unwrap();
+            assert_eq!(second.kind, MorseTokenWithEofKind::Dash(Dash));
+            assert_eq!(tokens.errors().len(), 1);
+
+            let third = tokens.next().unwrap();
+            assert_eq!(third.kind, MorseTokenWithEofKind::Eof);
+            assert!(tokens.next().is_none());
+
+            let errors = tokens.drain_errors().collect::<Vec<_>>();
+            assert_eq!(errors.len(), 1);
+            assert!(tokens.errors().is_empty());
+        }
+
+        #[test]
+        fn lazy_lossy_remaining() {
+            let input = SpannedStr::input_file(".-|.");
+            let mut tokens = Lexer::<MorseTokenWithEof>::lazy_lossy(input);
+
+            assert_eq!(tokens.remaining(), input);
+
+            tokens.next().unwrap();
+            tokens.next().unwrap();
+
+            let remaining = tokens.remaining();
+            assert_eq!(remaining.content(), "|.");
+            assert_eq!(remaining.span().start(), input.split_at(2).1.span().start());
+        }
+
+        #[test]
+        fn lazy_lossy_fatal_error() {
+            let input = SpannedStr::input_file(".|.");
+            let mut tokens = Lexer::<MorseTokenWithEof>::lazy_lossy(input);
+
+            assert_eq!(tokens.by_ref().count(), 1);
+            assert_eq!(tokens.errors().len(), 1);
+            assert!(tokens.next().is_none());
+        }
+
+        #[test]
+        fn unknown_token_lists_expected_terminals() {
+            let input = SpannedStr::input_file("|");
+            let errs = Lexer::<MorseToken>::from_spanned_str(input).unwrap_err();
+
+            let right = AnnotatedError::new(input.span(), "Unknown start of token: `|`")
+                .with_annotation(input.span(), "Expected one of `-`, `.`");
+
+            assert_eq!(errs, vec![right]);
+        }
+
+        #[test]
+        fn expected_one_of() {
+            assert_eq!(super::expected_one_of(&[]), "Unknown start of token");
+            assert_eq!(super::expected_one_of(&["`.`"]), "Expected `.`");
+            assert_eq!(
+                super::expected_one_of(&["`.`", "`-`"]),
+                "Expected one of `.`, `-`"
+            );
+        }
+
         #[test]
         fn error_with_recovery() {
             let input = SpannedStr::input_file("__");
@@ -421,5 +2282,247 @@ mod lexer {
             // | can not be recovered from, so there should be a single error
             assert_eq!(l.unwrap_err().len(), 1);
         }
+
+        #[test]
+        fn fatal_error_with_sync() {
+            let input = SpannedStr::input_file(".||.\n-||-");
+            let l = Lexer::<MorseToken>::from_spanned_str_with_sync(input, &['\n']);
+
+            // Lexing resumes after the newline, so both regions are reported.
+            assert_eq!(l.unwrap_err().len(), 2);
+        }
+
+        #[test]
+        fn fatal_error_without_sync_char_in_input() {
+            let input = SpannedStr::input_file(".||.-||-");
+            let l = Lexer::<MorseToken>::from_spanned_str_with_sync(input, &['\n']);
+
+            assert_eq!(l.unwrap_err().len(), 1);
+        }
+
+        #[derive(Clone, Debug, PartialEq)]
+        struct Stuck;
+
+        impl Terminal for Stuck {
+            // Reports an error but resumes at the same place.
+            fn lex(i: SpannedStr) -> Option<LexingResult<Self>> {
+                let report = AnnotatedError::new(i.span(), "Stuck");
+                Some(Err((vec![report], Some(i))))
+            }
+
+            fn specific_description(&self) -> Cow<'static, str> {
+                Cow::Borrowed("stuck")
+            }
+
+            const DESCRIPTION: &'static str = "stuck";
+        }
+
+        token! {
+            #[derive(Debug, PartialEq)]
+            StuckToken = Stuck
+        }
+
+        #[test]
+        #[cfg(debug_assertions)]
+        #[should_panic(expected = "Error recovery did not advance past offset 0")]
+        fn recovery_must_advance() {
+            let input = SpannedStr::input_file("foo");
+            let _ = Lexer::<StuckToken>::from_spanned_str(input);
+        }
+
+        #[test]
+        #[cfg(debug_assertions)]
+        #[should_panic(expected = "Error recovery did not advance past offset 0")]
+        fn lazy_recovery_must_advance() {
+            let input = SpannedStr::input_file("foo");
+            let _ = Lexer::<StuckToken>::lazy_lossy(input).next();
+        }
+
+        #[test]
+        fn lex_ext_recoverable_error() {
+            let input = SpannedStr::input_file("._-");
+            let (toks, errs) = input.lex::<MorseTokenWithEof>();
+
+            let kinds = toks.iter().map(|t| &t.kind).collect::<Vec<_>>();
+            assert_eq!(
+                kinds,
+                [
+                    &MorseTokenWithEofKind::Dot(Dot),
+                    &MorseTokenWithEofKind::Dash(Dash),
+                    &MorseTokenWithEofKind::Eof,
+                ]
+            );
+
+            assert_eq!(errs.len(), 1);
+            assert_eq!(errs[0].message(), "Expected `-`, found `_`");
+        }
+
+        #[test]
+        fn streaming_two_chunks() {
+            let mut lexer = StreamingLexer::<MorseTokenWithEof>::new();
+
+            let mut toks = lexer.feed(lexer.chunk("..."));
+            toks.extend(lexer.feed(lexer.chunk("---")));
+            toks.extend(lexer.finish());
+
+            let kinds = toks.iter().map(|t| &t.kind).collect::<Vec<_>>();
+            assert_eq!(
+                kinds,
+                [
+                    &MorseTokenWithEofKind::Dot(Dot),
+                    &MorseTokenWithEofKind::Dot(Dot),
+                    &MorseTokenWithEofKind::Dot(Dot),
+                    &MorseTokenWithEofKind::Dash(Dash),
+                    &MorseTokenWithEofKind::Dash(Dash),
+                    &MorseTokenWithEofKind::Dash(Dash),
+                    &MorseTokenWithEofKind::Eof,
+                ]
+            );
+
+            let whole = Lexer::<MorseTokenWithEof>::from_str_input("...---").unwrap();
+            assert_eq!(toks, whole.0);
+        }
+
+        #[test]
+        fn streaming_skips_rest_of_chunk_on_fatal_error() {
+            let mut lexer = StreamingLexer::<MorseToken>::new();
+
+            let first = lexer.feed(lexer.chunk(".|."));
+            let second = lexer.feed(lexer.chunk("-"));
+
+            assert_eq!(first.len(), 1);
+            assert_eq!(second.len(), 1);
+            assert_eq!(second[0].span.start().offset(), 3);
+            assert_eq!(lexer.drain_errors().count(), 1);
+        }
+
+        #[test]
+        #[should_panic(expected = "Chunk does not continue the previous input")]
+        fn streaming_rejects_non_continuation() {
+            let mut lexer = StreamingLexer::<MorseToken>::new();
+
+            lexer.feed(lexer.chunk(".."));
+            lexer.feed(SpannedStr::input_file("--"));
+        }
+
+        fn both_dashes(left: &MorseToken, right: &MorseToken) -> bool {
+            matches!(
+                (&left.kind, &right.kind),
+                (MorseTokenKind::Dash(_), MorseTokenKind::Dash(_))
+            )
+        }
+
+        #[test]
+        fn adjacent_dashes_are_rejected() {
+            let input = SpannedStr::input_file(".--");
+            let errs = Lexer::<MorseToken>::from_spanned_str(input)
+                .unwrap()
+                .require_separators(both_dashes)
+                .unwrap_err();
+
+            let dashes = input.split_at(1).1;
+            let second = dashes.split_at(1).1;
+
+            assert_eq!(errs.len(), 1);
+            assert_eq!(errs[0].span(), dashes.span());
+            assert_eq!(errs[0].message(), "Missing separator between `-` and `-`");
+            assert_eq!(
+                errs[0].annotations().next(),
+                Some((second.span(), "Expected a separator before this token"))
+            );
+        }
+
+        #[test]
+        fn separated_dashes_are_accepted() {
+            let input = SpannedStr::input_file("-.-");
+            let l = Lexer::<MorseToken>::from_spanned_str(input)
+                .unwrap()
+                .require_separators(both_dashes)
+                .unwrap();
+
+            assert_eq!(l.0.len(), 3);
+        }
+
+        #[test]
+        fn limit_exceeded() {
+            let input = SpannedStr::input_file(".-.-.");
+            let errs = Lexer::<MorseToken>::from_spanned_str_with_limit(input, 4).unwrap_err();
+
+            assert_eq!(errs.len(), 1);
+            assert_eq!(errs[0].span(), input.span());
+            assert_eq!(errs[0].message(), "Limit exceeded: more than 4 bytes");
+            assert_eq!(errs[0].meta("limit"), Some("bytes"));
+        }
+
+        #[test]
+        fn limited_max_bytes() {
+            let input = SpannedStr::input_file(".-.-.");
+            let limits = LexLimits {
+                max_bytes: Some(4),
+                ..LexLimits::default()
+            };
+
+            let errs = Lexer::<MorseToken>::from_spanned_str_limited(input, &limits).unwrap_err();
+
+            assert_eq!(errs.len(), 1);
+            assert_eq!(errs[0].span(), input.span());
+            assert_eq!(errs[0].message(), "Limit exceeded: more than 4 bytes");
+            assert_eq!(errs[0].meta("limit"), Some("bytes"));
+        }
+
+        #[test]
+        fn limited_max_tokens() {
+            let input = SpannedStr::input_file(".-.-.");
+            let limits = LexLimits {
+                max_tokens: Some(3),
+                ..LexLimits::default()
+            };
+
+            let errs = Lexer::<MorseToken>::from_spanned_str_limited(input, &limits).unwrap_err();
+
+            assert_eq!(errs.len(), 1);
+            assert_eq!(errs[0].span(), input.split_at(3).1.split_at(1).0.span());
+            assert_eq!(errs[0].message(), "Limit exceeded: more than 3 tokens");
+            assert_eq!(errs[0].meta("limit"), Some("tokens"));
+        }
+
+        #[test]
+        fn limited_max_errors() {
+            let input = SpannedStr::input_file("._._._");
+            let limits = LexLimits {
+                max_errors: Some(2),
+                ..LexLimits::default()
+            };
+
+            let errs = Lexer::<MorseToken>::from_spanned_str_limited(input, &limits).unwrap_err();
+
+            assert_eq!(errs.len(), 3);
+            assert_eq!(errs[1].message(), "Expected `-`, found `_`");
+            assert_eq!(errs[2].message(), "Limit exceeded: more than 2 errors");
+            assert_eq!(errs[2].meta("limit"), Some("errors"));
+            assert_eq!(errs[2].span(), input.split_at(5).1.span());
+        }
+
+        #[test]
+        fn limited_within_bounds() {
+            let input = SpannedStr::input_file(".-.-.");
+            let limits = LexLimits {
+                max_bytes: Some(5),
+                max_tokens: Some(5),
+                max_errors: Some(0),
+            };
+
+            let l = Lexer::<MorseToken>::from_spanned_str_limited(input, &limits).unwrap();
+
+            assert_eq!(l.0.len(), 5);
+        }
+
+        #[test]
+        fn limit_not_exceeded() {
+            let input = SpannedStr::input_file(".-.-.");
+            let l = Lexer::<MorseToken>::from_spanned_str_with_limit(input, 5).unwrap();
+
+            assert_eq!(l.0.len(), 5);
+        }
     }
 }
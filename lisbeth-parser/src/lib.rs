@@ -4,4 +4,10 @@
 
 #![deny(warnings, missing_docs)]
 
+#[doc(hidden)]
+pub mod doc_fixtures;
+pub mod error;
+pub mod intern;
 pub mod lexer;
+#[cfg(feature = "test-util")]
+pub mod test_util;
@@ -0,0 +1,153 @@
+//! Helpers for testing tokens.
+//!
+//! This module is available when the `test-util` feature is enabled. It
+//! contains a small fuzzer that checks that a [`Token`] type can lex any input
+//! without panicking and without looping forever during error recovery.
+
+use std::convert::TryFrom;
+
+use lisbeth_error::span::SpannedStr;
+
+use crate::lexer::{Lexer, Token};
+
+/// Lexes `rounds` random inputs with `Tok`, and checks that lexing terminates.
+///
+/// Each input is a random sequence of at most `max_len` bytes picked in
+/// `alphabet`, converted to a string with [`String::from_utf8_lossy`].
+/// Picking the alphabet among the characters recognized by the terminals
+/// produces inputs that mix valid and invalid tokens, which is where recovery
+/// happens. The inputs are generated from `seed`, so that a failure can be
+/// reproduced.
+///
+/// Lexing panics if a terminal reports an error but resumes lexing from the
+/// place it started at, as this would make the lexer loop forever. This check
+/// is only performed when debug assertions are enabled.
+///
+/// # Panics
+///
+/// Panics if lexing one of the inputs panics, or if `alphabet` is empty.
+///
+/// # Example
+///
+/// ```rust
+/// # use lisbeth_error::span::{Span, SpannedStr};
+/// # use lisbeth_parser::lexer::{Lexer, Terminal};
+/// # use lisbeth_parser::{doc_fixtures::Dot, token};
+/// #
+/// # token! {
+/// #     #[derive(Clone, Debug, PartialEq)]
+/// #     Token = Dot
+/// # }
+/// use lisbeth_parser::test_util::fuzz_recovery;
+///
+/// fuzz_recovery::<Token>(b".?\n\xff", 42, 100, 32);
+/// ```
+pub fn fuzz_recovery<Tok: Token>(alphabet: &[u8], seed: u64, rounds: usize, max_len: usize) {
+    assert!(!alphabet.is_empty(), "The alphabet must not be empty");
+
+    let mut rng = XorShift::new(seed);
+
+    for _ in 0..rounds {
+        let len = rng.below(max_len + 1);
+        let bytes = (0..len)
+            .map(|_| alphabet[rng.below(alphabet.len())])
+            .collect::<Vec<_>>();
+        let input = String::from_utf8_lossy(&bytes);
+
+        let _ = Lexer::<Tok>::try_from(SpannedStr::input_file(&input));
+    }
+}
+
+// A xorshift64 pseudo-random number generator. It is not suitable for
+// anything but generating test inputs.
+struct XorShift(u64);
+
+impl XorShift {
+    fn new(seed: u64) -> XorShift {
+        // Xorshift never leaves the zero state.
+        XorShift(seed.max(1))
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    // Returns a number in 0..bound.
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::borrow::Cow;
+
+    use lisbeth_error::{
+        error::AnnotatedError,
+        span::{Span, SpannedStr},
+    };
+
+    use crate::{
+        lexer::{LexingResult, Terminal},
+        token,
+    };
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Dash;
+
+    impl Terminal for Dash {
+        fn lex(i: SpannedStr) -> Option<LexingResult<Self>> {
+            if i.content().starts_with('-') {
+                let (matched, tail) = i.split_at(1);
+                return Some(Ok((Dash, matched.span(), tail)));
+            }
+
+            if i.content().starts_with('_') {
+                let (matched, tail) = i.split_at(1);
+                let report = AnnotatedError::new(matched.span(), "Expected `-`, found `_`");
+                return Some(Err((vec![report], Some(tail))));
+            }
+
+            None
+        }
+
+        fn specific_description(&self) -> Cow<'static, str> {
+            Cow::Borrowed("`-`")
+        }
+
+        const DESCRIPTION: &'static str = "`-`";
+    }
+
+    token! {
+        #[derive(Debug, PartialEq)]
+        DashToken = Dash
+    }
+
+    #[test]
+    fn fuzz_recovering_token() {
+        fuzz_recovery::<DashToken>(b"-_?\xc3\xa9", 1, 200, 16);
+    }
+
+    #[test]
+    fn xorshift_is_deterministic() {
+        let mut left = XorShift::new(7);
+        let mut right = XorShift::new(7);
+
+        let left = (0..10).map(|_| left.below(100)).collect::<Vec<_>>();
+        let right = (0..10).map(|_| right.below(100)).collect::<Vec<_>>();
+
+        assert_eq!(left, right);
+        assert!(left.iter().all(|n| *n < 100));
+    }
+
+    #[test]
+    #[should_panic(expected = "The alphabet must not be empty")]
+    fn empty_alphabet() {
+        fuzz_recovery::<DashToken>(b"", 1, 1, 1);
+    }
+}
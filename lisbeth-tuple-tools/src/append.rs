@@ -35,6 +35,10 @@ impl_tuple_append! { (A, B, C, D) + E }
 impl_tuple_append! { (A, B, C, D, E) + F }
 impl_tuple_append! { (A, B, C, D, E, F) + G }
 impl_tuple_append! { (A, B, C, D, E, F, G) + H }
+impl_tuple_append! { (A, B, C, D, E, F, G, H) + I }
+impl_tuple_append! { (A, B, C, D, E, F, G, H, I) + J }
+impl_tuple_append! { (A, B, C, D, E, F, G, H, I, J) + K }
+impl_tuple_append! { (A, B, C, D, E, F, G, H, I, J, K) + L }
 
 #[cfg(test)]
 mod tests {
@@ -62,4 +66,22 @@ mod tests {
 
         assert_eq!(t, (1, 2, 3, 4, 5, 6, 7, 8));
     }
+
+    #[test]
+    fn append_to_eight() {
+        let t = (1, 2, 3, 4, 5, 6, 7, 8).append(9);
+
+        assert_eq!(t, (1, 2, 3, 4, 5, 6, 7, 8, 9));
+    }
+
+    #[test]
+    fn append_up_to_twelve() {
+        let t: (u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8) = (1, 2, 3, 4, 5, 6, 7, 8)
+            .append(9)
+            .append(10)
+            .append(11)
+            .append(12);
+
+        assert_eq!(t, (1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12));
+    }
 }
@@ -0,0 +1,68 @@
+/// Allows to reduce a tuple whose elements all have the same type to a single
+/// value.
+///
+/// The generic type parameter `T` represents the type of every element of the
+/// tuple.
+pub trait TupleFold<T> {
+    /// The folding function.
+    ///
+    /// Elements are consumed from the first to the last.
+    fn fold<Acc, F>(self, init: Acc, f: F) -> Acc
+    where
+        F: FnMut(Acc, T) -> Acc;
+}
+
+macro_rules! impl_tuple_fold {
+    ( @replace $_elem:ident with $ty:ident ) => {
+        $ty
+    };
+
+    (
+        $( $elem:ident ),* $(,)?
+    ) => {
+        impl<T> TupleFold<T> for ( $( impl_tuple_fold!(@replace $elem with T), )* ) {
+            fn fold<Acc, F>(self, init: Acc, mut func: F) -> Acc
+            where
+                F: FnMut(Acc, T) -> Acc,
+            {
+                let ( $( $elem, )* ) = self;
+                let acc = init;
+                $( let acc = func(acc, $elem); )*
+                acc
+            }
+        }
+    };
+}
+
+impl_tuple_fold! { a }
+impl_tuple_fold! { a, b }
+impl_tuple_fold! { a, b, c }
+impl_tuple_fold! { a, b, c, d }
+impl_tuple_fold! { a, b, c, d, e }
+impl_tuple_fold! { a, b, c, d, e, f }
+impl_tuple_fold! { a, b, c, d, e, f, g }
+impl_tuple_fold! { a, b, c, d, e, f, g, h }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fold_sum() {
+        assert_eq!((1, 2, 3).fold(0, |acc, x| acc + x), 6);
+    }
+
+    #[test]
+    fn fold_concat_in_order() {
+        let mut buf = [0u8; 16];
+        let mut len = 0;
+
+        ("foo", "bar", "baz").fold(&mut buf, |buf, s| {
+            buf[len..len + s.len()].copy_from_slice(s.as_bytes());
+            len += s.len();
+            buf
+        });
+
+        assert_eq!(&buf[..len], b"foobarbaz");
+    }
+}
@@ -1,7 +1,11 @@
 //! Some tools to manipulate tuples.
 //!
+//! This crate does not depend on the standard library, and can be used in
+//! `no_std` environments.
+//!
 //! Most traits in this crate are implemented for tuples with an arity inferior
-//! or equal to eight.
+//! or equal to eight. [`TupleAppend`] is implemented for tuples whose arity is
+//! inferior or equal to eleven, so that it can produce tuples of arity twelve.
 //!
 //! # `TupleAppend`
 //!
@@ -20,6 +24,62 @@
 //! assert_eq!(tup, ('l', 42, 's'));
 //! ```
 //!
+//! # `TupleSplitFirst` and `TupleSplitLast`
+//!
+//! [`TupleSplitFirst`] separates the first element of a tuple from the
+//! remaining ones, while [`TupleSplitLast`] separates the last element of a
+//! tuple from the preceding ones.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use lisbeth_tuple_tools::{TupleSplitFirst, TupleSplitLast};
+//!
+//! assert_eq!(('a', 1, true).split_first(), ('a', (1, true)));
+//! assert_eq!(('a', 1, true).split_last(), (('a', 1), true));
+//! ```
+//!
+//! # `TupleHead` and `TupleTail`
+//!
+//! [`TupleHead`] returns the first element of a tuple, while [`TupleTail`]
+//! returns the remaining ones.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use lisbeth_tuple_tools::{TupleHead, TupleTail};
+//!
+//! assert_eq!(('a', 1, true).head(), 'a');
+//! assert_eq!(('a', 1, true).tail(), (1, true));
+//! ```
+//!
+//! # `TupleZip`
+//!
+//! [`TupleZip`] combines two tuples of the same arity into a tuple of pairs.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use lisbeth_tuple_tools::TupleZip;
+//!
+//! let tup = ('a', 1).zip((true, "foo"));
+//!
+//! assert_eq!(tup, (('a', true), (1, "foo")));
+//! ```
+//!
+//! # `TupleFold`
+//!
+//! When every element of a tuple has the same type, [`TupleFold`] allows to
+//! reduce it to a single value, from the first element to the last.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use lisbeth_tuple_tools::TupleFold;
+//!
+//! assert_eq!((1, 2, 3).fold(0, |acc, x| acc + x), 6);
+//! ```
+//!
 //! # `TupleMap*`
 //!
 //! This crate contains [`TupleMap1`], [`TupleMap2`], and so on. These traits
@@ -39,11 +99,50 @@
 //!
 //! assert_eq!(t, (1, 0, "foo"));
 //! ```
+//!
+//! # `TupleMapAll`
+//!
+//! When every element of a tuple has the same type, [`TupleMapAll`] allows to
+//! map all of them with a single function, from the first to the last.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use lisbeth_tuple_tools::TupleMapAll;
+//!
+//! let t = (1, 2, 3).map_all(|x| x * 2);
+//!
+//! assert_eq!(t, (2, 4, 6));
+//! ```
+//!
+//! # `TupleMapResult*`
+//!
+//! [`TupleMapResult1`], [`TupleMapResult2`], and so on, work the same way as
+//! the `TupleMap*` traits, but take a function that may fail. The error is
+//! returned as soon as it is encountered.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use lisbeth_tuple_tools::TupleMapResult2;
+//!
+//! let t = ('a', "42", "foo");
+//! let t = t.try_map_2(str::parse::<u32>);
+//!
+//! assert_eq!(t, Ok(('a', 42, "foo")));
+//! ```
 
+#![no_std]
 #![deny(warnings)]
 
 mod append;
+mod fold;
 mod map;
+mod split;
+mod zip;
 
 pub use append::TupleAppend;
+pub use fold::TupleFold;
 pub use map::*;
+pub use split::{TupleHead, TupleSplitFirst, TupleSplitLast, TupleTail};
+pub use zip::TupleZip;
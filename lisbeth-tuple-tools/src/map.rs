@@ -1,7 +1,8 @@
 macro_rules! declare_map_n {
     (
         #[doc = $ordinal:literal]
-        $name:ident::$fn_name:ident
+        $name:ident::$fn_name:ident,
+        $try_name:ident::$try_fn_name:ident $(,)?
     ) => {
         #[doc = "Allows to map the "]
         #[doc = $ordinal]
@@ -12,45 +13,69 @@ macro_rules! declare_map_n {
             where
                 Func: FnOnce(T) -> U;
         }
+
+        #[doc = "Allows to map the "]
+        #[doc = $ordinal]
+        #[doc = " element of a tuple to another type with a function that may"]
+        #[doc = " fail."]
+        #[doc = ""]
+        #[doc = "If the function returns an error, then the error is returned"]
+        #[doc = " and the other elements of the tuple are dropped."]
+        pub trait $try_name<T, U> {
+            type Output;
+            fn $try_fn_name<Func, Error>(self, f: Func) -> Result<Self::Output, Error>
+            where
+                Func: FnOnce(T) -> Result<U, Error>;
+        }
     };
 }
 
 declare_map_n! {
     /// first
-    TupleMap1::map_1
+    TupleMap1::map_1,
+    TupleMapResult1::try_map_1,
 }
 declare_map_n! {
     /// second
-    TupleMap2::map_2
+    TupleMap2::map_2,
+    TupleMapResult2::try_map_2,
 }
 declare_map_n! {
     /// third
-    TupleMap3::map_3
+    TupleMap3::map_3,
+    TupleMapResult3::try_map_3,
 }
 declare_map_n! {
     /// fourth
-    TupleMap4::map_4
+    TupleMap4::map_4,
+    TupleMapResult4::try_map_4,
 }
 declare_map_n! {
     /// difth
-    TupleMap5::map_5
+    TupleMap5::map_5,
+    TupleMapResult5::try_map_5,
 }
 declare_map_n! {
     /// sixth
-    TupleMap6::map_6
+    TupleMap6::map_6,
+    TupleMapResult6::try_map_6,
 }
 declare_map_n! {
     /// seventh
-    TupleMap7::map_7
+    TupleMap7::map_7,
+    TupleMapResult7::try_map_7,
 }
 declare_map_n! {
     /// eighth
-    TupleMap8::map_8
+    TupleMap8::map_8,
+    TupleMapResult8::try_map_8,
 }
 
 macro_rules! impl_map_n {
     (
-        $trait:ident::$fn:ident for ( $( $before:ident, )* _ $( , $after:ident )* $(,)? ) $(,)?
+        $trait:ident::$fn:ident,
+        $try_trait:ident::$try_fn:ident
+        for ( $( $before:ident, )* _ $( , $after:ident )* $(,)? ) $(,)?
     ) => {
         impl<$( $before, )* $( $after, )* T, U> $trait<T, U> for ( $( $before, )* T, $( $after, )* ) {
             type Output = ( $( $before, )* U, $( $after, )* );
@@ -65,49 +90,141 @@ macro_rules! impl_map_n {
                 ( $( $before, )* u, $( $after, )* )
             }
         }
+
+        impl<$( $before, )* $( $after, )* T, U> $try_trait<T, U> for ( $( $before, )* T, $( $after, )* ) {
+            type Output = ( $( $before, )* U, $( $after, )* );
+
+            #[allow(non_snake_case)]
+            fn $try_fn<Func, Error>(self, f: Func) -> Result<Self::Output, Error>
+            where
+                Func: FnOnce(T) -> Result<U, Error>,
+            {
+                let ( $( $before, )* t, $( $after, )* ) = self;
+                let u = f(t)?;
+                Ok(( $( $before, )* u, $( $after, )* ))
+            }
+        }
     };
 }
 
-impl_map_n! { TupleMap1::map_1 for (_,) }
-impl_map_n! { TupleMap1::map_1 for (_, B) }
-impl_map_n! { TupleMap1::map_1 for (_, B, C) }
-impl_map_n! { TupleMap1::map_1 for (_, B, C, D) }
-impl_map_n! { TupleMap1::map_1 for (_, B, C, D, E) }
-impl_map_n! { TupleMap1::map_1 for (_, B, C, D, E, F) }
-impl_map_n! { TupleMap1::map_1 for (_, B, C, D, E, F, G) }
-impl_map_n! { TupleMap1::map_1 for (_, B, C, D, E, F, G, H) }
-
-impl_map_n! { TupleMap2::map_2 for (A, _) }
-impl_map_n! { TupleMap2::map_2 for (A, _, C) }
-impl_map_n! { TupleMap2::map_2 for (A, _, C, D) }
-impl_map_n! { TupleMap2::map_2 for (A, _, C, D, E) }
-impl_map_n! { TupleMap2::map_2 for (A, _, C, D, E, F) }
-impl_map_n! { TupleMap2::map_2 for (A, _, C, D, E, F, G) }
-impl_map_n! { TupleMap2::map_2 for (A, _, C, D, E, F, G, H) }
-
-impl_map_n! { TupleMap3::map_3 for (A, B, _) }
-impl_map_n! { TupleMap3::map_3 for (A, B, _, D) }
-impl_map_n! { TupleMap3::map_3 for (A, B, _, D, E) }
-impl_map_n! { TupleMap3::map_3 for (A, B, _, D, E, F) }
-impl_map_n! { TupleMap3::map_3 for (A, B, _, D, E, F, G) }
-impl_map_n! { TupleMap3::map_3 for (A, B, _, D, E, F, G, H) }
-
-impl_map_n! { TupleMap4::map_4 for (A, B, C, _) }
-impl_map_n! { TupleMap4::map_4 for (A, B, C, _, E) }
-impl_map_n! { TupleMap4::map_4 for (A, B, C, _, E, F) }
-impl_map_n! { TupleMap4::map_4 for (A, B, C, _, E, F, G) }
-impl_map_n! { TupleMap4::map_4 for (A, B, C, _, E, F, G, H) }
-
-impl_map_n! { TupleMap5::map_5 for (A, B, C, D, _) }
-impl_map_n! { TupleMap5::map_5 for (A, B, C, D, _, F) }
-impl_map_n! { TupleMap5::map_5 for (A, B, C, D, _, F, G) }
-impl_map_n! { TupleMap5::map_5 for (A, B, C, D, _, F, G, H) }
-
-impl_map_n! { TupleMap6::map_6 for (A, B, C, D, E, _) }
-impl_map_n! { TupleMap6::map_6 for (A, B, C, D, E, _, G) }
-impl_map_n! { TupleMap6::map_6 for (A, B, C, D, E, _, G, H) }
-
-impl_map_n! { TupleMap7::map_7 for (A, B, C, D, E, F, _) }
-impl_map_n! { TupleMap7::map_7 for (A, B, C, D, E, F, _, H) }
-
-impl_map_n! { TupleMap8::map_8 for (A, B, C, D, E, F, G, _) }
+impl_map_n! { TupleMap1::map_1, TupleMapResult1::try_map_1 for (_,) }
+impl_map_n! { TupleMap1::map_1, TupleMapResult1::try_map_1 for (_, B) }
+impl_map_n! { TupleMap1::map_1, TupleMapResult1::try_map_1 for (_, B, C) }
+impl_map_n! { TupleMap1::map_1, TupleMapResult1::try_map_1 for (_, B, C, D) }
+impl_map_n! { TupleMap1::map_1, TupleMapResult1::try_map_1 for (_, B, C, D, E) }
+impl_map_n! { TupleMap1::map_1, TupleMapResult1::try_map_1 for (_, B, C, D, E, F) }
+impl_map_n! { TupleMap1::map_1, TupleMapResult1::try_map_1 for (_, B, C, D, E, F, G) }
+impl_map_n! { TupleMap1::map_1, TupleMapResult1::try_map_1 for (_, B, C, D, E, F, G, H) }
+
+impl_map_n! { TupleMap2::map_2, TupleMapResult2::try_map_2 for (A, _) }
+impl_map_n! { TupleMap2::map_2, TupleMapResult2::try_map_2 for (A, _, C) }
+impl_map_n! { TupleMap2::map_2, TupleMapResult2::try_map_2 for (A, _, C, D) }
+impl_map_n! { TupleMap2::map_2, TupleMapResult2::try_map_2 for (A, _, C, D, E) }
+impl_map_n! { TupleMap2::map_2, TupleMapResult2::try_map_2 for (A, _, C, D, E, F) }
+impl_map_n! { TupleMap2::map_2, TupleMapResult2::try_map_2 for (A, _, C, D, E, F, G) }
+impl_map_n! { TupleMap2::map_2, TupleMapResult2::try_map_2 for (A, _, C, D, E, F, G, H) }
+
+impl_map_n! { TupleMap3::map_3, TupleMapResult3::try_map_3 for (A, B, _) }
+impl_map_n! { TupleMap3::map_3, TupleMapResult3::try_map_3 for (A, B, _, D) }
+impl_map_n! { TupleMap3::map_3, TupleMapResult3::try_map_3 for (A, B, _, D, E) }
+impl_map_n! { TupleMap3::map_3, TupleMapResult3::try_map_3 for (A, B, _, D, E, F) }
+impl_map_n! { TupleMap3::map_3, TupleMapResult3::try_map_3 for (A, B, _, D, E, F, G) }
+impl_map_n! { TupleMap3::map_3, TupleMapResult3::try_map_3 for (A, B, _, D, E, F, G, H) }
+
+impl_map_n! { TupleMap4::map_4, TupleMapResult4::try_map_4 for (A, B, C, _) }
+impl_map_n! { TupleMap4::map_4, TupleMapResult4::try_map_4 for (A, B, C, _, E) }
+impl_map_n! { TupleMap4::map_4, TupleMapResult4::try_map_4 for (A, B, C, _, E, F) }
+impl_map_n! { TupleMap4::map_4, TupleMapResult4::try_map_4 for (A, B, C, _, E, F, G) }
+impl_map_n! { TupleMap4::map_4, TupleMapResult4::try_map_4 for (A, B, C, _, E, F, G, H) }
+
+impl_map_n! { TupleMap5::map_5, TupleMapResult5::try_map_5 for (A, B, C, D, _) }
+impl_map_n! { TupleMap5::map_5, TupleMapResult5::try_map_5 for (A, B, C, D, _, F) }
+impl_map_n! { TupleMap5::map_5, TupleMapResult5::try_map_5 for (A, B, C, D, _, F, G) }
+impl_map_n! { TupleMap5::map_5, TupleMapResult5::try_map_5 for (A, B, C, D, _, F, G, H) }
+
+impl_map_n! { TupleMap6::map_6, TupleMapResult6::try_map_6 for (A, B, C, D, E, _) }
+impl_map_n! { TupleMap6::map_6, TupleMapResult6::try_map_6 for (A, B, C, D, E, _, G) }
+impl_map_n! { TupleMap6::map_6, TupleMapResult6::try_map_6 for (A, B, C, D, E, _, G, H) }
+
+impl_map_n! { TupleMap7::map_7, TupleMapResult7::try_map_7 for (A, B, C, D, E, F, _) }
+impl_map_n! { TupleMap7::map_7, TupleMapResult7::try_map_7 for (A, B, C, D, E, F, _, H) }
+
+impl_map_n! { TupleMap8::map_8, TupleMapResult8::try_map_8 for (A, B, C, D, E, F, G, _) }
+
+/// Allows to map every element of a tuple whose elements all have the same
+/// type.
+pub trait TupleMapAll<T, U> {
+    type Output;
+    fn map_all<Func>(self, f: Func) -> Self::Output
+    where
+        Func: FnMut(T) -> U;
+}
+
+macro_rules! impl_map_all {
+    ( @replace $_elem:ident with $ty:ident ) => {
+        $ty
+    };
+
+    (
+        $( $elem:ident ),* $(,)?
+    ) => {
+        impl<T, U> TupleMapAll<T, U> for ( $( impl_map_all!(@replace $elem with T), )* ) {
+            type Output = ( $( impl_map_all!(@replace $elem with U), )* );
+
+            fn map_all<Func>(self, mut func: Func) -> Self::Output
+            where
+                Func: FnMut(T) -> U,
+            {
+                let ( $( $elem, )* ) = self;
+                ( $( func($elem), )* )
+            }
+        }
+    };
+}
+
+impl_map_all! { a }
+impl_map_all! { a, b }
+impl_map_all! { a, b, c }
+impl_map_all! { a, b, c, d }
+impl_map_all! { a, b, c, d, e }
+impl_map_all! { a, b, c, d, e, f }
+impl_map_all! { a, b, c, d, e, f, g }
+impl_map_all! { a, b, c, d, e, f, g, h }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn try_map_2_ok() {
+        let t: Result<_, core::num::ParseIntError> = ('a', "42", true).try_map_2(str::parse::<u32>);
+
+        assert_eq!(t, Ok(('a', 42, true)));
+    }
+
+    #[test]
+    fn try_map_2_err() {
+        let t = ('a', "foo", true).try_map_2(str::parse::<u32>);
+
+        assert!(t.is_err());
+    }
+
+    #[test]
+    fn map_all_double() {
+        let t: (u8, u8, u8) = (1, 2, 3);
+        let t = t.map_all(|x| x * 2);
+
+        assert_eq!(t, (2, 4, 6));
+    }
+
+    #[test]
+    fn map_all_in_order() {
+        let mut calls = 0;
+        let t = ('a', 'b', 'c').map_all(|c| {
+            calls += 1;
+            (calls, c)
+        });
+
+        assert_eq!(t, ((1, 'a'), (2, 'b'), (3, 'c')));
+    }
+}
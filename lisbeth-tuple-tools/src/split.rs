@@ -0,0 +1,160 @@
+/// Allows to split a tuple into its first element and the remaining elements.
+pub trait TupleSplitFirst {
+    /// The type of the first element.
+    type First;
+    /// The type of the remaining elements.
+    type Rest;
+
+    /// The splitting function.
+    fn split_first(self) -> (Self::First, Self::Rest);
+}
+
+/// Allows to split a tuple into its last element and the preceding elements.
+pub trait TupleSplitLast {
+    /// The type of the preceding elements.
+    type Init;
+    /// The type of the last element.
+    type Last;
+
+    /// The splitting function.
+    fn split_last(self) -> (Self::Init, Self::Last);
+}
+
+/// Allows to get the first element of a tuple.
+///
+/// This trait is implemented for every tuple that implements
+/// [`TupleSplitFirst`].
+pub trait TupleHead {
+    /// The type of the first element.
+    type Head;
+
+    /// Returns the first element.
+    fn head(self) -> Self::Head;
+}
+
+/// Allows to get every element of a tuple except the first one.
+///
+/// This trait is implemented for every tuple that implements
+/// [`TupleSplitFirst`].
+pub trait TupleTail {
+    /// The type of the remaining elements.
+    type Tail;
+
+    /// Returns the remaining elements.
+    fn tail(self) -> Self::Tail;
+}
+
+impl<T: TupleSplitFirst> TupleHead for T {
+    type Head = T::First;
+
+    fn head(self) -> Self::Head {
+        self.split_first().0
+    }
+}
+
+impl<T: TupleSplitFirst> TupleTail for T {
+    type Tail = T::Rest;
+
+    fn tail(self) -> Self::Tail {
+        self.split_first().1
+    }
+}
+
+macro_rules! impl_tuple_split_first {
+    (
+        $first:ident ; $( $rest:ident ),* $(,)?
+    ) => {
+        impl<$first, $( $rest, )*> TupleSplitFirst for ( $first, $( $rest, )* ) {
+            type First = $first;
+            type Rest = ( $( $rest, )* );
+
+            #[allow(non_snake_case)]
+            fn split_first(self) -> (Self::First, Self::Rest) {
+                let ( $first, $( $rest, )* ) = self;
+
+                ( $first, ( $( $rest, )* ) )
+            }
+        }
+    };
+}
+
+macro_rules! impl_tuple_split_last {
+    (
+        $( $init:ident ),* ; $last:ident $(,)?
+    ) => {
+        impl<$( $init, )* $last> TupleSplitLast for ( $( $init, )* $last, ) {
+            type Init = ( $( $init, )* );
+            type Last = $last;
+
+            #[allow(non_snake_case)]
+            fn split_last(self) -> (Self::Init, Self::Last) {
+                let ( $( $init, )* $last, ) = self;
+
+                ( ( $( $init, )* ), $last )
+            }
+        }
+    };
+}
+
+impl_tuple_split_first! { A; }
+impl_tuple_split_first! { A; B }
+impl_tuple_split_first! { A; B, C }
+impl_tuple_split_first! { A; B, C, D }
+impl_tuple_split_first! { A; B, C, D, E }
+impl_tuple_split_first! { A; B, C, D, E, F }
+impl_tuple_split_first! { A; B, C, D, E, F, G }
+impl_tuple_split_first! { A; B, C, D, E, F, G, H }
+
+impl_tuple_split_last! { ; A }
+impl_tuple_split_last! { A; B }
+impl_tuple_split_last! { A, B; C }
+impl_tuple_split_last! { A, B, C; D }
+impl_tuple_split_last! { A, B, C, D; E }
+impl_tuple_split_last! { A, B, C, D, E; F }
+impl_tuple_split_last! { A, B, C, D, E, F; G }
+impl_tuple_split_last! { A, B, C, D, E, F, G; H }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn head_and_tail_single() {
+        assert_eq!(('a',).head(), 'a');
+        assert_eq!(('a',).tail(), ());
+    }
+
+    #[test]
+    fn head_and_tail_three() {
+        assert_eq!(('a', 1, true).head(), 'a');
+        assert_eq!(('a', 1, true).tail(), (1, true));
+    }
+
+    #[test]
+    fn split_first_single() {
+        let t = ('a',).split_first();
+
+        assert_eq!(t, ('a', ()));
+    }
+
+    #[test]
+    fn split_first_three() {
+        let t = ('a', 1, true).split_first();
+
+        assert_eq!(t, ('a', (1, true)));
+    }
+
+    #[test]
+    fn split_last_single() {
+        let t = ('a',).split_last();
+
+        assert_eq!(t, ((), 'a'));
+    }
+
+    #[test]
+    fn split_last_three() {
+        let t = ('a', 1, true).split_last();
+
+        assert_eq!(t, (('a', 1), true));
+    }
+}
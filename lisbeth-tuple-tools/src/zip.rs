@@ -0,0 +1,56 @@
+/// Allows to combine two tuples of the same arity element-wise.
+///
+/// The generic type parameter `Rhs` represents the tuple to be combined with.
+pub trait TupleZip<Rhs> {
+    /// The type that is returned.
+    type Zipped;
+
+    /// The zipping function.
+    fn zip(self, rhs: Rhs) -> Self::Zipped;
+}
+
+macro_rules! impl_tuple_zip {
+    (
+        $( $left:ident : $right:ident ),* $(,)?
+    ) => {
+        impl<$( $left, )* $( $right, )*> TupleZip<( $( $right, )* )> for ( $( $left, )* ) {
+            type Zipped = ( $( ($left, $right), )* );
+
+            #[allow(non_snake_case)]
+            fn zip(self, rhs: ( $( $right, )* )) -> Self::Zipped {
+                let ( $( $left, )* ) = self;
+                let ( $( $right, )* ) = rhs;
+
+                ( $( ($left, $right), )* )
+            }
+        }
+    };
+}
+
+impl_tuple_zip! { A1: A2 }
+impl_tuple_zip! { A1: A2, B1: B2 }
+impl_tuple_zip! { A1: A2, B1: B2, C1: C2 }
+impl_tuple_zip! { A1: A2, B1: B2, C1: C2, D1: D2 }
+impl_tuple_zip! { A1: A2, B1: B2, C1: C2, D1: D2, E1: E2 }
+impl_tuple_zip! { A1: A2, B1: B2, C1: C2, D1: D2, E1: E2, F1: F2 }
+impl_tuple_zip! { A1: A2, B1: B2, C1: C2, D1: D2, E1: E2, F1: F2, G1: G2 }
+impl_tuple_zip! { A1: A2, B1: B2, C1: C2, D1: D2, E1: E2, F1: F2, G1: G2, H1: H2 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zip_two() {
+        let t = ('a', 1).zip((true, "foo"));
+
+        assert_eq!(t, (('a', true), (1, "foo")));
+    }
+
+    #[test]
+    fn zip_four() {
+        let t = (1, 2, 3, 4).zip(('a', 'b', 'c', 'd'));
+
+        assert_eq!(t, ((1, 'a'), (2, 'b'), (3, 'c'), (4, 'd')));
+    }
+}
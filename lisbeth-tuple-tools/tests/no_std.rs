@@ -0,0 +1,13 @@
+// Ensures that the crate can be used from a crate that does not depend on the
+// standard library.
+
+#![no_std]
+
+use lisbeth_tuple_tools::{TupleAppend, TupleMap1};
+
+#[test]
+fn append_and_map() {
+    let t = ('a', 1).append(true).map_1(char::len_utf8);
+
+    assert_eq!(t, (1, 1, true));
+}